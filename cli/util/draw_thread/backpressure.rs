@@ -0,0 +1,73 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+//! Estimates how often lines are printed above the drawn region, which
+//! clears the region and draws it again each time, so that the draw
+//! thread can back off while a lot of output is being printed.
+
+use std::time::Duration;
+use std::time::Instant;
+
+/// How quickly the estimated rate decays, in seconds.
+const TIME_CONSTANT_SECS: f64 = 1.0;
+/// The number of prints per second above which the draw thread backs off.
+const BACKOFF_RATE: f64 = 20.0;
+/// How long the draw thread waits between frames while backing off.
+pub const BACKOFF_INTERVAL: Duration = Duration::from_millis(500);
+
+/// An exponentially decaying estimate of the number of prints per second.
+#[derive(Debug, Default)]
+pub struct PrintRate {
+  rate: f64,
+  updated_at: Option<Instant>,
+}
+
+impl PrintRate {
+  pub fn record(&mut self, now: Instant) {
+    self.rate = self.rate_at(now) + 1.0 / TIME_CONSTANT_SECS;
+    self.updated_at = Some(now);
+  }
+
+  pub fn rate_at(&self, now: Instant) -> f64 {
+    match self.updated_at {
+      Some(updated_at) => {
+        let elapsed = now.saturating_duration_since(updated_at);
+        self.rate * (-elapsed.as_secs_f64() / TIME_CONSTANT_SECS).exp()
+      }
+      None => 0.0,
+    }
+  }
+
+  /// Gets whether lines are being printed often enough that drawing
+  /// the region between them would thrash the terminal.
+  pub fn is_high(&self, now: Instant) -> bool {
+    self.rate_at(now) > BACKOFF_RATE
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn estimates_decaying_rate() {
+    let start = Instant::now();
+    let mut rate = PrintRate::default();
+    assert_eq!(rate.rate_at(start), 0.0);
+    assert!(!rate.is_high(start));
+    for i in 0..30 {
+      rate.record(start + Duration::from_millis(i));
+    }
+    let now = start + Duration::from_millis(30);
+    assert!(rate.rate_at(now) > 29.0);
+    assert!(rate.is_high(now));
+
+    // a steady trickle stays low
+    let now = start + Duration::from_secs(2);
+    assert!(!rate.is_high(now));
+    let mut rate = PrintRate::default();
+    for i in 0..100 {
+      rate.record(start + Duration::from_millis(i * 200));
+    }
+    assert!(!rate.is_high(start + Duration::from_secs(20)));
+  }
+}
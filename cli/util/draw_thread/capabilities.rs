@@ -0,0 +1,575 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::time::Duration;
+
+use console_static_text::ansi::tokenize;
+
+/// The OSC 11 sequence that asks the terminal for its background color.
+#[cfg(unix)]
+const BACKGROUND_QUERY: &str = "\x1b]11;?\x07";
+/// How long to wait for the terminal to answer the background query,
+/// since terminals that don't support it never answer.
+const BACKGROUND_QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// The number of colors the terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+  /// The 16 basic colors.
+  Ansi16,
+  /// The 256 colors of the xterm palette.
+  Ansi256,
+  /// 24-bit colors.
+  TrueColor,
+}
+
+/// The features the terminal is detected to support, which renderers can
+/// consult to pick glyphs and color depth. Each field is a heuristic
+/// based on the environment, so it may be wrong for unusual terminals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalCaps {
+  /// 24-bit colors, which terminals advertise by setting `COLORTERM` to
+  /// `truecolor` or `24bit`.
+  pub truecolor: bool,
+  /// The number of colors, which is `ColorDepth::TrueColor` along with
+  /// `truecolor` or in Windows Terminal, `ColorDepth::Ansi256` when
+  /// `TERM` contains `256color`, and `ColorDepth::Ansi16` when `TERM` is
+  /// a terminal known to only display 16 colors, such as the Linux
+  /// console. Otherwise it's `None`, since it's unknown, such as when
+  /// `TERM` isn't set on Windows, and the colors aren't downgraded.
+  pub color_depth: Option<ColorDepth>,
+  /// Unicode glyphs, which on unix is based on the encoding of the first
+  /// non-empty of `LC_ALL`, `LC_CTYPE`, and `LANG` being UTF-8. The other
+  /// platforms are assumed to support unicode.
+  pub unicode: bool,
+  /// OSC 8 hyperlinks, which is based on `TERM_PROGRAM`, `TERM`, and
+  /// variables set by terminals known to support them, such as
+  /// `WT_SESSION` for Windows Terminal and `VTE_VERSION` for VTE based
+  /// terminals.
+  pub hyperlinks: bool,
+  /// Ansi escape sequences, which is based on whether virtual terminal
+  /// processing is enabled for stderr's console on Windows. The other
+  /// platforms always process them.
+  pub vt_processing: bool,
+  /// The synchronized output mode (DEC 2026), which draws the output
+  /// between its begin and end sequences at once. This is based on
+  /// `TERM_PROGRAM`, `TERM`, and `WT_SESSION` for the terminals known to
+  /// support it.
+  pub synchronized_output: bool,
+}
+
+impl TerminalCaps {
+  /// Detects the features of the terminal from the environment.
+  pub fn detect() -> Self {
+    Self::detect_with(|name| std::env::var(name).ok(), vt_processing())
+  }
+
+  fn detect_with(
+    env_var: impl Fn(&str) -> Option<String>,
+    vt_processing: bool,
+  ) -> Self {
+    let env_var = |name: &str| env_var(name).filter(|value| !value.is_empty());
+    let truecolor = env_var("COLORTERM")
+      .is_some_and(|value| value == "truecolor" || value == "24bit");
+    let unicode = if cfg!(unix) {
+      let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(&env_var);
+      locale.is_some_and(|locale| {
+        let locale = locale.to_ascii_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+      })
+    } else {
+      true
+    };
+    let term = env_var("TERM").unwrap_or_default();
+    let color_depth = if truecolor || env_var("WT_SESSION").is_some() {
+      Some(ColorDepth::TrueColor)
+    } else if term.contains("256color") {
+      Some(ColorDepth::Ansi256)
+    } else if matches!(
+      term.as_str(),
+      "linux" | "vt100" | "vt220" | "ansi" | "cons25" | "xterm-16color"
+    ) {
+      Some(ColorDepth::Ansi16)
+    } else {
+      None
+    };
+    let hyperlinks = term != "dumb"
+      && (env_var("TERM_PROGRAM").is_some_and(|program| {
+        matches!(
+          program.as_str(),
+          "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+        )
+      }) || ["kitty", "alacritty", "foot", "wezterm"]
+        .iter()
+        .any(|name| term.contains(name))
+        || env_var("WT_SESSION").is_some()
+        || env_var("VTE_VERSION")
+          .and_then(|version| version.parse::<u32>().ok())
+          .is_some_and(|version| version >= 5000));
+    let synchronized_output = term != "dumb"
+      && (env_var("TERM_PROGRAM").is_some_and(|program| {
+        matches!(program.as_str(), "iTerm.app" | "WezTerm" | "ghostty")
+      }) || ["kitty", "alacritty", "foot", "wezterm", "contour"]
+        .iter()
+        .any(|name| term.contains(name))
+        || env_var("WT_SESSION").is_some());
+    Self {
+      truecolor,
+      color_depth,
+      unicode,
+      hyperlinks,
+      vt_processing,
+      synchronized_output,
+    }
+  }
+}
+
+/// Whether the terminal's background is light or dark, which themes can
+/// use to pick colors that contrast with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+  Light,
+  Dark,
+}
+
+/// Queries the terminal for its background color, returning `None` when
+/// stdin or stderr isn't a terminal or the terminal doesn't answer in
+/// time. Prefer `DrawThread::detect_background`, which caches the result.
+pub fn detect_background() -> Option<Background> {
+  let response = query_background_response(BACKGROUND_QUERY_TIMEOUT)?;
+  parse_background_response(&response)
+}
+
+/// Gets whether the response to the background query is complete, which
+/// ends with BEL or ST.
+#[cfg(any(unix, test))]
+fn is_background_response_complete(response: &[u8]) -> bool {
+  response.ends_with(b"\x07") || response.ends_with(b"\x1b\\")
+}
+
+/// Parses the terminal's answer to the background query, such as
+/// `"\x1b]11;rgb:ffff/ffff/dddd\x07"`, and classifies the color by its
+/// relative luminance. Each channel has one to four hex digits.
+fn parse_background_response(response: &[u8]) -> Option<Background> {
+  fn parse_channel(hex: &str) -> Option<f64> {
+    if hex.is_empty() || hex.len() > 4 {
+      return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let max = (1u32 << (hex.len() * 4)) - 1;
+    Some(value as f64 / max as f64)
+  }
+
+  let text = std::str::from_utf8(response).ok()?;
+  let color = &text[text.find("\x1b]11;")? + 5..];
+  let color = color
+    .strip_prefix("rgb:")
+    .or_else(|| color.strip_prefix("rgba:"))?;
+  let color = &color[..color.find(['\x07', '\x1b']).unwrap_or(color.len())];
+  // the alpha channel of "rgba" is ignored
+  let mut channels = color.split('/').map(parse_channel);
+  let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+  let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+  Some(if luminance > 0.5 {
+    Background::Light
+  } else {
+    Background::Dark
+  })
+}
+
+/// Writes the background query to stderr and reads the answer from
+/// stdin with its echo and line buffering disabled until the answer is
+/// complete or the timeout elapses.
+#[cfg(unix)]
+fn query_background_response(timeout: Duration) -> Option<Vec<u8>> {
+  use std::io::IsTerminal;
+  use std::io::Write;
+  use std::time::Instant;
+
+  if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+    return None;
+  }
+  // SAFETY: the termios is plain data that's filled in by tcgetattr
+  let mut original: libc::termios = unsafe { std::mem::zeroed() };
+  // SAFETY: stdin is a terminal and the termios is a valid pointer
+  if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+    return None;
+  }
+  let mut raw = original;
+  raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+  raw.c_cc[libc::VMIN] = 0;
+  raw.c_cc[libc::VTIME] = 0;
+  // SAFETY: stdin is a terminal and the termios is a valid pointer
+  if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+    return None;
+  }
+
+  let read_response = || {
+    let mut stderr = std::io::stderr();
+    stderr.write_all(BACKGROUND_QUERY.as_bytes()).ok()?;
+    stderr.flush().ok()?;
+    let deadline = Instant::now() + timeout;
+    let mut response = Vec::new();
+    while !is_background_response_complete(&response) {
+      let remaining = deadline.saturating_duration_since(Instant::now());
+      // the answer is short, so anything longer isn't the answer
+      if remaining.is_zero() || response.len() > 64 {
+        return None;
+      }
+      let mut poll_fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+      };
+      let timeout_ms = remaining.as_millis().max(1) as libc::c_int;
+      // SAFETY: the pollfd is a valid pointer to a single pollfd
+      if unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) } <= 0 {
+        // timed out or interrupted, so check the deadline again
+        continue;
+      }
+      let mut buffer = [0u8; 32];
+      // SAFETY: the buffer is valid for writes of its length
+      let read_count = unsafe {
+        libc::read(
+          libc::STDIN_FILENO,
+          buffer.as_mut_ptr() as *mut libc::c_void,
+          buffer.len(),
+        )
+      };
+      if read_count <= 0 {
+        return None;
+      }
+      response.extend_from_slice(&buffer[..read_count as usize]);
+    }
+    Some(response)
+  };
+  let response = read_response();
+  // SAFETY: restores the settings read above
+  unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &original) };
+  response
+}
+
+/// Reading the console's input with a timeout isn't supported on the
+/// other platforms, so the background is never detected.
+#[cfg(not(unix))]
+fn query_background_response(_timeout: Duration) -> Option<Vec<u8>> {
+  None
+}
+
+/// The colors of the 16 color palette as displayed by xterm, which the
+/// other terminals roughly match.
+const ANSI16_PALETTE: [(u8, u8, u8); 16] = [
+  (0, 0, 0),
+  (205, 0, 0),
+  (0, 205, 0),
+  (205, 205, 0),
+  (0, 0, 238),
+  (205, 0, 205),
+  (0, 205, 205),
+  (229, 229, 229),
+  (127, 127, 127),
+  (255, 0, 0),
+  (0, 255, 0),
+  (255, 255, 0),
+  (92, 92, 255),
+  (255, 0, 255),
+  (0, 255, 255),
+  (255, 255, 255),
+];
+
+/// The levels of each channel of the 6x6x6 color cube of the 256 colors.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+  let channel = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+  channel(a.0, b.0) + channel(a.1, b.1) + channel(a.2, b.2)
+}
+
+/// Gets the nearest of the 256 colors, which is either in the color cube
+/// or the grayscale ramp that follows it.
+fn rgb_to_ansi256(rgb: (u8, u8, u8)) -> u8 {
+  fn cube_index(value: u8) -> u8 {
+    match value {
+      0..48 => 0,
+      48..115 => 1,
+      _ => (value - 35) / 40,
+    }
+  }
+
+  let (r, g, b) = (cube_index(rgb.0), cube_index(rgb.1), cube_index(rgb.2));
+  let cube_rgb = (
+    CUBE_LEVELS[r as usize],
+    CUBE_LEVELS[g as usize],
+    CUBE_LEVELS[b as usize],
+  );
+  let average = ((rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3) as u8;
+  let gray_index = if average > 238 {
+    23
+  } else {
+    average.saturating_sub(3) / 10
+  };
+  let gray = 8 + gray_index * 10;
+  if distance(rgb, (gray, gray, gray)) < distance(rgb, cube_rgb) {
+    232 + gray_index
+  } else {
+    16 + r * 36 + g * 6 + b
+  }
+}
+
+fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+  match index {
+    0..16 => ANSI16_PALETTE[index as usize],
+    16..232 => {
+      let index = index - 16;
+      (
+        CUBE_LEVELS[(index / 36) as usize],
+        CUBE_LEVELS[(index / 6 % 6) as usize],
+        CUBE_LEVELS[(index % 6) as usize],
+      )
+    }
+    _ => {
+      let gray = 8 + (index - 232) * 10;
+      (gray, gray, gray)
+    }
+  }
+}
+
+/// Gets the index of the nearest of the 16 colors.
+fn rgb_to_ansi16(rgb: (u8, u8, u8)) -> u8 {
+  (0..16)
+    .min_by_key(|index| distance(rgb, ANSI16_PALETTE[*index as usize]))
+    .unwrap()
+}
+
+/// Gets the SGR parameter that sets the 16 color in the foreground or
+/// background, such as `31` for red or `101` for a bright red background.
+fn ansi16_param(index: u8, is_background: bool) -> String {
+  let base = match (index < 8, is_background) {
+    (true, false) => 30,
+    (true, true) => 40,
+    (false, false) => 90,
+    (false, true) => 100,
+  };
+  (base + index % 8).to_string()
+}
+
+/// Downgrades the colors set in the SGR parameters, returning `None`
+/// when the parameters can't be parsed.
+fn downgrade_sgr_params(params: &str, depth: ColorDepth) -> Option<String> {
+  let params = params.split(';').collect::<Vec<_>>();
+  let mut result = Vec::with_capacity(params.len());
+  let mut i = 0;
+  while i < params.len() {
+    let param = params[i];
+    if param != "38" && param != "48" {
+      result.push(param.to_string());
+      i += 1;
+      continue;
+    }
+    let is_background = param == "48";
+    let number = |offset: usize| -> Option<u8> {
+      params.get(i + offset)?.parse::<u8>().ok()
+    };
+    let (rgb, index, len) = match params.get(i + 1).copied() {
+      Some("2") => {
+        let rgb = (number(2)?, number(3)?, number(4)?);
+        (rgb, rgb_to_ansi256(rgb), 5)
+      }
+      Some("5") => {
+        let index = number(2)?;
+        (ansi256_to_rgb(index), index, 3)
+      }
+      _ => return None,
+    };
+    let keeps_rgb = depth == ColorDepth::TrueColor;
+    if keeps_rgb || (depth == ColorDepth::Ansi256 && len == 3) {
+      result.extend(params[i..i + len].iter().map(|param| param.to_string()));
+    } else if depth == ColorDepth::Ansi256 {
+      result.push(param.to_string());
+      result.push("5".to_string());
+      result.push(index.to_string());
+    } else {
+      result.push(ansi16_param(rgb_to_ansi16(rgb), is_background));
+    }
+    i += len;
+  }
+  Some(result.join(";"))
+}
+
+/// Downgrades the 24-bit and 256 colors set by the text's escape
+/// sequences to the nearest colors the depth supports, such as for
+/// drawing a theme's colors on a terminal that only displays 256 colors.
+/// The other escape sequences are left as is.
+pub fn downgrade_colors(text: &str, depth: ColorDepth) -> String {
+  if depth == ColorDepth::TrueColor || !text.contains("\x1b[") {
+    return text.to_string();
+  }
+  let mut result = String::with_capacity(text.len());
+  for token in tokenize(text) {
+    let token_text = &text[token.range];
+    let sgr_params = token_text
+      .strip_prefix("\x1b[")
+      .and_then(|sequence| sequence.strip_suffix('m'))
+      .filter(|_| token.is_escape);
+    match sgr_params.and_then(|params| downgrade_sgr_params(params, depth)) {
+      Some(params) => {
+        result.push_str("\x1b[");
+        result.push_str(&params);
+        result.push('m');
+      }
+      None => result.push_str(token_text),
+    }
+  }
+  result
+}
+
+#[cfg(windows)]
+fn vt_processing() -> bool {
+  use std::os::windows::io::AsRawHandle;
+
+  use winapi::shared::minwindef::DWORD;
+  use winapi::um::consoleapi::GetConsoleMode;
+
+  const ENABLE_VIRTUAL_TERMINAL_PROCESSING: DWORD = 0x0004;
+
+  let handle = std::io::stderr().as_raw_handle();
+  let mut mode: DWORD = 0;
+  // SAFETY: the handle is stderr's handle and the mode is a valid pointer
+  let is_console = unsafe { GetConsoleMode(handle as _, &mut mode) } != 0;
+  is_console && mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+}
+
+#[cfg(not(windows))]
+fn vt_processing() -> bool {
+  true
+}
+
+#[cfg(test)]
+mod test {
+  use std::collections::HashMap;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn detect(vars: &[(&str, &str)]) -> TerminalCaps {
+    let vars = vars
+      .iter()
+      .map(|(name, value)| (name.to_string(), value.to_string()))
+      .collect::<HashMap<_, _>>();
+    TerminalCaps::detect_with(|name| vars.get(name).cloned(), true)
+  }
+
+  #[test]
+  fn detects_from_env_vars() {
+    assert_eq!(
+      detect(&[]),
+      TerminalCaps {
+        truecolor: false,
+        color_depth: None,
+        unicode: !cfg!(unix),
+        hyperlinks: false,
+        vt_processing: true,
+        synchronized_output: false,
+      }
+    );
+    assert!(detect(&[("COLORTERM", "truecolor")]).truecolor);
+    assert!(detect(&[("COLORTERM", "24bit")]).truecolor);
+    assert!(!detect(&[("COLORTERM", "1")]).truecolor);
+    assert_eq!(
+      detect(&[("COLORTERM", "truecolor")]).color_depth,
+      Some(ColorDepth::TrueColor)
+    );
+    assert_eq!(
+      detect(&[("WT_SESSION", "1")]).color_depth,
+      Some(ColorDepth::TrueColor)
+    );
+    assert_eq!(
+      detect(&[("TERM", "xterm-256color")]).color_depth,
+      Some(ColorDepth::Ansi256)
+    );
+    assert_eq!(
+      detect(&[("TERM", "linux")]).color_depth,
+      Some(ColorDepth::Ansi16)
+    );
+    // unknown terminals aren't assumed to be limited
+    assert_eq!(detect(&[("TERM", "xterm")]).color_depth, None);
+
+    assert!(detect(&[("LANG", "en_US.UTF-8")]).unicode);
+    if cfg!(unix) {
+      assert!(!detect(&[("LANG", "C")]).unicode);
+      // the first non-empty variable wins
+      assert!(!detect(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")]).unicode);
+      assert!(detect(&[("LC_ALL", ""), ("LANG", "en_US.utf8")]).unicode);
+    }
+
+    assert!(detect(&[("TERM_PROGRAM", "WezTerm")]).hyperlinks);
+    assert!(detect(&[("TERM", "xterm-kitty")]).hyperlinks);
+    assert!(detect(&[("WT_SESSION", "1")]).hyperlinks);
+    assert!(detect(&[("VTE_VERSION", "6003")]).hyperlinks);
+    assert!(!detect(&[("VTE_VERSION", "3405")]).hyperlinks);
+    assert!(!detect(&[("TERM", "dumb"), ("WT_SESSION", "1")]).hyperlinks);
+
+    assert!(detect(&[("TERM_PROGRAM", "ghostty")]).synchronized_output);
+    assert!(detect(&[("TERM", "foot")]).synchronized_output);
+    assert!(!detect(&[("TERM", "xterm-256color")]).synchronized_output);
+  }
+
+  #[test]
+  fn parses_background_responses() {
+    let parse = |response: &str| parse_background_response(response.as_bytes());
+    assert_eq!(
+      parse("\x1b]11;rgb:ffff/ffff/dddd\x07"),
+      Some(Background::Light)
+    );
+    assert_eq!(
+      parse("\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\"),
+      Some(Background::Dark)
+    );
+    // the channels may have fewer digits
+    assert_eq!(parse("\x1b]11;rgb:f/f/f\x07"), Some(Background::Light));
+    assert_eq!(parse("\x1b]11;rgb:00/00/ff\x07"), Some(Background::Dark));
+    assert_eq!(
+      parse("\x1b]11;rgba:fff/fff/fff/000\x07"),
+      Some(Background::Light)
+    );
+    // input typed before the answer is skipped
+    assert_eq!(parse("ab\x1b]11;rgb:0/0/0\x07"), Some(Background::Dark));
+    assert_eq!(parse("\x1b]11;rgb:ff/ff\x07"), None);
+    assert_eq!(parse("\x1b]11;rgb:fffff/0/0\x07"), None);
+    assert_eq!(parse("\x1b]10;rgb:0/0/0\x07"), None);
+    assert_eq!(parse(""), None);
+
+    assert!(!is_background_response_complete(b""));
+    assert!(!is_background_response_complete(b"\x1b]11;rgb:0/0"));
+    assert!(is_background_response_complete(b"\x1b]11;rgb:0/0/0\x07"));
+    assert!(is_background_response_complete(b"\x1b]11;rgb:0/0/0\x1b\\"));
+  }
+
+  #[test]
+  fn downgrades_colors() {
+    let text = "\x1b[38;2;255;0;0mred\x1b[39m \x1b[1;48;2;0;0;128mblue\x1b[0m";
+    assert_eq!(downgrade_colors(text, ColorDepth::TrueColor), text);
+    assert_eq!(
+      downgrade_colors(text, ColorDepth::Ansi256),
+      "\x1b[38;5;196mred\x1b[39m \x1b[1;48;5;18mblue\x1b[0m"
+    );
+    assert_eq!(
+      downgrade_colors(text, ColorDepth::Ansi16),
+      "\x1b[91mred\x1b[39m \x1b[1;44mblue\x1b[0m"
+    );
+    // grays use the grayscale ramp
+    assert_eq!(
+      downgrade_colors("\x1b[38;2;128;128;128m", ColorDepth::Ansi256),
+      "\x1b[38;5;244m"
+    );
+    // 256 colors are only downgraded to 16
+    let text = "\x1b[38;5;196mred\x1b[2K";
+    assert_eq!(downgrade_colors(text, ColorDepth::Ansi256), text);
+    assert_eq!(
+      downgrade_colors(text, ColorDepth::Ansi16),
+      "\x1b[91mred\x1b[2K"
+    );
+  }
+}
@@ -0,0 +1,199 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use deno_core::parking_lot::Mutex;
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::text::truncate_to_width;
+use super::DrawThreadRenderer;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+#[derive(Debug, PartialEq, Eq)]
+enum EscapeSequence {
+  Incomplete,
+  /// The sequence is complete and whether it's kept in the line.
+  Complete {
+    keep: bool,
+  },
+}
+
+/// Gets whether the escape sequence, starting with the escape character,
+/// is complete. Only the sequences that set the style (SGR) are kept.
+/// The others, such as those that move the cursor, can't be drawn
+/// within the entry.
+fn parse_escape_sequence(sequence: &[u8]) -> EscapeSequence {
+  match sequence {
+    [ESC] => EscapeSequence::Incomplete,
+    // CSI, such as "\x1b[2K" or "\x1b[32m"
+    [ESC, b'[', rest @ ..] => match rest.last() {
+      Some(byte @ 0x40..=0x7e) => EscapeSequence::Complete {
+        keep: *byte == b'm',
+      },
+      _ => EscapeSequence::Incomplete,
+    },
+    // OSC, which ends with BEL or ST
+    [ESC, b']', rest @ ..] => {
+      if rest.ends_with(&[BEL]) || rest.ends_with(&[ESC, b'\\']) {
+        EscapeSequence::Complete { keep: false }
+      } else {
+        EscapeSequence::Incomplete
+      }
+    }
+    _ => EscapeSequence::Complete { keep: false },
+  }
+}
+
+#[derive(Debug, Default)]
+struct StreamState {
+  /// The bytes of the line currently being written.
+  line: Vec<u8>,
+  /// The bytes of an escape sequence that isn't complete yet.
+  escape: Vec<u8>,
+  /// Whether a carriage return was received, which replaces the line
+  /// once more text is written unless it's followed by a newline.
+  is_return_pending: bool,
+}
+
+impl StreamState {
+  fn replace_line_if_pending(&mut self) {
+    if self.is_return_pending {
+      self.line.clear();
+      self.is_return_pending = false;
+    }
+  }
+}
+
+/// Renders the line a stream is currently writing, such as the progress
+/// of a subprocess like `git clone`, which replaces its line by writing
+/// a carriage return. Add it to the draw thread once, then push the
+/// stream's output to it as it's read.
+///
+/// Sequences that move the cursor or erase the line are removed, while
+/// the sequences that set the style are kept.
+#[derive(Debug, Default)]
+pub struct CarriageReturnRenderer {
+  state: Mutex<StreamState>,
+}
+
+// constructed by the callers that forward the output of a subprocess
+#[allow(dead_code)]
+impl CarriageReturnRenderer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds bytes of the stream, which may end partway through a line,
+  /// character or escape sequence. Returns the lines that were ended by
+  /// a newline, such as for printing them above the region.
+  pub fn push_bytes(&self, bytes: &[u8]) -> Vec<String> {
+    let mut state = self.state.lock();
+    let mut committed_lines = Vec::new();
+    for &byte in bytes {
+      if !state.escape.is_empty() {
+        state.escape.push(byte);
+        if let EscapeSequence::Complete { keep } =
+          parse_escape_sequence(&state.escape)
+        {
+          let escape = std::mem::take(&mut state.escape);
+          if keep {
+            state.line.extend_from_slice(&escape);
+          }
+        }
+        continue;
+      }
+      match byte {
+        b'\r' => state.is_return_pending = true,
+        b'\n' => {
+          state.is_return_pending = false;
+          let line = std::mem::take(&mut state.line);
+          committed_lines.push(String::from_utf8_lossy(&line).into_owned());
+        }
+        ESC => {
+          state.replace_line_if_pending();
+          state.escape.push(byte);
+        }
+        _ => {
+          state.replace_line_if_pending();
+          state.line.push(byte);
+        }
+      }
+    }
+    committed_lines
+  }
+}
+
+impl DrawThreadRenderer for CarriageReturnRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    let state = self.state.lock();
+    truncate_to_width(&String::from_utf8_lossy(&state.line), size.cols as usize)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const SIZE: ConsoleSize = ConsoleSize { cols: 40, rows: 10 };
+
+  #[test]
+  fn renders_current_line_of_stream() {
+    let renderer = CarriageReturnRenderer::new();
+    assert_eq!(renderer.render(&SIZE), "");
+    let committed = renderer.push_bytes(b"Cloning into 'deno'...\nremote: Cou");
+    assert_eq!(committed, vec!["Cloning into 'deno'..."]);
+    assert_eq!(renderer.render(&SIZE), "remote: Cou");
+    renderer.push_bytes(b"nting objects:  50% (1/2)\r");
+    // the line is kept until more text is written
+    assert_eq!(
+      renderer.render(&SIZE),
+      "remote: Counting objects:  50% (1/2)"
+    );
+    let committed =
+      renderer.push_bytes(b"remote: Counting objects: 100% (2/2), done.\r\n");
+    assert_eq!(
+      committed,
+      vec!["remote: Counting objects: 100% (2/2), done."]
+    );
+    assert_eq!(renderer.render(&SIZE), "");
+
+    // removes the cursor sequences while keeping the styles, which may be
+    // split across pushes like the characters
+    renderer.push_bytes(b"Receiving objects:  10%\x1b[K\r");
+    assert_eq!(renderer.render(&SIZE), "Receiving objects:  10%");
+    renderer.push_bytes(b"\x1b[3");
+    assert_eq!(renderer.render(&SIZE), "");
+    renderer.push_bytes(b"2mReceiving objects: \xe2\x9c");
+    renderer.push_bytes(b"\x93\x1b[0m\x1b[1A\x1b]0;title\x07");
+    assert_eq!(
+      renderer.render(&SIZE),
+      "\x1b[32mReceiving objects: \u{2713}\x1b[0m"
+    );
+    renderer.push_bytes(b"\r");
+    assert_eq!(
+      renderer.push_bytes(b"\n"),
+      vec!["\x1b[32mReceiving objects: \u{2713}\x1b[0m"]
+    );
+  }
+
+  #[test]
+  fn parses_escape_sequences() {
+    use EscapeSequence::*;
+    assert_eq!(parse_escape_sequence(b"\x1b"), Incomplete);
+    assert_eq!(parse_escape_sequence(b"\x1b["), Incomplete);
+    assert_eq!(parse_escape_sequence(b"\x1b[1;3"), Incomplete);
+    assert_eq!(
+      parse_escape_sequence(b"\x1b[1;32m"),
+      Complete { keep: true }
+    );
+    assert_eq!(parse_escape_sequence(b"\x1b[2K"), Complete { keep: false });
+    assert_eq!(parse_escape_sequence(b"\x1b]0;ti"), Incomplete);
+    assert_eq!(
+      parse_escape_sequence(b"\x1b]0;title\x1b\\"),
+      Complete { keep: false }
+    );
+    assert_eq!(parse_escape_sequence(b"\x1b7"), Complete { keep: false });
+  }
+}
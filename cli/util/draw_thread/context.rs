@@ -0,0 +1,89 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::any::Any;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::progress::DurationFormat;
+use super::text::TextDirection;
+
+/// Shared immutable values provided to every renderer, such as a theme
+/// or locale, which saves threading them through each renderer's
+/// constructor.
+///
+/// Values are keyed by their type, so there's at most one value of each
+/// type and a renderer fetches a value by asking for its type.
+#[derive(Debug, Default, Clone)]
+pub struct RenderContext {
+  values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl RenderContext {
+  /// Gets the value of the provided type, if one was set.
+  pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+    self
+      .values
+      .get(&TypeId::of::<T>())
+      .and_then(|value| value.downcast_ref::<T>())
+  }
+
+  /// Sets the value of its type, replacing any previous value.
+  pub fn set<T: Any + Send + Sync>(&mut self, value: Arc<T>) {
+    self.values.insert(TypeId::of::<T>(), value);
+  }
+
+  /// Gets the direction the text is read in, which is set as a
+  /// `TextDirection` value. Defaults to `TextDirection::Ltr`.
+  // read by the renderers that align right to left text
+  #[allow(dead_code)]
+  pub fn text_direction(&self) -> TextDirection {
+    self.get::<TextDirection>().copied().unwrap_or_default()
+  }
+
+  /// Gets the style durations are formatted in, which is set as a
+  /// `DurationFormat` value. The renderers use their own style when
+  /// this isn't set.
+  pub fn duration_format(&self) -> Option<DurationFormat> {
+    self.get::<DurationFormat>().copied()
+  }
+
+  /// Removes the value of the provided type.
+  pub fn remove<T: Any + Send + Sync>(&mut self) {
+    self.values.remove(&TypeId::of::<T>());
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::util::draw_thread::text::justify;
+
+  #[derive(Debug, PartialEq)]
+  struct Locale(&'static str);
+
+  #[derive(Debug, PartialEq)]
+  struct UseMetricUnits(bool);
+
+  #[test]
+  fn gets_values_by_type() {
+    let mut context = RenderContext::default();
+    assert_eq!(context.get::<Locale>(), None);
+    context.set(Arc::new(Locale("en-US")));
+    assert_eq!(context.get::<Locale>(), Some(&Locale("en-US")));
+    assert_eq!(context.get::<UseMetricUnits>(), None);
+    context.set(Arc::new(UseMetricUnits(true)));
+    context.set(Arc::new(Locale("de-DE")));
+    assert_eq!(context.get::<Locale>(), Some(&Locale("de-DE")));
+    assert_eq!(context.get::<UseMetricUnits>(), Some(&UseMetricUnits(true)));
+    context.remove::<Locale>();
+    assert_eq!(context.get::<Locale>(), None);
+
+    assert_eq!(context.text_direction(), TextDirection::Ltr);
+    context.set(Arc::new(TextDirection::Rtl));
+    assert_eq!(context.text_direction(), TextDirection::Rtl);
+    assert_eq!(justify("שלום", 6, context.text_direction()), "  שלום");
+  }
+}
@@ -0,0 +1,136 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::context::RenderContext;
+use super::progress::format_duration;
+use super::progress::DurationFormat;
+use super::text::truncate_to_width;
+use super::DrawThreadRenderer;
+
+/// Renders a label followed by the whole seconds remaining until a
+/// deadline, such as "Retrying in 5s". Once the deadline passes, this
+/// renders the completion message or nothing. The remaining time is
+/// formatted with `DurationFormat::Compact` unless the context provides
+/// another format.
+#[derive(Debug)]
+pub struct CountdownRenderer {
+  label: String,
+  deadline: Instant,
+  completion_message: Option<String>,
+}
+
+// for the callers that wait on a deadline, such as before a retry
+#[allow(dead_code)]
+impl CountdownRenderer {
+  pub fn new(label: impl Into<String>, deadline: Instant) -> Self {
+    Self {
+      label: label.into(),
+      deadline,
+      completion_message: None,
+    }
+  }
+
+  /// Sets the message rendered once the deadline passes.
+  pub fn with_completion_message(mut self, message: impl Into<String>) -> Self {
+    self.completion_message = Some(message.into());
+    self
+  }
+
+  fn render_at(
+    &self,
+    size: &ConsoleSize,
+    now: Instant,
+    duration_format: DurationFormat,
+  ) -> String {
+    let remaining = self.deadline.saturating_duration_since(now);
+    let text = if remaining.is_zero() {
+      match &self.completion_message {
+        Some(message) => message.clone(),
+        None => return String::new(),
+      }
+    } else {
+      // round up so that the last second displays "1s" rather than "0s"
+      let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+      let remaining =
+        format_duration(Duration::from_secs(secs), duration_format);
+      format!("{} {}", self.label, remaining)
+    };
+    truncate_to_width(&text, size.cols as usize)
+  }
+}
+
+impl DrawThreadRenderer for CountdownRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    self.render_at(size, Instant::now(), DurationFormat::Compact)
+  }
+
+  fn render_with_context(
+    &self,
+    size: &ConsoleSize,
+    _frame: u64,
+    context: &RenderContext,
+  ) -> String {
+    let duration_format =
+      context.duration_format().unwrap_or(DurationFormat::Compact);
+    self.render_at(size, Instant::now(), duration_format)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::sync::Arc;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const SIZE: ConsoleSize = ConsoleSize { cols: 20, rows: 10 };
+  const COMPACT: DurationFormat = DurationFormat::Compact;
+
+  #[test]
+  fn renders_remaining_seconds() {
+    let start = Instant::now();
+    let renderer =
+      CountdownRenderer::new("Retrying in", start + Duration::from_secs(5));
+    assert_eq!(renderer.render_at(&SIZE, start, COMPACT), "Retrying in 5s");
+    let now = start + Duration::from_millis(1500);
+    assert_eq!(renderer.render_at(&SIZE, now, COMPACT), "Retrying in 4s");
+    let now = start + Duration::from_millis(4900);
+    assert_eq!(renderer.render_at(&SIZE, now, COMPACT), "Retrying in 1s");
+    let now = start + Duration::from_secs(5);
+    assert_eq!(renderer.render_at(&SIZE, now, COMPACT), "");
+    let size = ConsoleSize { cols: 10, rows: 10 };
+    assert_eq!(renderer.render_at(&size, start, COMPACT), "Retrying i");
+
+    let mut context = RenderContext::default();
+    context.set(Arc::new(DurationFormat::Verbose));
+    let renderer =
+      CountdownRenderer::new("Retrying in", start + Duration::from_secs(90));
+    let size = ConsoleSize { cols: 40, rows: 10 };
+    assert_eq!(
+      renderer.render_with_context(&size, 0, &context),
+      "Retrying in 1 minute 30 seconds"
+    );
+  }
+
+  #[test]
+  fn renders_completion_message_once_passed() {
+    let start = Instant::now();
+    // already passed at construction
+    let renderer = CountdownRenderer::new("Retrying in", start)
+      .with_completion_message("Retrying");
+    assert_eq!(renderer.render(&SIZE), "Retrying");
+
+    // shorter than a tick
+    let renderer =
+      CountdownRenderer::new("Retrying in", start + Duration::from_millis(10))
+        .with_completion_message("Retrying");
+    assert_eq!(renderer.render_at(&SIZE, start, COMPACT), "Retrying in 1s");
+    let now = start + Duration::from_millis(60);
+    assert_eq!(renderer.render_at(&SIZE, now, COMPACT), "Retrying");
+  }
+}
@@ -0,0 +1,243 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::collections::HashMap;
+
+/// Values keyed by id that keep the order they were added in while
+/// allowing removal by id in amortized constant time.
+///
+/// Removed values leave an empty slot behind so that the other values
+/// don't shift. The slots are compacted once most of them are empty.
+#[derive(Debug)]
+pub struct EntryList<T> {
+  slots: Vec<Option<(u16, T)>>,
+  indexes: HashMap<u16, usize>,
+}
+
+impl<T> Default for EntryList<T> {
+  fn default() -> Self {
+    Self {
+      slots: Vec::new(),
+      indexes: HashMap::new(),
+    }
+  }
+}
+
+impl<T> EntryList<T> {
+  pub fn len(&self) -> usize {
+    self.indexes.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.indexes.is_empty()
+  }
+
+  /// Adds the value to the end. The id must not be in use, though a value
+  /// with the same id is replaced if it is.
+  pub fn push(&mut self, id: u16, value: T) {
+    debug_assert!(!self.indexes.contains_key(&id), "id {id} is in use");
+    self.remove(id);
+    self.indexes.insert(id, self.slots.len());
+    self.slots.push(Some((id, value)));
+  }
+
+  pub fn remove(&mut self, id: u16) -> Option<T> {
+    let index = self.indexes.remove(&id)?;
+    let (_, value) = self.slots[index].take()?;
+    if self.indexes.is_empty() {
+      self.slots.clear();
+    } else if self.slots.len() > 16 && self.indexes.len() < self.slots.len() / 2
+    {
+      self.compact();
+    }
+    Some(value)
+  }
+
+  /// Moves the value to the start, returning whether it exists.
+  pub fn move_to_front(&mut self, id: u16) -> bool {
+    let Some(&index) = self.indexes.get(&id) else {
+      return false;
+    };
+    let slot = self.slots[index].take();
+    self.slots.insert(0, slot);
+    self.compact();
+    true
+  }
+
+  /// Moves the value to the end, returning whether it exists.
+  pub fn move_to_back(&mut self, id: u16) -> bool {
+    match self.remove(id) {
+      Some(value) => {
+        self.push(id, value);
+        true
+      }
+      None => false,
+    }
+  }
+
+  pub fn get(&self, id: u16) -> Option<&T> {
+    let index = *self.indexes.get(&id)?;
+    self.slots[index].as_ref().map(|(_, value)| value)
+  }
+
+  pub fn get_mut(&mut self, id: u16) -> Option<&mut T> {
+    let index = *self.indexes.get(&id)?;
+    self.slots[index].as_mut().map(|(_, value)| value)
+  }
+
+  /// Iterates the values in the order they were added.
+  pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+    self.slots.iter().flatten().map(|(_, value)| value)
+  }
+
+  /// Keeps only the values the predicate returns `true` for.
+  pub fn retain(&mut self, mut predicate: impl FnMut(&T) -> bool) {
+    for slot in &mut self.slots {
+      if let Some((id, value)) = slot {
+        if !predicate(value) {
+          self.indexes.remove(id);
+          *slot = None;
+        }
+      }
+    }
+    self.compact();
+  }
+
+  fn compact(&mut self) {
+    self.slots.retain(|slot| slot.is_some());
+    for (index, slot) in self.slots.iter().enumerate() {
+      if let Some((id, _)) = slot {
+        self.indexes.insert(*id, index);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::time::Instant;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn values(list: &EntryList<&'static str>) -> Vec<&'static str> {
+    list.iter().copied().collect()
+  }
+
+  #[test]
+  fn keeps_order_when_removing() {
+    let mut list = EntryList::default();
+    assert!(list.is_empty());
+    list.push(1, "a");
+    list.push(2, "b");
+    list.push(3, "c");
+    assert_eq!(list.len(), 3);
+    assert_eq!(values(&list), vec!["a", "b", "c"]);
+    assert_eq!(list.remove(2), Some("b"));
+    assert_eq!(list.remove(2), None);
+    assert_eq!(values(&list), vec!["a", "c"]);
+    list.push(4, "d");
+    assert_eq!(values(&list), vec!["a", "c", "d"]);
+    assert_eq!(list.get(3), Some(&"c"));
+    *list.get_mut(3).unwrap() = "e";
+    assert_eq!(values(&list), vec!["a", "e", "d"]);
+    list.retain(|value| *value != "a");
+    assert_eq!(values(&list), vec!["e", "d"]);
+    assert_eq!(list.get(1), None);
+    assert_eq!(list.get(4), Some(&"d"));
+  }
+
+  #[test]
+  fn moves_values() {
+    let mut list = EntryList::default();
+    list.push(1, "a");
+    list.push(2, "b");
+    list.push(3, "c");
+    assert!(list.move_to_front(2));
+    assert_eq!(values(&list), vec!["b", "a", "c"]);
+    assert!(list.move_to_back(2));
+    assert_eq!(values(&list), vec!["a", "c", "b"]);
+    assert!(list.move_to_front(3));
+    assert_eq!(values(&list), vec!["c", "a", "b"]);
+    assert_eq!(list.get(1), Some(&"a"));
+    list.remove(1);
+    assert!(!list.move_to_front(1));
+    assert!(!list.move_to_back(1));
+    assert_eq!(values(&list), vec!["c", "b"]);
+  }
+
+  #[test]
+  fn compacts_removed_slots() {
+    let mut list = EntryList::default();
+    for id in 0..100 {
+      list.push(id, id);
+    }
+    for id in (0..100).filter(|id| id % 4 != 0) {
+      list.remove(id);
+    }
+    assert!(list.slots.len() < 50);
+    assert_eq!(list.len(), 25);
+    assert_eq!(
+      list.iter().copied().collect::<Vec<_>>(),
+      (0..100).filter(|id| id % 4 == 0).collect::<Vec<_>>()
+    );
+    for id in (0..100).filter(|id| id % 4 == 0) {
+      assert_eq!(list.get(id), Some(&id));
+    }
+  }
+
+  /// Compares removing entries by id with a linear search, like the draw
+  /// thread used to do, against the entry list under heavy churn. Run
+  /// with `cargo test --release bench_entry_churn -- --ignored`.
+  #[test]
+  #[ignore]
+  fn bench_entry_churn() {
+    const LIVE_ENTRIES: u16 = 500;
+    const ITERATIONS: usize = 200_000;
+
+    /// Finishes a pseudo-random live entry and adds a new one each
+    /// iteration, providing the ids to remove and add.
+    fn churn(mut on_iteration: impl FnMut(u16, u16)) {
+      let mut live_ids = (0..LIVE_ENTRIES).collect::<Vec<_>>();
+      let mut seed = 1u64;
+      for i in 0..ITERATIONS {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+        let index = (seed >> 33) as usize % live_ids.len();
+        let removed_id = live_ids.swap_remove(index);
+        let added_id = LIVE_ENTRIES.wrapping_add(i as u16);
+        live_ids.push(added_id);
+        on_iteration(removed_id, added_id);
+      }
+    }
+
+    let mut entries = (0..LIVE_ENTRIES).map(|id| (id, id)).collect::<Vec<_>>();
+    let start = Instant::now();
+    churn(|removed_id, added_id| {
+      if let Some(index) = entries.iter().position(|(id, _)| *id == removed_id)
+      {
+        entries.remove(index);
+      }
+      entries.push((added_id, added_id));
+    });
+    let vec_elapsed = start.elapsed();
+
+    let mut list = EntryList::default();
+    for id in 0..LIVE_ENTRIES {
+      list.push(id, id);
+    }
+    let start = Instant::now();
+    churn(|removed_id, added_id| {
+      list.remove(removed_id);
+      list.push(added_id, added_id);
+    });
+    let list_elapsed = start.elapsed();
+
+    assert_eq!(entries.len(), list.len());
+    assert!(
+      list_elapsed < vec_elapsed,
+      "entry list: {:?}, linear search: {:?}",
+      list_elapsed,
+      vec_elapsed
+    );
+  }
+}
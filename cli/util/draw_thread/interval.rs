@@ -0,0 +1,101 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+//! Lengthens the draw interval while the process is starved of CPU, so
+//! that the draw thread doesn't compete with the actual work.
+//!
+//! The system's load isn't measured directly. Instead, sleeping much
+//! longer than requested indicates that the thread isn't being scheduled
+//! promptly, which happens when the system is loaded. The interval is
+//! doubled after several consecutive late wakeups, up to four times the
+//! base interval, and halved again after many consecutive prompt ones.
+
+use std::time::Duration;
+
+/// How much longer than requested a sleep must take to count as late.
+const LATE_FACTOR: f64 = 1.5;
+/// The number of consecutive late wakeups that lengthens the interval.
+const LATE_WAKEUPS_TO_BACK_OFF: u32 = 3;
+/// The number of consecutive prompt wakeups that shortens the interval.
+const PROMPT_WAKEUPS_TO_RECOVER: u32 = 10;
+const MAX_MULTIPLIER: u32 = 4;
+
+#[derive(Debug)]
+pub struct AdaptiveInterval {
+  base: Duration,
+  current: Duration,
+  late_count: u32,
+  prompt_count: u32,
+}
+
+impl AdaptiveInterval {
+  pub fn new(base: Duration) -> Self {
+    Self {
+      base,
+      current: base,
+      late_count: 0,
+      prompt_count: 0,
+    }
+  }
+
+  pub fn current(&self) -> Duration {
+    self.current
+  }
+
+  /// Records how long a sleep of the requested duration actually took.
+  pub fn record_sleep(&mut self, requested: Duration, actual: Duration) {
+    if actual.as_secs_f64() > requested.as_secs_f64() * LATE_FACTOR {
+      self.prompt_count = 0;
+      self.late_count += 1;
+      if self.late_count >= LATE_WAKEUPS_TO_BACK_OFF {
+        self.late_count = 0;
+        self.current = (self.current * 2).min(self.base * MAX_MULTIPLIER);
+      }
+    } else {
+      self.late_count = 0;
+      self.prompt_count += 1;
+      if self.prompt_count >= PROMPT_WAKEUPS_TO_RECOVER {
+        self.prompt_count = 0;
+        self.current = (self.current / 2).max(self.base);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const BASE: Duration = Duration::from_millis(100);
+
+  fn record(interval: &mut AdaptiveInterval, actual_ms: u64, count: u32) {
+    for _ in 0..count {
+      let requested = interval.current();
+      interval.record_sleep(requested, Duration::from_millis(actual_ms));
+    }
+  }
+
+  #[test]
+  fn backs_off_when_waking_late() {
+    let mut interval = AdaptiveInterval::new(BASE);
+    // occasional late wakeups don't back off
+    record(&mut interval, 300, 2);
+    record(&mut interval, 100, 1);
+    record(&mut interval, 300, 2);
+    assert_eq!(interval.current(), BASE);
+    record(&mut interval, 300, 1);
+    assert_eq!(interval.current(), BASE * 2);
+    // stays within four times the base
+    record(&mut interval, 1000, 30);
+    assert_eq!(interval.current(), BASE * 4);
+
+    // recovers once waking promptly
+    record(&mut interval, 0, 9);
+    assert_eq!(interval.current(), BASE * 4);
+    record(&mut interval, 0, 1);
+    assert_eq!(interval.current(), BASE * 2);
+    record(&mut interval, 0, 100);
+    assert_eq!(interval.current(), BASE);
+  }
+}
@@ -0,0 +1,85 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use deno_core::parking_lot::Mutex;
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::text::truncate_to_width;
+use super::DrawThreadRenderer;
+
+/// Renders the latest line of a subprocess's output as a single
+/// status line, such as to show what it's currently compiling
+/// without interleaving all its output in the region.
+#[derive(Debug, Default)]
+pub struct MirrorRenderer {
+  line: Mutex<String>,
+}
+
+// fed by the callers that mirror a stream
+#[allow(dead_code)]
+impl MirrorRenderer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Replaces the displayed line with the last non-empty line of the
+  /// text. A carriage return also starts a new line, so the progress
+  /// a subprocess draws over a single line is mirrored.
+  pub fn push_line(&self, text: impl AsRef<str>) {
+    let last_line = text
+      .as_ref()
+      .rsplit(['\n', '\r'])
+      .find(|line| !line.is_empty());
+    if let Some(line) = last_line {
+      *self.line.lock() = line.to_string();
+    }
+  }
+
+  /// Removes the displayed line.
+  pub fn clear(&self) {
+    self.line.lock().clear();
+  }
+}
+
+impl DrawThreadRenderer for MirrorRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    truncate_to_width(&self.line.lock(), size.cols as usize)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const SIZE: ConsoleSize = ConsoleSize { cols: 10, rows: 20 };
+
+  #[test]
+  fn renders_latest_line() {
+    let renderer = MirrorRenderer::new();
+    assert_eq!(renderer.render(&SIZE), "");
+    renderer.push_line("one");
+    assert_eq!(renderer.render(&SIZE), "one");
+    renderer.push_line("two\nthree\n");
+    assert_eq!(renderer.render(&SIZE), "three");
+    // empty lines keep the previous line
+    renderer.push_line("\n");
+    assert_eq!(renderer.render(&SIZE), "three");
+    // truncated to the width
+    renderer.push_line("Compiling a_long_crate_name");
+    assert_eq!(renderer.render(&SIZE), "Compiling ");
+    renderer.clear();
+    assert_eq!(renderer.render(&SIZE), "");
+  }
+
+  #[test]
+  fn carriage_return_replaces_line() {
+    let renderer = MirrorRenderer::new();
+    renderer.push_line("10%\r20%\r30%");
+    assert_eq!(renderer.render(&SIZE), "30%");
+    renderer.push_line("\r40%");
+    assert_eq!(renderer.render(&SIZE), "40%");
+    renderer.push_line("done\r\n");
+    assert_eq!(renderer.render(&SIZE), "done");
+  }
+}
@@ -0,0 +1,6696 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use console_static_text::ConsoleStaticText;
+use deno_core::parking_lot::Mutex;
+use deno_core::unsync::spawn;
+use deno_core::unsync::spawn_blocking;
+use deno_runtime::ops::tty::ConsoleSize;
+use deno_terminal::colors;
+use once_cell::sync::Lazy;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use self::capabilities::ColorDepth;
+use self::capabilities::TerminalCaps;
+use self::context::RenderContext;
+use self::entries::EntryList;
+use self::text::align_columns;
+use self::text::display_width;
+use self::text::truncate_to_width;
+use self::text::truncate_with_ellipsis;
+use self::text::wrap_to_width;
+use crate::util::console::console_size;
+
+mod backpressure;
+pub mod capabilities;
+pub mod carriage_return;
+pub mod context;
+pub mod countdown;
+mod entries;
+mod interval;
+pub mod mirror;
+pub mod multi_line;
+pub mod progress;
+pub mod reader;
+mod signal;
+pub mod sparkline;
+pub mod spinner;
+pub mod status_line;
+pub mod summary;
+pub mod tail;
+pub mod template;
+pub mod text;
+pub mod theme;
+pub mod transfer;
+pub mod tree;
+pub mod writer_sink;
+
+/// Renders text that will be displayed stacked in a
+/// static place on the console.
+pub trait DrawThreadRenderer: Send + Sync + std::fmt::Debug {
+  fn render(&self, data: &ConsoleSize) -> String;
+
+  /// Renders the provided frame, which animations can derive their
+  /// phase from. The frame number increases every render pass and
+  /// persists for the lifetime of the process, even when the draw
+  /// thread restarts. Defaults to calling `render`.
+  fn render_frame(&self, data: &ConsoleSize, frame: u64) -> String {
+    let _ = frame;
+    self.render(data)
+  }
+
+  /// Renders the provided frame with the context set via
+  /// `DrawThread::set_context`. Defaults to calling `render_frame`.
+  fn render_with_context(
+    &self,
+    data: &ConsoleSize,
+    frame: u64,
+    context: &RenderContext,
+  ) -> String {
+    let _ = context;
+    self.render_frame(data, frame)
+  }
+
+  /// Renders more detail than usual, such as extra lines for a task
+  /// that's taking unusually long, which is used instead of the other
+  /// render methods once `DrawThreadGuard::set_verbose` is enabled.
+  /// Defaults to calling `render`.
+  fn render_verbose(&self, data: &ConsoleSize) -> String {
+    self.render(data)
+  }
+
+  /// The minimum number of columns this renderer needs. It's not drawn
+  /// while the console is narrower than this.
+  fn min_cols(&self) -> u16 {
+    0
+  }
+
+  /// Gets the determinate progress of this renderer as a value
+  /// between `0.0` and `1.0`, if known.
+  fn progress(&self) -> Option<f64> {
+    None
+  }
+
+  /// Gets whether the operation this renderer displays failed, which is
+  /// recorded once the entry finishes. Defaults to `false`.
+  fn is_failed(&self) -> bool {
+    false
+  }
+
+  /// Gets this renderer as a renderer that provides structured
+  /// data for the JSON lines fallback, if it is one.
+  fn as_structured(&self) -> Option<&dyn StructuredRenderer> {
+    None
+  }
+
+  /// Gets the version of the state this renderer displays, which the
+  /// draw thread uses to measure how long updates take to be drawn.
+  /// Defaults to `None`, which isn't measured.
+  fn version(&self) -> Option<RenderVersion> {
+    None
+  }
+
+  /// Gets what this renderer contributes to the counts drawn by a
+  /// `SummaryRenderer`, which are aggregated over the live entries
+  /// every frame. Defaults to `None`, which isn't counted.
+  fn summary_contribution(&self) -> Option<summary::SummaryPart> {
+    None
+  }
+}
+
+/// The version of a renderer's state.
+///
+/// The number must change whenever the state changes in a way that
+/// affects the rendered text, and `changed_at` is when it last changed.
+/// Updates between two frames may share a version, since only the
+/// latest one is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderVersion {
+  pub number: u64,
+  pub changed_at: Instant,
+}
+
+/// A renderer that exposes its state as structured data for machine
+/// consumption, which is emitted instead of its text when using
+/// `Fallback::JsonLines`.
+pub trait StructuredRenderer: DrawThreadRenderer {
+  fn render_structured(&self, data: &ConsoleSize) -> serde_json::Value;
+}
+
+/// Receives every frame drawn by the draw thread in addition to the
+/// terminal, such as to stream the progress to a remote dashboard.
+///
+/// Sinks are called while the draw thread's state is locked, so they
+/// should be quick and must not call into the `DrawThread`.
+pub trait FrameSink: Send + std::fmt::Debug {
+  /// Receives the text of the frame and the size it was rendered for.
+  fn write_frame(&mut self, text: &str, size: &ConsoleSize);
+
+  /// Called when the region is cleared, such as when the
+  /// draw thread is hidden or all the entries are finished.
+  fn clear(&mut self) {}
+
+  /// Gets the size the frames are rendered at for this sink when it
+  /// differs from the terminal's, such as the size of a pseudo terminal.
+  /// Defaults to `None`, which receives the frames drawn to the terminal.
+  /// The entries are rendered once per size in each frame, so the sinks
+  /// of the same size share the rendered text.
+  fn size(&self) -> Option<ConsoleSize> {
+    None
+  }
+}
+
+/// Keeps a sink registered with the draw thread. Once it is
+/// dropped, the sink is removed.
+#[derive(Debug)]
+pub struct FrameSinkGuard {
+  id: usize,
+  instance: DrawThreadInstance,
+}
+
+impl Drop for FrameSinkGuard {
+  fn drop(&mut self) {
+    let mut internal_state = self.instance.state.lock();
+    internal_state.sinks.retain(|(id, _)| *id != self.id);
+  }
+}
+
+/// Keeps the draw thread suspended. Pass it to `DrawThread::resume`
+/// or drop it in order to resume drawing.
+#[derive(Debug)]
+#[must_use = "the draw thread resumes once the token is dropped"]
+pub struct SuspendToken {
+  instance: DrawThreadInstance,
+}
+
+impl Drop for SuspendToken {
+  fn drop(&mut self) {
+    let mut internal_state = self.instance.state.lock();
+    internal_state.suspend_count -= 1;
+    self.instance.maybe_start_draw_thread(&mut internal_state);
+  }
+}
+
+/// Keeps the draw thread hidden until dropped, which is returned by
+/// `DrawThread::hide_scope`. The guards nested on a thread only take the
+/// lock for the outermost one. A guard can't be sent to another thread,
+/// so the nesting on each thread stays balanced.
+#[derive(Debug)]
+#[must_use = "the draw thread is shown again once the guard is dropped"]
+pub struct HideGuard {
+  instance: DrawThreadInstance,
+  _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for HideGuard {
+  fn drop(&mut self) {
+    let key = self.instance.hide_depth_key();
+    let is_outermost = HIDE_DEPTHS.with(|depths| {
+      let mut depths = depths.borrow_mut();
+      let Some(index) = depths.iter().position(|(k, _)| *k == key) else {
+        return false;
+      };
+      depths[index].1 -= 1;
+      let is_outermost = depths[index].1 == 0;
+      if is_outermost {
+        depths.swap_remove(index);
+      }
+      is_outermost
+    });
+    if is_outermost {
+      self.instance.show();
+    }
+  }
+}
+
+/// Keeps a ticker attached via `DrawThread::attach_ticker`. Once it's
+/// dropped, the ticker stops and the manual mode is restored to what it
+/// was before the ticker was attached.
+#[derive(Debug)]
+#[must_use = "the ticker is detached once the guard is dropped"]
+pub struct TickerGuard {
+  id: usize,
+  token: CancellationToken,
+  instance: DrawThreadInstance,
+}
+
+impl Drop for TickerGuard {
+  fn drop(&mut self) {
+    self.token.cancel();
+    let previous_manual = {
+      let mut internal_state = self.instance.state.lock();
+      match &internal_state.ticker {
+        Some(ticker) if ticker.id == self.id => {
+          internal_state.ticker.take().map(|t| t.previous_manual)
+        }
+        _ => None,
+      }
+    };
+    // a ticker attached after this one keeps driving the draw thread
+    if let Some(previous_manual) = previous_manual {
+      self.instance.set_manual(previous_manual);
+    }
+  }
+}
+
+/// How the previously drawn text is cleared before drawing a new frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClearStrategy {
+  /// Only re-draws the lines that changed since the last frame.
+  #[default]
+  Diff,
+  /// Clears the entire region before drawing each frame. This writes
+  /// considerably more to the terminal every frame and may flicker, but
+  /// avoids artifacts on terminals that don't handle the diff well when
+  /// the content shrinks.
+  FullClear,
+}
+
+/// How the rendered text of each entry is joined together.
+// the variants are picked by the callers of `set_join_mode`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JoinMode {
+  /// Entries that render empty text don't take up a line.
+  #[default]
+  SkipEmpty,
+  /// Every entry takes up at least one line, even when it renders
+  /// empty text, which keeps the row of each entry stable.
+  OneLinePerEntry,
+}
+
+/// The characters that end each line written to the terminal.
+// the variants are picked by the callers of `set_line_ending`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+  #[default]
+  Lf,
+  /// Lines end with a carriage return before the newline, for terminals
+  /// that don't return the cursor to the first column on a newline.
+  CrLf,
+}
+
+impl LineEnding {
+  /// Converts the newlines of the text that aren't already preceded by
+  /// a carriage return to this line ending.
+  fn apply(self, text: &str) -> Cow<'_, str> {
+    match self {
+      LineEnding::Lf => Cow::Borrowed(text),
+      LineEnding::CrLf if !text.contains('\n') => Cow::Borrowed(text),
+      LineEnding::CrLf => {
+        let mut output = String::with_capacity(text.len() + 16);
+        let mut previous = None;
+        for c in text.chars() {
+          if c == '\n' && previous != Some('\r') {
+            output.push('\r');
+          }
+          output.push(c);
+          previous = Some(c);
+        }
+        Cow::Owned(output)
+      }
+    }
+  }
+}
+
+/// What to output instead of drawing to the terminal.
+// the variants are picked by the callers of `set_fallback`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Fallback {
+  /// Output nothing when drawing isn't supported.
+  #[default]
+  None,
+  /// Emit a JSON object per entry to stdout on every tick when
+  /// drawing isn't supported, such as when not a TTY.
+  JsonLines,
+  /// Always emit JSON lines to stdout instead of drawing.
+  ForceJsonLines,
+}
+
+/// A rectangle of the terminal the entries are confined to. The
+/// positions are zero-based cells from the top left of the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+  pub top: u16,
+  pub left: u16,
+  pub width: u16,
+  pub height: u16,
+}
+
+impl Viewport {
+  /// The top rows of the terminal that `Pin::Top` draws in.
+  fn pinned(rows: u16, cols: u32) -> Self {
+    Self {
+      top: 0,
+      left: 0,
+      width: cols.min(u16::MAX as u32) as u16,
+      height: rows,
+    }
+  }
+
+  /// The size the renderers are provided when drawing in the viewport.
+  fn size(&self) -> ConsoleSize {
+    ConsoleSize {
+      cols: self.width as u32,
+      rows: self.height as u32,
+    }
+  }
+
+  /// Gets the text that draws the provided text within the viewport
+  /// using absolute cursor addressing, clipping it to the viewport and
+  /// blanking the rest of each row.
+  fn draw_text(&self, text: &str) -> String {
+    let mut lines = text.lines();
+    let mut output = String::from(CURSOR_SAVE);
+    for row in 0..self.height {
+      let line = lines.next().unwrap_or("");
+      let line = truncate_to_width(line, self.width as usize);
+      let padding = (self.width as usize).saturating_sub(display_width(&line));
+      output.push_str(&self.cursor_to_row(row));
+      output.push_str(&line);
+      output.push_str(&" ".repeat(padding));
+    }
+    output.push_str(CURSOR_RESTORE);
+    output
+  }
+
+  /// Gets the text that blanks the viewport.
+  fn clear_text(&self) -> String {
+    self.draw_text("")
+  }
+
+  fn cursor_to_row(&self, row: u16) -> String {
+    format!(
+      "\x1b[{};{}H",
+      self.top as u32 + row as u32 + 1,
+      self.left as u32 + 1
+    )
+  }
+}
+
+const CURSOR_SAVE: &str = "\x1b7";
+const CURSOR_RESTORE: &str = "\x1b8";
+
+/// Where the region is drawn in the terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pin {
+  /// The region is drawn below the cursor and output printed
+  /// via `DrawThread::println` is printed above it.
+  #[default]
+  Bottom,
+  /// The region is fixed to the top rows of the terminal and the
+  /// terminal's scroll region (DECSTBM) is set to the rows below it,
+  /// so output scrolls below the region without clearing it. The
+  /// scroll region is reset once the region is cleared.
+  Top,
+}
+
+/// Gets the text that sets the scroll region to the rows below the
+/// provided number of pinned rows, or resets it when there are none.
+/// Setting the scroll region moves the cursor, so it's restored after.
+fn scroll_region_text(pinned_rows: u16, total_rows: u32) -> String {
+  if pinned_rows == 0 {
+    format!("{}{}{}", CURSOR_SAVE, SCROLL_REGION_RESET, CURSOR_RESTORE)
+  } else {
+    format!(
+      "{}\x1b[{};{}r{}",
+      CURSOR_SAVE,
+      pinned_rows as u32 + 1,
+      total_rows,
+      CURSOR_RESTORE
+    )
+  }
+}
+
+const SCROLL_REGION_RESET: &str = "\x1b[r";
+
+/// How the frames are output to the terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderMode {
+  /// The region is redrawn in place.
+  #[default]
+  Static,
+  /// Each frame is appended below the previous output without moving
+  /// the cursor, which gives a scrolling log of the progress for
+  /// terminals that misbehave with cursor movement. Frames are emitted
+  /// at a slower interval and unchanged frames are skipped.
+  AppendOnly,
+}
+
+/// What's drawn when every entry renders empty text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyFrame {
+  /// The region is cleared until an entry renders text again.
+  #[default]
+  Clear,
+  /// The last frame with text stays drawn.
+  Keep,
+}
+
+/// Whether each frame is drawn using the terminal's synchronized output
+/// mode (DEC 2026), which draws the whole frame at once rather than
+/// partway through clearing and redrawing it.
+// forcing it is only done by the callers of `set_synchronized_output`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SynchronizedOutput {
+  /// Used when the terminal is detected to support it via
+  /// `TerminalCaps::synchronized_output`.
+  #[default]
+  Auto,
+  On,
+  Off,
+}
+
+const SYNCHRONIZED_UPDATE_BEGIN: &str = "\x1b[?2026h";
+const SYNCHRONIZED_UPDATE_END: &str = "\x1b[?2026l";
+
+/// The order the entries are drawn in.
+// the variants are picked by the callers of `set_order_direction`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderDirection {
+  /// The oldest entry is drawn at the top.
+  #[default]
+  Oldest,
+  /// The newest entry is drawn at the top, such as for log-like views.
+  Newest,
+}
+
+/// How lines of the rendered text that are wider than the
+/// console are handled.
+// the variants are picked by the callers of `set_wrap_mode`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WrapMode {
+  /// The lines are left as-is for the terminal to wrap.
+  #[default]
+  None,
+  /// The lines are cut off at the width of the console.
+  Truncate,
+  /// The lines are cut off at the width of the console with an ellipsis
+  /// placed where the characters were removed.
+  Ellipsis(text::Ellipsis),
+  /// The lines are wrapped onto additional rows at word boundaries.
+  Wrap,
+}
+
+impl WrapMode {
+  fn apply(&self, text: &str, cols: usize) -> Option<String> {
+    match self {
+      WrapMode::None => None,
+      WrapMode::Truncate => Some(
+        text
+          .split('\n')
+          .map(|line| truncate_to_width(line, cols))
+          .collect::<Vec<_>>()
+          .join("\n"),
+      ),
+      WrapMode::Ellipsis(placement) => Some(
+        text
+          .split('\n')
+          .map(|line| truncate_with_ellipsis(line, cols, *placement))
+          .collect::<Vec<_>>()
+          .join("\n"),
+      ),
+      WrapMode::Wrap => Some(wrap_to_width(text, cols)),
+    }
+  }
+}
+
+/// Statistics about the current draw thread. These are
+/// reset each time a new draw thread is started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrawStats {
+  /// Number of frames that were drawn.
+  pub frames_rendered: u64,
+  /// Number of times the loop fell behind because rendering a frame
+  /// took long enough that the time since the previous frame exceeded
+  /// twice the draw interval.
+  pub dropped_frames: u64,
+  /// Moving average of the latency between a renderer's version
+  /// changing and the first frame drawn with that version.
+  ///
+  /// The latency is measured from the version's `changed_at` to when
+  /// the frame is written, for each entry whose version differs from
+  /// the one in the previous frame it was drawn in. An entry's first
+  /// frame isn't measured, and when several updates share a version
+  /// only the latest one is measured, so earlier updates may have
+  /// waited longer.
+  pub average_update_latency: Duration,
+  /// The longest latency measured for `average_update_latency`.
+  pub max_update_latency: Duration,
+  /// Number of latencies measured for `average_update_latency`.
+  pub update_latency_samples: u64,
+}
+
+impl DrawStats {
+  fn record_update_latency(&mut self, latency: Duration) {
+    // an exponential moving average, which weighs recent frames more
+    self.average_update_latency = if self.update_latency_samples == 0 {
+      latency
+    } else {
+      (self.average_update_latency * 7 + latency) / 8
+    };
+    self.max_update_latency = self.max_update_latency.max(latency);
+    self.update_latency_samples += 1;
+  }
+}
+
+/// The record of an entry that finished, which is collected
+/// when `DrawThread::set_record_completions` is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryCompletion {
+  pub id: u16,
+  /// The tag the entry was added with, if any.
+  pub tag: Option<String>,
+  /// How long the entry existed for.
+  pub elapsed: Duration,
+  /// The text of the entry in the last frame drawn, without styles.
+  pub text: String,
+  pub failed: bool,
+}
+
+/// Draw thread guard. Keep this alive for the duration
+/// that you wish the entry to be drawn for. Once it is
+/// dropped, then the entry will be removed from the draw
+/// thread.
+#[derive(Debug)]
+pub struct DrawThreadGuard {
+  /// The id of the entry, which is `None` when it was added while the
+  /// draw thread was shutting down and so the guard does nothing.
+  id: Option<u16>,
+  instance: DrawThreadInstance,
+  /// Set by `linger`, after which the entry is removed once it expires
+  /// rather than when this is dropped.
+  lingering: bool,
+}
+
+impl Drop for DrawThreadGuard {
+  fn drop(&mut self) {
+    if let (Some(id), false) = (self.id, self.lingering) {
+      self.instance.finish_entry(id)
+    }
+  }
+}
+
+// not every caller expands or lingers its entry
+#[allow(dead_code)]
+impl DrawThreadGuard {
+  /// Temporarily makes this entry the only one that's drawn so that
+  /// it may use the full height of the terminal. The other entries
+  /// are drawn again once the returned guard is dropped.
+  ///
+  /// When multiple entries are expanded, the most recent expand wins.
+  pub fn expand(&self) -> ExpandGuard {
+    let mut internal_state = self.instance.state.lock();
+    let id = internal_state.next_expand_id;
+    internal_state.next_expand_id += 1;
+    if let Some(entry_id) = self.id {
+      internal_state
+        .expanded_entries
+        .push(ExpandedEntry { id, entry_id });
+    }
+    ExpandGuard {
+      id,
+      instance: self.instance.clone(),
+    }
+  }
+
+  /// Keeps the entry drawn for the provided duration before it's
+  /// removed instead of removing it immediately, such as to briefly
+  /// leave the final result of an operation on the screen.
+  pub fn linger(mut self, duration: Duration) {
+    // the entry is removed once it expires instead
+    self.lingering = true;
+    let mut internal_state = self.instance.state.lock();
+    if let Some(entry) =
+      self.id.and_then(|id| internal_state.entries.get_mut(id))
+    {
+      entry.expires_at = Some(Instant::now() + duration);
+    }
+  }
+
+  /// Indents every line of the entry by the provided number of spaces,
+  /// such as to nest the entries of sub-tasks under their parent. The
+  /// renderer is provided the console's width minus the indent.
+  pub fn set_indent(&self, indent: u16) {
+    let mut internal_state = self.instance.state.lock();
+    if let Some(entry) =
+      self.id.and_then(|id| internal_state.entries.get_mut(id))
+    {
+      entry.indent = indent;
+    }
+  }
+
+  /// Moves the entry so that it's drawn above the other entries, such
+  /// as to surface the most recently active task. This does nothing
+  /// once the entry is finished.
+  pub fn move_to_front(&self) {
+    let Some(id) = self.id else {
+      return;
+    };
+    let mut internal_state = self.instance.state.lock();
+    match internal_state.order_direction {
+      OrderDirection::Oldest => internal_state.entries.move_to_front(id),
+      OrderDirection::Newest => internal_state.entries.move_to_back(id),
+    };
+  }
+
+  /// Moves the entry so that it's drawn below the other entries. This
+  /// does nothing once the entry is finished.
+  pub fn move_to_back(&self) {
+    let Some(id) = self.id else {
+      return;
+    };
+    let mut internal_state = self.instance.state.lock();
+    match internal_state.order_direction {
+      OrderDirection::Oldest => internal_state.entries.move_to_back(id),
+      OrderDirection::Newest => internal_state.entries.move_to_front(id),
+    };
+  }
+
+  /// Sets whether the entry is rendered with more detail via
+  /// `DrawThreadRenderer::render_verbose`, such as once its task is
+  /// taking unusually long, while the other entries stay compact. The
+  /// extra lines take rows from the other entries like any other line.
+  pub fn set_verbose(&self, value: bool) {
+    let mut internal_state = self.instance.state.lock();
+    if let Some(entry) =
+      self.id.and_then(|id| internal_state.entries.get_mut(id))
+    {
+      entry.verbose = value;
+    }
+  }
+
+  /// Gets how long ago this entry was added to the draw thread.
+  pub fn elapsed(&self) -> Duration {
+    let internal_state = self.instance.state.lock();
+    self
+      .id
+      .and_then(|id| internal_state.entries.get(id))
+      .map(|e| e.added_at.elapsed())
+      .unwrap_or_default()
+  }
+}
+
+/// Keeps an entry expanded for as long as it is alive.
+#[derive(Debug)]
+pub struct ExpandGuard {
+  id: usize,
+  instance: DrawThreadInstance,
+}
+
+impl Drop for ExpandGuard {
+  fn drop(&mut self) {
+    let mut internal_state = self.instance.state.lock();
+    internal_state.expanded_entries.retain(|e| e.id != self.id);
+  }
+}
+
+#[derive(Debug)]
+struct ExpandedEntry {
+  id: usize,
+  entry_id: u16,
+}
+
+#[derive(Debug)]
+struct AttachedTicker {
+  id: usize,
+  /// Stops the ticker's task.
+  token: CancellationToken,
+  /// The manual mode from before the first of the tickers that replaced
+  /// each other was attached, which is restored once it's detached.
+  previous_manual: bool,
+}
+
+#[derive(Debug, Clone)]
+struct InternalEntry {
+  id: u16,
+  added_at: Instant,
+  renderer: Arc<dyn DrawThreadRenderer>,
+  /// When the entry is automatically removed.
+  expires_at: Option<Instant>,
+  /// Cancelled once the entry is removed in order to stop the task
+  /// watching the entry's cancellation token.
+  watcher_token: Option<CancellationToken>,
+  /// The category of the entry for `DrawThread::hide_tag`.
+  tag: Option<Arc<str>>,
+  /// The number of spaces each line of the entry is indented by.
+  indent: u16,
+  /// Whether the entry is drawn in a frame, for `DrawThread::add_entry_if`.
+  predicate: Option<EntryPredicate>,
+  /// The rows of the frame the entry occupies, for
+  /// `DrawThread::add_entry_at_rows`.
+  rows: Option<Range<u16>>,
+  /// Whether the entry is rendered via `render_verbose`.
+  verbose: bool,
+  /// The span the entry was added in, if associated with one.
+  #[cfg(feature = "tracing")]
+  span: Option<tracing::Span>,
+}
+
+impl InternalEntry {
+  /// Gets whether the entry is drawn in a frame of the provided size,
+  /// which calls into the predicate, so call this outside the lock.
+  fn is_drawn(&self, size: &ConsoleSize) -> bool {
+    self.renderer.min_cols() as u32 <= size.cols
+      && self
+        .predicate
+        .as_ref()
+        .is_none_or(|predicate| (predicate.0)())
+  }
+
+  /// Renders the entry at the provided size, which calls into the
+  /// renderer, so call this outside the lock.
+  fn render(
+    &self,
+    size: &ConsoleSize,
+    frame: u64,
+    context: &RenderContext,
+  ) -> String {
+    if self.verbose {
+      self.renderer.render_verbose(size)
+    } else {
+      self.renderer.render_with_context(size, frame, context)
+    }
+  }
+}
+
+/// The attributes an entry is added with, which are set before the
+/// entry can be drawn.
+#[derive(Debug, Default)]
+struct EntryAttributes {
+  expires_at: Option<Instant>,
+  watcher_token: Option<CancellationToken>,
+  tag: Option<Arc<str>>,
+  predicate: Option<EntryPredicate>,
+  rows: Option<Range<u16>>,
+  #[cfg(feature = "tracing")]
+  span: Option<tracing::Span>,
+}
+
+#[derive(Clone)]
+struct EntryPredicate(Arc<dyn Fn() -> bool + Send + Sync>);
+
+impl std::fmt::Debug for EntryPredicate {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("EntryPredicate").finish()
+  }
+}
+
+#[derive(Debug)]
+struct InternalState {
+  // this ensures only one actual draw thread is running
+  drawer_id: usize,
+  hide_count: usize,
+  suspend_count: usize,
+  has_draw_thread: bool,
+  /// When set, the caller draws frames via `DrawThread::tick()`
+  /// instead of a draw thread being spawned.
+  manual: bool,
+  /// Set by `DrawThread::shutdown`, after which entries aren't added.
+  shutting_down: bool,
+  manual_pass_state: Option<RenderPassState>,
+  next_ticker_id: usize,
+  /// The ticker driving the manual mode, if any.
+  ticker: Option<AttachedTicker>,
+  taskbar_progress: bool,
+  is_showing_taskbar_progress: bool,
+  clear_strategy: ClearStrategy,
+  join_mode: JoinMode,
+  line_ending: LineEnding,
+  render_delay: Duration,
+  /// The frame number of the next render pass.
+  next_frame: u64,
+  table_align: bool,
+  collapse_duplicates: bool,
+  wrap_mode: WrapMode,
+  order_direction: OrderDirection,
+  debug_overlay: bool,
+  empty_frame: EmptyFrame,
+  synchronized_output: SynchronizedOutput,
+  /// The output of the synchronized update being written, which is
+  /// written to the terminal once it ends.
+  synchronized_update: Option<String>,
+  render_mode: RenderMode,
+  /// The text last appended in `RenderMode::AppendOnly`
+  /// and when the last frame was emitted.
+  last_appended: Option<(String, Instant)>,
+  stale_timeout: Option<Duration>,
+  render_timeout: Option<Duration>,
+  render_workers: Arc<Mutex<RenderWorkers>>,
+  viewport: Option<Viewport>,
+  pin: Pin,
+  /// The number of pinned rows and the number of rows of the terminal the
+  /// scroll region was set for while `Pin::Top` is drawn.
+  scroll_region: Option<(u16, u32)>,
+  fallback: Fallback,
+  /// The last drawn text and when it last changed.
+  last_frame_change: Option<(String, Instant)>,
+  context: Arc<RenderContext>,
+  /// The maximum number of lines kept in `log_lines`.
+  managed_log_lines: usize,
+  /// The most recent lines of `DrawThread::log`, which are drawn
+  /// above the entries.
+  log_lines: VecDeque<String>,
+  /// The line printed once the last entry finishes.
+  pending_summary: Option<String>,
+  /// Whether the draw thread backs off while many lines are printed.
+  output_backpressure: bool,
+  /// Whether the draw interval lengthens while starved of CPU.
+  adaptive_interval: bool,
+  print_rate: backpressure::PrintRate,
+  /// Whether finished entries are recorded in `completions`.
+  record_completions: bool,
+  completions: VecDeque<EntryCompletion>,
+  /// The maximum number of completions and log lines that are kept.
+  history_limit: usize,
+  /// Whether the last render pass wrote its frame to the terminal.
+  last_frame_emitted: bool,
+  stats: DrawStats,
+  next_entry_id: u16,
+  next_expand_id: usize,
+  entries: EntryList<InternalEntry>,
+  /// The tags of the entries that aren't drawn.
+  hidden_tags: HashSet<Arc<str>>,
+  /// The text each live entry rendered in the last frame.
+  last_rendered: HashMap<u16, String>,
+  /// The version each live entry had in the last frame it was drawn in.
+  drawn_versions: HashMap<u16, u64>,
+  next_sink_id: usize,
+  /// Additional sinks that receive each frame drawn to the terminal.
+  sinks: Vec<(usize, Box<dyn FrameSink>)>,
+  /// Called after the drawn text is cleared.
+  clear_hook: Option<ClearHook>,
+  /// Stack of expanded entries where the last one that's
+  /// still alive is the only entry drawn.
+  expanded_entries: Vec<ExpandedEntry>,
+  size_provider: fn() -> Option<ConsoleSize>,
+  /// Used instead of the size of the console when set.
+  size_override: Option<ConsoleSize>,
+  /// The maximum number of columns provided to the renderers.
+  max_cols: Option<u16>,
+  /// Only the latest entry is drawn on a single line when the console
+  /// has fewer rows than this.
+  min_rows_required: u16,
+  /// Drawn instead of the entries when set.
+  frame_override: Option<String>,
+  output: TerminalOutput,
+  /// Whether the number of drawn rows is recorded for clearing them when
+  /// the process is interrupted. The signal handlers can't acquire the
+  /// lock of an instance, so this is only done for the default instance.
+  records_drawn_rows: bool,
+  /// Notified when the region is cleared, which is shared with the
+  /// `DrawThreadInstance` so the waiters don't need the lock.
+  region_cleared: Arc<Notify>,
+  /// The text drawn by the static text and the size it was drawn for.
+  last_drawn: Option<(String, ConsoleSize)>,
+  /// Where the JSON lines fallback is written to, which is stdout.
+  json_output: TerminalOutput,
+  static_text: ConsoleStaticText,
+}
+
+/// Where the frames are written to.
+struct TerminalOutput(Box<dyn Write + Send>);
+
+impl std::fmt::Debug for TerminalOutput {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("TerminalOutput").finish()
+  }
+}
+
+/// The hook of `DrawThread::set_clear_hook`.
+struct ClearHook(Box<dyn Fn() + Send + Sync>);
+
+impl std::fmt::Debug for ClearHook {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ClearHook").finish()
+  }
+}
+
+impl InternalState {
+  fn new() -> Self {
+    Self {
+      region_cleared: Default::default(),
+      drawer_id: 0,
+      hide_count: 0,
+      suspend_count: 0,
+      has_draw_thread: false,
+      manual: false,
+      shutting_down: false,
+      manual_pass_state: None,
+      next_ticker_id: 0,
+      ticker: None,
+      taskbar_progress: false,
+      is_showing_taskbar_progress: false,
+      clear_strategy: ClearStrategy::Diff,
+      join_mode: JoinMode::SkipEmpty,
+      line_ending: LineEnding::Lf,
+      render_delay: Duration::ZERO,
+      next_frame: 0,
+      table_align: false,
+      collapse_duplicates: false,
+      wrap_mode: WrapMode::None,
+      order_direction: OrderDirection::Oldest,
+      debug_overlay: false,
+      empty_frame: EmptyFrame::Clear,
+      synchronized_output: SynchronizedOutput::Auto,
+      synchronized_update: None,
+      render_mode: RenderMode::Static,
+      last_appended: None,
+      stale_timeout: None,
+      render_timeout: None,
+      render_workers: Default::default(),
+      viewport: None,
+      pin: Pin::Bottom,
+      scroll_region: None,
+      fallback: Fallback::None,
+      last_frame_change: None,
+      context: Default::default(),
+      managed_log_lines: 0,
+      log_lines: VecDeque::new(),
+      pending_summary: None,
+      output_backpressure: false,
+      adaptive_interval: false,
+      print_rate: Default::default(),
+      record_completions: false,
+      completions: VecDeque::new(),
+      history_limit: DEFAULT_HISTORY_LIMIT,
+      last_frame_emitted: false,
+      stats: Default::default(),
+      entries: Default::default(),
+      hidden_tags: HashSet::new(),
+      last_rendered: HashMap::new(),
+      drawn_versions: HashMap::new(),
+      next_sink_id: 0,
+      sinks: Vec::new(),
+      clear_hook: None,
+      expanded_entries: Vec::new(),
+      next_entry_id: 0,
+      next_expand_id: 0,
+      size_provider: console_size,
+      size_override: None,
+      max_cols: None,
+      min_rows_required: 0,
+      frame_override: None,
+      output: TerminalOutput(Box::new(std::io::stderr())),
+      records_drawn_rows: false,
+      last_drawn: None,
+      json_output: TerminalOutput(Box::new(std::io::stdout())),
+      static_text: new_static_text(),
+    }
+  }
+
+  pub fn should_exit_draw_thread(&self, drawer_id: usize) -> bool {
+    self.drawer_id != drawer_id || self.entries.is_empty()
+  }
+
+  fn entries_to_render(&self) -> Vec<InternalEntry> {
+    let expanded_entry = self
+      .expanded_entries
+      .iter()
+      .rev()
+      .find_map(|e| self.entries.get(e.entry_id));
+    let is_visible = |entry: &&InternalEntry| {
+      entry
+        .tag
+        .as_ref()
+        .is_none_or(|tag| !self.hidden_tags.contains(tag))
+    };
+    let entries = match expanded_entry {
+      Some(entry) => vec![entry.clone()],
+      None => match self.order_direction {
+        OrderDirection::Oldest => {
+          self.entries.iter().filter(is_visible).cloned().collect()
+        }
+        OrderDirection::Newest => self
+          .entries
+          .iter()
+          .rev()
+          .filter(is_visible)
+          .cloned()
+          .collect(),
+      },
+    };
+    if self.render_delay.is_zero() {
+      entries
+    } else {
+      entries
+        .into_iter()
+        .filter(|e| e.added_at.elapsed() >= self.render_delay)
+        .collect()
+    }
+  }
+
+  /// Removes the oldest completions and log lines beyond their limits.
+  fn trim_history(&mut self) {
+    let max_log_lines = self.managed_log_lines.min(self.history_limit);
+    let len = self.log_lines.len();
+    self.log_lines.drain(..len.saturating_sub(max_log_lines));
+    let len = self.completions.len();
+    self
+      .completions
+      .drain(..len.saturating_sub(self.history_limit));
+  }
+
+  /// Removes the entries that have expired, returning
+  /// whether any were removed.
+  fn remove_expired_entries(&mut self, now: Instant) -> bool {
+    let len = self.entries.len();
+    let mut removed_ids = Vec::new();
+    self.entries.retain(|e| {
+      let is_expired = e.expires_at.is_some_and(|expires_at| expires_at <= now);
+      if is_expired {
+        removed_ids.push(e.id);
+        if let Some(watcher_token) = &e.watcher_token {
+          watcher_token.cancel();
+        }
+      }
+      !is_expired
+    });
+    for id in removed_ids {
+      self.last_rendered.remove(&id);
+      self.drawn_versions.remove(&id);
+    }
+    self.entries.len() != len
+  }
+
+  fn is_backing_off(&self, now: Instant) -> bool {
+    self.output_backpressure && self.print_rate.is_high(now)
+  }
+
+  /// Stores the text rendered for each entry outside the lock, ignoring
+  /// entries that were finished while rendering.
+  fn cache_rendered(&mut self, rendered: Vec<(u16, String)>) {
+    for (id, text) in rendered {
+      if self.entries.get(id).is_some() {
+        self.last_rendered.insert(id, text);
+      }
+    }
+  }
+
+  /// Records the latency of the entries whose version changed since the
+  /// last frame they were drawn in.
+  fn record_drawn_versions(
+    &mut self,
+    versions: Vec<(u16, RenderVersion)>,
+    now: Instant,
+  ) {
+    for (id, version) in versions {
+      if self.entries.get(id).is_none() {
+        continue;
+      }
+      let previous = self.drawn_versions.insert(id, version.number);
+      if previous.is_some_and(|number| number != version.number) {
+        self.stats.record_update_latency(
+          now.saturating_duration_since(version.changed_at),
+        );
+      }
+    }
+  }
+
+  /// Appends a marker to the text when it hasn't changed within
+  /// the stale timeout, which surfaces hung operations.
+  fn mark_if_stale(&mut self, text: String, now: Instant) -> String {
+    let changed_at = match &self.last_frame_change {
+      Some((last_text, changed_at)) if *last_text == text => *changed_at,
+      _ => {
+        self.last_frame_change = Some((text.clone(), now));
+        return text;
+      }
+    };
+    match self.stale_timeout {
+      Some(timeout) if now - changed_at >= timeout => {
+        let marker = format!(
+          "(no updates for {})",
+          progress::format_duration(
+            now - changed_at,
+            progress::DurationFormat::Compact
+          )
+        );
+        format!("{}\n{}", text, colors::gray(marker))
+      }
+      _ => text,
+    }
+  }
+
+  fn uses_json_lines(&self) -> bool {
+    match self.fallback {
+      Fallback::None => false,
+      Fallback::JsonLines => !DrawThread::is_supported(),
+      Fallback::ForceJsonLines => true,
+    }
+  }
+
+  fn write_json_lines(&mut self, text: &str) {
+    let _ = self.json_output.0.write_all(text.as_bytes());
+    let _ = self.json_output.0.flush();
+  }
+
+  fn console_size(&self) -> Option<ConsoleSize> {
+    self.size_override.or_else(self.size_provider)
+  }
+
+  fn set_drawn_row_count(&self, count: usize) {
+    if self.records_drawn_rows {
+      signal::set_drawn_row_count(count);
+    }
+  }
+
+  fn set_scroll_region_set(&self, value: bool) {
+    if self.records_drawn_rows {
+      signal::set_scroll_region_set(value);
+    }
+  }
+
+  fn write_terminal(&mut self, text: &str) {
+    if let Some(update) = &mut self.synchronized_update {
+      update.push_str(text);
+      return;
+    }
+    let text = self.line_ending.apply(text);
+    let _ = self.output.0.write_all(text.as_bytes());
+    let _ = self.output.0.flush();
+  }
+
+  /// Starts collecting the output written to the terminal until
+  /// `end_synchronized_update`, when synchronized output is used.
+  fn begin_synchronized_update(&mut self) {
+    let is_used = match self.synchronized_output {
+      SynchronizedOutput::Auto => TERMINAL_CAPS.synchronized_output,
+      SynchronizedOutput::On => true,
+      SynchronizedOutput::Off => false,
+    };
+    if is_used {
+      self.synchronized_update = Some(String::new());
+    }
+  }
+
+  /// Writes the output collected since `begin_synchronized_update`
+  /// between the sequences that draw it at once.
+  fn end_synchronized_update(&mut self) {
+    let Some(update) = self.synchronized_update.take() else {
+      return;
+    };
+    if !update.is_empty() {
+      self.write_terminal(&format!(
+        "{}{}{}",
+        SYNCHRONIZED_UPDATE_BEGIN, update, SYNCHRONIZED_UPDATE_END
+      ));
+    }
+  }
+
+  /// Draws the text, returning whether anything was written, which isn't
+  /// the case when the text is unchanged from the last drawn text.
+  fn draw_text(&mut self, text: &str, size: ConsoleSize) -> bool {
+    if self.render_mode == RenderMode::AppendOnly {
+      let is_changed = self
+        .last_appended
+        .as_ref()
+        .map(|(last_text, _)| last_text != text)
+        .unwrap_or(true);
+      let is_emitted = is_changed && !text.is_empty();
+      if is_emitted {
+        self.write_terminal(&format!("{}\n", text));
+      }
+      self.last_appended = Some((text.to_string(), Instant::now()));
+      return is_emitted;
+    }
+    if let Some(viewport) = self.viewport {
+      let text = viewport.draw_text(text);
+      self.write_terminal(&text);
+      return true;
+    }
+    if self.pin == Pin::Top {
+      self.draw_pinned_text(text, size);
+      return true;
+    }
+    // break the lines at the column boundary like the terminal would so
+    // that the number of rows is known exactly, which the static text
+    // measures differently for some wide characters, such as "❤️"
+    let cols = size.cols as usize;
+    let text = if text.split('\n').any(|line| display_width(line) > cols) {
+      Cow::Owned(text::break_at_width(text, cols))
+    } else {
+      Cow::Borrowed(text)
+    };
+    let text = text.as_ref();
+    let rendered = self
+      .static_text
+      .render_with_size(text, to_static_text_size(Some(size)));
+    if let Some(text) = &rendered {
+      self.write_terminal(text);
+    }
+    self.set_drawn_row_count(row_count(text, &size));
+    self.last_drawn = Some((text.to_string(), size));
+    rendered.is_some()
+  }
+
+  /// Draws the text in the top rows of the terminal, keeping at least one
+  /// row below it for the scroll region.
+  fn draw_pinned_text(&mut self, text: &str, size: ConsoleSize) {
+    let rows = (text.lines().count() as u32).min(size.rows.saturating_sub(1));
+    let rows = rows as u16;
+    let mut output = String::new();
+    if self.scroll_region != Some((rows, size.rows)) {
+      if let Some((previous_rows, _)) = self.scroll_region {
+        // blank the rows that are no longer pinned
+        let viewport = Viewport::pinned(previous_rows, size.cols);
+        output.push_str(&viewport.clear_text());
+      }
+      output.push_str(&scroll_region_text(rows, size.rows));
+      self.scroll_region = (rows > 0).then_some((rows, size.rows));
+      self.set_scroll_region_set(rows > 0);
+    }
+    output.push_str(&Viewport::pinned(rows, size.cols).draw_text(text));
+    self.write_terminal(&output);
+  }
+
+  /// Clears the region until the draw thread draws again, notifying
+  /// the waiters of `DrawThreadInstance::on_next_clear`.
+  fn clear_region(&mut self) {
+    self.clear_drawn_text();
+    self.region_cleared.notify_waiters();
+  }
+
+  /// Clears the text currently drawn in the terminal, such as
+  /// before drawing it again.
+  fn clear_drawn_text(&mut self) {
+    if self.render_mode == RenderMode::AppendOnly {
+      // appended frames stay in the output
+      return;
+    }
+    if self.write_clear_text() {
+      if let Some(hook) = &self.clear_hook {
+        (hook.0)();
+      }
+    }
+  }
+
+  /// Writes the text that clears the drawn text, returning whether
+  /// anything was written.
+  fn write_clear_text(&mut self) -> bool {
+    if let Some((rows, total_rows)) = self.scroll_region.take() {
+      let cols = self.console_size().map(|s| s.cols).unwrap_or(0);
+      let viewport = Viewport::pinned(rows, cols);
+      let text = format!(
+        "{}{}",
+        viewport.clear_text(),
+        scroll_region_text(0, total_rows)
+      );
+      self.write_terminal(&text);
+      self.set_scroll_region_set(false);
+      return true;
+    }
+    if let Some(viewport) = self.viewport {
+      self.write_terminal(&viewport.clear_text());
+      return true;
+    }
+    let size = to_static_text_size(self.console_size());
+    let text = self.static_text.render_clear_with_size(size);
+    if let Some(text) = &text {
+      self.write_terminal(text);
+    }
+    self.set_drawn_row_count(0);
+    self.last_drawn = None;
+    text.is_some()
+  }
+
+  fn clear_sinks(&mut self) {
+    for (_, sink) in &mut self.sinks {
+      sink.clear();
+    }
+  }
+
+  fn clear_taskbar_progress(&mut self) {
+    if self.is_showing_taskbar_progress {
+      self.write_terminal(TASKBAR_PROGRESS_CLEAR);
+      self.is_showing_taskbar_progress = false;
+    }
+  }
+}
+
+/// OSC 9;4 sequence that removes the progress from the terminal's taskbar.
+const TASKBAR_PROGRESS_CLEAR: &str = "\x1b]9;4;0;0\x07";
+
+/// A thread that renders the entries while a render timeout is set,
+/// which is reused across the entries and frames.
+#[derive(Debug)]
+struct RenderWorker {
+  jobs: mpsc::Sender<RenderJob>,
+  results: mpsc::Receiver<String>,
+}
+
+struct RenderJob {
+  entry: InternalEntry,
+  size: ConsoleSize,
+  frame: u64,
+  context: Arc<RenderContext>,
+}
+
+impl RenderWorker {
+  fn spawn() -> Self {
+    let (jobs, job_receiver) = mpsc::channel::<RenderJob>();
+    let (result_sender, results) = mpsc::channel();
+    std::thread::spawn(move || {
+      let _flag = DrawThreadFlag::set();
+      while let Ok(job) = job_receiver.recv() {
+        let text = job.entry.render(&job.size, job.frame, &job.context);
+        if result_sender.send(text).is_err() {
+          break;
+        }
+      }
+    });
+    Self { jobs, results }
+  }
+}
+
+/// The render workers of the draw thread. A worker whose entry doesn't
+/// render in time is left to finish that render, so a new worker is
+/// only spawned after an entry times out.
+#[derive(Debug, Default)]
+struct RenderWorkers {
+  idle: Option<RenderWorker>,
+  /// The workers still rendering an entry that timed out, keyed by the
+  /// entry id, which provide the text once it's rendered.
+  pending: HashMap<u16, RenderWorker>,
+}
+
+impl RenderWorkers {
+  fn clear(&mut self) {
+    self.idle = None;
+    self.pending.clear();
+  }
+
+  fn take_idle(&mut self) -> RenderWorker {
+    self.idle.take().unwrap_or_else(RenderWorker::spawn)
+  }
+
+  fn release(&mut self, worker: RenderWorker) {
+    if self.idle.is_none() {
+      self.idle = Some(worker);
+    }
+  }
+}
+
+/// Renders the entries on the render workers, waiting up until the
+/// timeout for each of them. The entries that don't render in time use
+/// their cached text and, since the render can't be interrupted, aren't
+/// rendered again until the pending render finishes. Returns the ids of
+/// the entries that timed out in this frame along with the texts.
+fn render_entries_with_timeout(
+  entries: &[InternalEntry],
+  size: &ConsoleSize,
+  frame: u64,
+  context: &Arc<RenderContext>,
+  timeout: Duration,
+  cached_texts: &HashMap<u16, String>,
+  render_workers: &Mutex<RenderWorkers>,
+) -> (Vec<(u16, String)>, Vec<u16>) {
+  let mut workers = render_workers.lock();
+  workers
+    .pending
+    .retain(|id, _| entries.iter().any(|e| e.id == *id));
+  let mut timed_out_ids = Vec::new();
+  let rendered = entries
+    .iter()
+    .map(|entry| {
+      let cached_text =
+        || cached_texts.get(&entry.id).cloned().unwrap_or_default();
+      if let Some(worker) = workers.pending.remove(&entry.id) {
+        // don't wait again on a render that already timed out
+        let text = match worker.results.try_recv() {
+          Ok(text) => {
+            workers.release(worker);
+            text
+          }
+          Err(mpsc::TryRecvError::Empty) => {
+            workers.pending.insert(entry.id, worker);
+            cached_text()
+          }
+          // the renderer panicked
+          Err(mpsc::TryRecvError::Disconnected) => cached_text(),
+        };
+        return (entry.id, text);
+      }
+
+      let worker = workers.take_idle();
+      let job = RenderJob {
+        entry: entry.clone(),
+        size: indented_size(size, entry.indent),
+        frame,
+        context: context.clone(),
+      };
+      if worker.jobs.send(job).is_err() {
+        return (entry.id, cached_text());
+      }
+      let text = match worker.results.recv_timeout(timeout) {
+        Ok(text) => {
+          workers.release(worker);
+          text
+        }
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+          timed_out_ids.push(entry.id);
+          workers.pending.insert(entry.id, worker);
+          cached_text()
+        }
+        // the renderer panicked
+        Err(mpsc::RecvTimeoutError::Disconnected) => cached_text(),
+      };
+      (entry.id, text)
+    })
+    .collect();
+  (rendered, timed_out_ids)
+}
+
+/// Renders the entries. This must be called outside the
+/// internal state lock.
+fn render_entries(
+  entries: &[InternalEntry],
+  size: &ConsoleSize,
+  frame: u64,
+  context: &RenderContext,
+) -> Vec<(u16, String)> {
+  entries
+    .iter()
+    .map(|entry| {
+      let size = indented_size(size, entry.indent);
+      let text = entry.render(&size, frame, context);
+      (entry.id, text)
+    })
+    .collect()
+}
+
+/// Provides the counts of the summary contributions of the entries to
+/// the renderers as a value of the context.
+fn with_summary_counts(
+  entries: &[InternalEntry],
+  context: Arc<RenderContext>,
+) -> Arc<RenderContext> {
+  let mut counts = summary::SummaryCounts::default();
+  for part in entries
+    .iter()
+    .filter_map(|e| e.renderer.summary_contribution())
+  {
+    counts.add(part);
+  }
+  if counts.total() == 0 {
+    return context;
+  }
+  let mut context = (*context).clone();
+  context.set(Arc::new(counts));
+  Arc::new(context)
+}
+
+/// Takes the most recently added of the entries, which are ordered
+/// according to the order direction.
+fn latest_entry(
+  mut entries: Vec<InternalEntry>,
+  order_direction: OrderDirection,
+) -> Option<InternalEntry> {
+  match order_direction {
+    OrderDirection::Oldest => entries.pop(),
+    OrderDirection::Newest => entries.into_iter().next(),
+  }
+}
+
+/// The size provided to the renderer of an entry with the indent.
+fn indented_size(size: &ConsoleSize, indent: u16) -> ConsoleSize {
+  ConsoleSize {
+    cols: size.cols.saturating_sub(indent as u32),
+    rows: size.rows,
+  }
+}
+
+/// Indents the non-empty lines of the text by the provided
+/// number of spaces.
+fn indent_lines(text: &str, indent: u16) -> Cow<str> {
+  if indent == 0 {
+    return Cow::Borrowed(text);
+  }
+  let prefix = " ".repeat(indent as usize);
+  let lines = text
+    .split('\n')
+    .map(|line| {
+      if line.is_empty() {
+        Cow::Borrowed(line)
+      } else {
+        Cow::Owned(format!("{}{}", prefix, line))
+      }
+    })
+    .collect::<Vec<_>>();
+  Cow::Owned(lines.join("\n"))
+}
+
+/// Aligns the columns and wraps the rendered text of the entries.
+fn format_rendered(
+  entries: &[InternalEntry],
+  rendered: &mut [(u16, String)],
+  size: &ConsoleSize,
+  table_align: bool,
+  wrap_mode: WrapMode,
+) {
+  if table_align {
+    align_rendered_columns(rendered);
+  }
+  for (entry, (_, text)) in entries.iter().zip(rendered) {
+    let cols = indented_size(size, entry.indent).cols as usize;
+    if let Some(wrapped) = wrap_mode.apply(text, cols) {
+      *text = wrapped;
+    }
+  }
+}
+
+/// Indents and joins the rendered text of the entries, collapsing the
+/// identical adjacent lines when enabled.
+fn join_rendered(
+  entries: &[InternalEntry],
+  rendered: &[(u16, String)],
+  join_mode: JoinMode,
+  collapse_duplicates: bool,
+) -> String {
+  let text = join_rendered_rows(entries, rendered, join_mode);
+  if collapse_duplicates {
+    collapse_duplicate_lines(&text)
+  } else {
+    text
+  }
+}
+
+/// Coalesces each run of identical adjacent lines into its first line
+/// with a count, such as "waiting for lock (x3)". Empty lines are kept
+/// as they are.
+fn collapse_duplicate_lines(text: &str) -> String {
+  let mut lines: Vec<(&str, usize)> = Vec::new();
+  for line in text.split('\n') {
+    match lines.last_mut() {
+      Some((last, count)) if *last == line && !line.is_empty() => *count += 1,
+      _ => lines.push((line, 1)),
+    }
+  }
+  lines
+    .into_iter()
+    .map(|(line, count)| match count {
+      1 => Cow::Borrowed(line),
+      _ => Cow::Owned(format!("{} (x{})", line, count)),
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn join_rendered_rows(
+  entries: &[InternalEntry],
+  rendered: &[(u16, String)],
+  join_mode: JoinMode,
+) -> String {
+  let indented = entries
+    .iter()
+    .zip(rendered)
+    .map(|(entry, (_, text))| indent_lines(text, entry.indent))
+    .collect::<Vec<_>>();
+  if entries.iter().all(|entry| entry.rows.is_none()) {
+    return join_entry_texts(
+      indented.iter().map(|text| text.as_ref()),
+      join_mode,
+    );
+  }
+
+  // place the entries with rows first, where the later added entry
+  // wins the rows that overlap, then fill the other rows in order
+  let mut frame_rows: Vec<Option<&str>> = Vec::new();
+  let mut positioned = entries
+    .iter()
+    .zip(&indented)
+    .filter_map(|(entry, text)| Some((entry.rows.clone()?, entry, text)))
+    .collect::<Vec<_>>();
+  positioned.sort_by_key(|(_, entry, _)| entry.added_at);
+  for (rows, _, text) in positioned {
+    if frame_rows.len() < rows.end as usize {
+      frame_rows.resize(rows.end as usize, None);
+    }
+    let mut lines = text.split('\n');
+    for row in rows {
+      frame_rows[row as usize] = Some(lines.next().unwrap_or(""));
+    }
+  }
+  let flowing_text = join_entry_texts(
+    entries
+      .iter()
+      .zip(&indented)
+      .filter(|(entry, _)| entry.rows.is_none())
+      .map(|(_, text)| text.as_ref()),
+    join_mode,
+  );
+  let mut flowing_lines = flowing_text
+    .split('\n')
+    .filter(|_| !flowing_text.is_empty());
+  for row in frame_rows.iter_mut().filter(|row| row.is_none()) {
+    *row = flowing_lines.next();
+  }
+  frame_rows
+    .into_iter()
+    .map(|row| row.unwrap_or(""))
+    .chain(flowing_lines)
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Aligns the tab separated columns of the rendered text of the entries.
+fn align_rendered_columns(rendered: &mut [(u16, String)]) {
+  let mut texts = rendered
+    .iter_mut()
+    .map(|(_, text)| std::mem::take(text))
+    .collect::<Vec<_>>();
+  align_columns(&mut texts);
+  for ((_, text), aligned) in rendered.iter_mut().zip(texts) {
+    *text = aligned;
+  }
+}
+
+/// Renders a JSON object per entry, one per line. This must be
+/// called outside the internal state lock.
+fn render_json_lines(
+  entries: &[InternalEntry],
+  rendered: &[(u16, String)],
+  size: &ConsoleSize,
+) -> String {
+  let mut text = String::new();
+  for (entry, (id, entry_text)) in entries.iter().zip(rendered) {
+    let mut value = serde_json::json!({
+      "id": id,
+      "text": console_static_text::ansi::strip_ansi_codes(entry_text),
+    });
+    if let Some(renderer) = entry.renderer.as_structured() {
+      value["data"] = renderer.render_structured(size);
+    }
+    text.push_str(&value.to_string());
+    text.push('\n');
+  }
+  text
+}
+
+fn join_entry_texts<'a>(
+  texts: impl IntoIterator<Item = &'a str>,
+  join_mode: JoinMode,
+) -> String {
+  let mut text = String::new();
+  match join_mode {
+    JoinMode::SkipEmpty => {
+      let mut should_new_line_next = false;
+      for new_text in texts {
+        if should_new_line_next && !new_text.is_empty() {
+          text.push('\n');
+        }
+        should_new_line_next = !new_text.is_empty();
+        text.push_str(new_text);
+      }
+    }
+    JoinMode::OneLinePerEntry => {
+      for (i, new_text) in texts.into_iter().enumerate() {
+        if i > 0 {
+          text.push('\n');
+        }
+        text.push_str(new_text);
+      }
+    }
+  }
+  text
+}
+
+/// Gets the number of rows the text occupies in the console, including
+/// the rows of lines that the terminal wraps.
+fn row_count(text: &str, size: &ConsoleSize) -> usize {
+  if text.is_empty() {
+    return 0;
+  }
+  let cols = (size.cols as usize).max(1);
+  let rows = text
+    .split('\n')
+    .map(|line| display_width(line).div_ceil(cols).max(1))
+    .sum::<usize>();
+  rows.min(size.rows as usize)
+}
+
+/// Takes the lines of the text that fit within the rows of the console,
+/// starting from the top.
+fn take_top_rows(text: &str, size: &ConsoleSize) -> String {
+  let mut available_rows = size.rows as usize;
+  let mut lines = Vec::new();
+  for line in text.split('\n') {
+    let rows = row_count(line, size).max(1);
+    if rows > available_rows {
+      break;
+    }
+    available_rows -= rows;
+    lines.push(line);
+  }
+  lines.join("\n")
+}
+
+/// Gets the line of `DrawThread::set_debug_overlay`.
+fn debug_overlay_text(
+  drawer_id: Option<usize>,
+  entry_count: usize,
+  frame: u64,
+  render_time: Duration,
+  size: &ConsoleSize,
+) -> String {
+  let drawer = match drawer_id {
+    Some(drawer_id) => drawer_id.to_string(),
+    None => "manual".to_string(),
+  };
+  let text = format!(
+    "[draw debug] drawer={} entries={} frame={} render={:.1}ms size={}x{}",
+    drawer,
+    entry_count,
+    frame,
+    render_time.as_secs_f64() * 1000f64,
+    size.cols,
+    size.rows,
+  );
+  colors::gray(truncate_to_width(&text, size.cols as usize)).to_string()
+}
+
+/// Draws the most recent log lines that fit in the console above the
+/// text of the entries, which take precedence.
+fn prepend_log_lines(
+  log_lines: &[String],
+  text: String,
+  size: &ConsoleSize,
+) -> String {
+  let mut available_rows =
+    (size.rows as usize).saturating_sub(row_count(&text, size));
+  let mut line_count = 0;
+  for line in log_lines.iter().rev() {
+    let rows = row_count(line, size).max(1);
+    if rows > available_rows {
+      break;
+    }
+    available_rows -= rows;
+    line_count += 1;
+  }
+  if line_count == 0 {
+    return text;
+  }
+  let mut lines = log_lines[log_lines.len() - line_count..].to_vec();
+  if !text.is_empty() {
+    lines.push(text);
+  }
+  lines.join("\n")
+}
+
+fn new_static_text() -> ConsoleStaticText {
+  ConsoleStaticText::new(|| {
+    // the render loop keeps this up to date, so don't do
+    // a syscall for every frame
+    let size = CACHED_CONSOLE_SIZE.get().or_else(console_size);
+    to_static_text_size(size)
+  })
+}
+
+fn to_static_text_size(
+  size: Option<ConsoleSize>,
+) -> console_static_text::ConsoleSize {
+  console_static_text::ConsoleSize {
+    cols: size.map(|s| s.cols as u16),
+    rows: size.map(|s| s.rows as u16),
+  }
+}
+
+/// Gets the OSC 9;4 sequence that displays the provided
+/// progress (`0.0` to `1.0`) in the terminal's taskbar.
+fn taskbar_progress_text(progress: f64) -> String {
+  let percent = (progress * 100f64).round().clamp(0f64, 100f64) as u8;
+  format!("\x1b]9;4;1;{}\x07", percent)
+}
+
+/// The instance `DrawThread`'s functions use.
+static DEFAULT_INSTANCE: Lazy<DrawThreadInstance> = Lazy::new(|| {
+  let instance = DrawThreadInstance::new();
+  // the signal handlers only clear the rows drawn by this instance
+  instance.state.lock().records_drawn_rows = true;
+  instance
+});
+
+/// The size provided to the renderers when emitting JSON
+/// lines without a console.
+const JSON_LINES_CONSOLE_SIZE: ConsoleSize = ConsoleSize { cols: 80, rows: 24 };
+
+/// The console size last fetched by the render loop.
+static CACHED_CONSOLE_SIZE: CachedConsoleSize = CachedConsoleSize::new();
+
+/// A console size that can be read without acquiring the internal
+/// state lock. The columns are stored in the upper bits and the rows in
+/// the lower bits, where zero means the size is unknown.
+struct CachedConsoleSize(AtomicU64);
+
+impl CachedConsoleSize {
+  const fn new() -> Self {
+    Self(AtomicU64::new(0))
+  }
+
+  fn get(&self) -> Option<ConsoleSize> {
+    let value = self.0.load(Ordering::Relaxed);
+    if value == 0 {
+      None
+    } else {
+      Some(ConsoleSize {
+        cols: (value >> 32) as u32,
+        rows: value as u32,
+      })
+    }
+  }
+
+  fn set(&self, size: Option<ConsoleSize>) {
+    let value = size
+      .map(|s| ((s.cols as u64) << 32) | s.rows as u64)
+      .unwrap_or(0);
+    self.0.store(value, Ordering::Relaxed);
+  }
+}
+
+/// Prints to stderr like `eprintln!`, but through `DrawThread::println`
+/// so that the draw thread's region is cleared before printing and drawn
+/// again afterwards. Using `eprintln!` while the region is drawn garbles
+/// the output, so use this instead. When nothing is drawn, this is the
+/// same as `eprintln!`.
+// not used by the cli itself yet
+#[allow(unused_macros)]
+macro_rules! draw_eprintln {
+  () => {
+    $crate::util::draw_thread::DrawThread::println("")
+  };
+  ($($arg:tt)*) => {
+    $crate::util::draw_thread::DrawThread::println(&std::format!($($arg)*))
+  };
+}
+
+#[allow(unused_imports)]
+pub(crate) use draw_eprintln;
+
+const DRAW_INTERVAL: Duration = Duration::from_millis(120);
+/// The default of `DrawThread::set_history_limit`.
+const DEFAULT_HISTORY_LIMIT: usize = 1000;
+/// How often frames are emitted in `RenderMode::AppendOnly`.
+const APPEND_ONLY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// An override of the support detection. These are stored outside the
+/// internal state because they're checked while it's locked.
+struct SupportOverride(AtomicU8);
+
+impl SupportOverride {
+  const NONE: u8 = 0;
+  const FALSE: u8 = 1;
+  const TRUE: u8 = 2;
+
+  const fn new() -> Self {
+    Self(AtomicU8::new(Self::NONE))
+  }
+
+  fn get(&self) -> Option<bool> {
+    match self.0.load(Ordering::Relaxed) {
+      Self::FALSE => Some(false),
+      Self::TRUE => Some(true),
+      _ => None,
+    }
+  }
+
+  fn set(&self, value: Option<bool>) {
+    let value = match value {
+      None => Self::NONE,
+      Some(false) => Self::FALSE,
+      Some(true) => Self::TRUE,
+    };
+    self.0.store(value, Ordering::Relaxed);
+  }
+}
+
+static ENABLED_OVERRIDE: SupportOverride = SupportOverride::new();
+static TERMINAL_OVERRIDE: SupportOverride = SupportOverride::new();
+
+/// The environment variable a parent process sets for its subprocesses
+/// so that only the parent draws, such as `DENO_DRAW_SUSPENDED=1`.
+pub const DRAW_SUSPENDED_ENV_VAR: &str = "DENO_DRAW_SUSPENDED";
+
+static IS_SUSPENDED_BY_PARENT: Lazy<bool> = Lazy::new(|| {
+  is_suspended_value(std::env::var(DRAW_SUSPENDED_ENV_VAR).ok().as_deref())
+});
+
+fn is_suspended_value(value: Option<&str>) -> bool {
+  value.is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+fn is_supported_with(
+  enabled_override: Option<bool>,
+  is_tty: bool,
+  is_suspended_by_parent: bool,
+) -> bool {
+  // don't put the log level in the lazy because the
+  // log level may change as the application runs
+  let is_enabled =
+    enabled_override.unwrap_or_else(|| log::log_enabled!(log::Level::Info));
+  is_enabled && is_tty && !is_suspended_by_parent
+}
+
+static TERMINAL_CAPS: Lazy<TerminalCaps> = Lazy::new(TerminalCaps::detect);
+
+static IS_TTY_WITH_CONSOLE_SIZE: Lazy<bool> = Lazy::new(|| {
+  std::io::stderr().is_terminal()
+    && console_size()
+      .map(|s| s.cols > 0 && s.rows > 0)
+      .unwrap_or(false)
+});
+
+/// The draw thread is responsible for rendering multiple active
+/// `DrawThreadRenderer`s to stderr. It is global because the
+/// concept of stderr in the process is also a global concept.
+///
+/// The functions use a default `DrawThreadInstance`, which is
+/// documented in more detail.
+#[derive(Clone, Debug)]
+pub struct DrawThread;
+
+// the functions are the API of the draw thread, which the cli only
+// uses part of
+#[allow(dead_code)]
+impl DrawThread {
+  /// Gets whether the current thread is the draw thread, which is also
+  /// the case while `tick()` renders in manual mode or `prime()` renders.
+  ///
+  /// Renderers are called on the draw thread, so code that may be called
+  /// by a renderer can use this to avoid calling into the `DrawThread`.
+  /// Doing so takes the internal state lock, which may deadlock with a
+  /// lock the renderer holds, as described in `render_pass`.
+  pub fn is_draw_thread() -> bool {
+    IS_DRAW_THREAD.with(|flag| flag.get())
+  }
+
+  /// Gets the features the terminal is detected to support, such as
+  /// for picking glyphs and color depth. This is detected once.
+  pub fn terminal_capabilities() -> TerminalCaps {
+    *TERMINAL_CAPS
+  }
+
+  /// Detects whether the terminal's background is light or dark by asking
+  /// the terminal for its background color (OSC 11), so that themes can
+  /// pick contrasting colors. Returns `None` when stdin or stderr isn't a
+  /// terminal, on Windows, or when the terminal doesn't answer within a
+  /// short timeout, in which case callers should assume a default.
+  ///
+  /// This reads the answer from stdin, so it's only done when called and
+  /// shouldn't be called while something else is reading stdin, such as
+  /// a prompt. Input typed while waiting may be consumed. The result is
+  /// cached, so the terminal is only asked once.
+  pub fn detect_background() -> Option<capabilities::Background> {
+    static BACKGROUND: OnceLock<Option<capabilities::Background>> =
+      OnceLock::new();
+    *BACKGROUND.get_or_init(capabilities::detect_background)
+  }
+
+  /// Is using a draw thread supported.
+  ///
+  /// This is never the case when a parent process that draws set
+  /// `DRAW_SUSPENDED_ENV_VAR`, so that the region isn't drawn by both
+  /// processes. The entries then use the fallback, such as
+  /// `Fallback::JsonLines` for the parent to consume.
+  pub fn is_supported() -> bool {
+    let is_tty = TERMINAL_OVERRIDE
+      .get()
+      .unwrap_or_else(|| *IS_TTY_WITH_CONSOLE_SIZE);
+    is_supported_with(ENABLED_OVERRIDE.get(), is_tty, *IS_SUSPENDED_BY_PARENT)
+  }
+
+  /// Overrides whether drawing is enabled regardless of the log level,
+  /// such as to show progress when only logging warnings. A TTY is still
+  /// required in order to draw. `None` only draws when the log level
+  /// is at least info, which is the default.
+  ///
+  /// The override applies to every instance, but only the draw thread
+  /// of the default instance is started or stopped right away. Other
+  /// instances observe it the next time they start their draw thread.
+  pub fn set_enabled(value: Option<bool>) {
+    let mut internal_state = DEFAULT_INSTANCE.state.lock();
+    ENABLED_OVERRIDE.set(value);
+    Self::on_support_changed(&mut internal_state);
+  }
+
+  /// Overrides whether stderr is considered a terminal, such as for
+  /// wrappers that know better than the detection or for tests. `None`
+  /// uses the detected value, which is the default.
+  ///
+  /// Like `set_enabled`, this only starts or stops the draw thread of the
+  /// default instance right away.
+  pub fn set_terminal_override(value: Option<bool>) {
+    let mut internal_state = DEFAULT_INSTANCE.state.lock();
+    TERMINAL_OVERRIDE.set(value);
+    Self::on_support_changed(&mut internal_state);
+  }
+
+  /// Starts or stops the draw thread of the default instance, whose state
+  /// is provided, once the support changed.
+  fn on_support_changed(internal_state: &mut InternalState) {
+    if Self::is_supported() || internal_state.uses_json_lines() {
+      DEFAULT_INSTANCE.maybe_start_draw_thread(internal_state);
+    } else if !internal_state.manual {
+      DrawThreadInstance::clear_and_stop_draw_thread(internal_state);
+    }
+  }
+
+  /// Resolves once the region is next cleared, which allows printing
+  /// without racing the draw thread.
+  pub fn on_next_clear() -> impl Future<Output = ()> {
+    DEFAULT_INSTANCE.on_next_clear()
+  }
+
+  /// Installs handlers that clear the drawn region when the process is
+  /// interrupted with SIGINT or SIGTERM on unix or a console control
+  /// event on Windows, which otherwise leaves it on the screen. The
+  /// previous handlers are still run afterwards, so the signal's action
+  /// still proceeds, and a handler installed later that chains to these
+  /// stays in charge of whether the process exits. Only the region of
+  /// the default instance is cleared, not of ones from `DrawThread::new`.
+  pub fn install_signal_cleanup() {
+    signal::install();
+  }
+
+  /// Resumes drawing the entries after a `suspend()`. The
+  /// entries are drawn from scratch.
+  pub fn resume(token: SuspendToken) {
+    drop(token);
+  }
+
+  /// Creates a draw thread with its own entries and settings that's
+  /// isolated from the default one these functions use.
+  #[allow(clippy::new_ret_no_self)]
+  pub fn new() -> DrawThreadInstance {
+    DrawThreadInstance::new()
+  }
+
+  /// Adds a renderer to the draw thread.
+  pub fn add_entry(renderer: Arc<dyn DrawThreadRenderer>) -> DrawThreadGuard {
+    DEFAULT_INSTANCE.add_entry(renderer)
+  }
+
+  /// Adds a renderer to the draw thread that's removed once the token is
+  /// cancelled or the returned guard is dropped, whichever happens first.
+  pub fn add_entry_with_token(
+    renderer: Arc<dyn DrawThreadRenderer>,
+    token: CancellationToken,
+  ) -> DrawThreadGuard {
+    DEFAULT_INSTANCE.add_entry_with_token(renderer, token)
+  }
+
+  /// Adds a renderer to the draw thread with a category, such as "network",
+  /// that's shared by the entries that can be hidden together with `hide_tag`.
+  pub fn add_entry_tagged(
+    renderer: Arc<dyn DrawThreadRenderer>,
+    tag: &str,
+  ) -> DrawThreadGuard {
+    DEFAULT_INSTANCE.add_entry_tagged(renderer, tag)
+  }
+
+  /// Adds a renderer to the draw thread that always occupies the provided
+  /// rows of the frame.
+  pub fn add_entry_at_rows(
+    renderer: Arc<dyn DrawThreadRenderer>,
+    start: u16,
+    count: u16,
+  ) -> DrawThreadGuard {
+    DEFAULT_INSTANCE.add_entry_at_rows(renderer, start, count)
+  }
+
+  /// Wraps the reader to display a bar of the bytes read from it until
+  /// the returned reader is dropped.
+  pub fn track_reader<R: std::io::Read>(
+    reader: R,
+    total: Option<u64>,
+    label: impl Into<String>,
+  ) -> reader::TrackedReader<R> {
+    DEFAULT_INSTANCE.track_reader(reader, total, label)
+  }
+
+  /// Adds a bar that's updated by sending events over the returned channel
+  /// rather than by sharing its state. This must be called within a tokio
+  /// runtime.
+  pub fn add_entry_from_channel() -> (
+    tokio::sync::mpsc::UnboundedSender<progress::ProgressEvent>,
+    DrawThreadGuard,
+  ) {
+    DEFAULT_INSTANCE.add_entry_from_channel()
+  }
+
+  /// Adds a renderer to the draw thread that's only drawn in the frames
+  /// where the predicate returns `true`.
+  pub fn add_entry_if(
+    renderer: Arc<dyn DrawThreadRenderer>,
+    predicate: Arc<dyn Fn() -> bool + Send + Sync>,
+  ) -> DrawThreadGuard {
+    DEFAULT_INSTANCE.add_entry_if(renderer, predicate)
+  }
+
+  /// Stops drawing the entries with the tag, including ones added later, until
+  /// `show_tag` is called.
+  pub fn hide_tag(tag: &str) {
+    DEFAULT_INSTANCE.hide_tag(tag)
+  }
+
+  /// Draws the entries with the tag again after `hide_tag`.
+  pub fn show_tag(tag: &str) {
+    DEFAULT_INSTANCE.show_tag(tag)
+  }
+
+  /// Briefly shows a message, such as "Saved!", that's removed once the
+  /// duration elapses.
+  pub fn flash(message: impl Into<String>, duration: Duration) {
+    DEFAULT_INSTANCE.flash(message, duration)
+  }
+
+  /// Adds a renderer to the draw thread that's associated with the current
+  /// tracing span, so its lifecycle events are recorded within the span.
+  #[cfg(feature = "tracing")]
+  pub fn add_entry_in_current_span(
+    renderer: Arc<dyn DrawThreadRenderer>,
+  ) -> DrawThreadGuard {
+    DEFAULT_INSTANCE.add_entry_in_current_span(renderer)
+  }
+
+  /// Shows the renderer while the provided future runs, returning its output.
+  pub async fn add_entry_until<TFuture: Future>(
+    renderer: Arc<dyn DrawThreadRenderer>,
+    future: TFuture,
+  ) -> TFuture::Output {
+    DEFAULT_INSTANCE.add_entry_until(renderer, future).await
+  }
+
+  /// Sets whether the aggregate progress of the entries should also be
+  /// displayed in the terminal's taskbar (OSC 9;4).
+  pub fn set_taskbar_progress(value: bool) {
+    DEFAULT_INSTANCE.set_taskbar_progress(value)
+  }
+
+  /// Adds a sink that receives every frame drawn in addition to the terminal
+  /// for as long as the returned guard is alive.
+  pub fn add_sink(sink: Box<dyn FrameSink>) -> FrameSinkGuard {
+    DEFAULT_INSTANCE.add_sink(sink)
+  }
+
+  /// Adds a sink that draws every frame into the writer at the provided
+  /// size for as long as the returned guard is alive.
+  pub fn add_writer_sink(
+    writer: Box<dyn Write + Send>,
+    size: ConsoleSize,
+  ) -> FrameSinkGuard {
+    DEFAULT_INSTANCE.add_writer_sink(writer, size)
+  }
+
+  /// Sets how the previous frame is cleared when drawing a new one.
+  pub fn set_clear_strategy(strategy: ClearStrategy) {
+    DEFAULT_INSTANCE.set_clear_strategy(strategy)
+  }
+
+  /// Sets a function that's called each time the drawn text is cleared.
+  pub fn set_clear_hook(hook: Option<Box<dyn Fn() + Send + Sync>>) {
+    DEFAULT_INSTANCE.set_clear_hook(hook)
+  }
+
+  /// Sets how the rendered text of the entries is joined.
+  pub fn set_join_mode(mode: JoinMode) {
+    DEFAULT_INSTANCE.set_join_mode(mode)
+  }
+
+  /// Sets the characters that end each line written to the terminal.
+  pub fn set_line_ending(line_ending: LineEnding) {
+    DEFAULT_INSTANCE.set_line_ending(line_ending)
+  }
+
+  /// Sets how long an entry must exist before it's drawn, which prevents quick
+  /// operations from briefly flashing on the screen.
+  pub fn set_render_delay(delay: Duration) {
+    DEFAULT_INSTANCE.set_render_delay(delay)
+  }
+
+  /// Sets a size that's used instead of the size of the console, such as for
+  /// reproducible output or when rendering for a sink of a known width.
+  pub fn set_size_override(size: Option<ConsoleSize>) {
+    DEFAULT_INSTANCE.set_size_override(size)
+  }
+
+  /// Sets the order the entries are drawn in.
+  pub fn set_order_direction(direction: OrderDirection) {
+    DEFAULT_INSTANCE.set_order_direction(direction)
+  }
+
+  /// Sets what's drawn when every entry renders empty text, such as while idle.
+  pub fn set_empty_frame(empty_frame: EmptyFrame) {
+    DEFAULT_INSTANCE.set_empty_frame(empty_frame)
+  }
+
+  /// Sets whether each frame is drawn using the terminal's synchronized output
+  /// mode.
+  pub fn set_synchronized_output(value: SynchronizedOutput) {
+    DEFAULT_INSTANCE.set_synchronized_output(value)
+  }
+
+  /// Sets whether a line describing the draw thread's internals, such as the
+  /// frame number and how long the frame took to render, is drawn below the
+  /// entries for diagnosing rendering issues.
+  pub fn set_debug_overlay(value: bool) {
+    DEFAULT_INSTANCE.set_debug_overlay(value)
+  }
+
+  /// Sets the maximum number of columns the region uses, such as to prevent
+  /// progress bars from stretching across ultrawide terminals.
+  pub fn set_max_cols(max_cols: Option<u16>) {
+    DEFAULT_INSTANCE.set_max_cols(max_cols)
+  }
+
+  /// Sets the number of rows the console must have for all the entries to be
+  /// drawn.
+  pub fn set_min_rows_required(rows: u16) {
+    DEFAULT_INSTANCE.set_min_rows_required(rows)
+  }
+
+  /// Draws the provided text instead of the entries until this is called with
+  /// `None`, such as while waiting on a lock.
+  pub fn override_frame(text: Option<String>) {
+    DEFAULT_INSTANCE.override_frame(text)
+  }
+
+  /// Sets how the frames are output to the terminal.
+  pub fn set_render_mode(mode: RenderMode) {
+    DEFAULT_INSTANCE.set_render_mode(mode)
+  }
+
+  /// Sets how lines of the rendered text that are wider than the console are
+  /// handled.
+  pub fn set_wrap_mode(mode: WrapMode) {
+    DEFAULT_INSTANCE.set_wrap_mode(mode)
+  }
+
+  /// Sets whether the tab separated cells in the rendered text of the entries
+  /// are aligned into columns across all the entries, which draws independent
+  /// renderers as a table.
+  pub fn set_table_align(value: bool) {
+    DEFAULT_INSTANCE.set_table_align(value)
+  }
+
+  /// Sets whether identical adjacent lines of the region are collapsed into
+  /// one line with a count.
+  pub fn set_collapse_duplicates(value: bool) {
+    DEFAULT_INSTANCE.set_collapse_duplicates(value)
+  }
+
+  /// Sets how long the drawn text may stay unchanged before it's marked as
+  /// having no updates, such as when a task hangs.
+  pub fn set_stale_timeout(timeout: Option<Duration>) {
+    DEFAULT_INSTANCE.set_stale_timeout(timeout)
+  }
+
+  /// Sets how long each entry has to render each frame before the frame is
+  /// drawn with the last text of the entries that are still rendering.
+  pub fn set_render_timeout(timeout: Option<Duration>) {
+    DEFAULT_INSTANCE.set_render_timeout(timeout)
+  }
+
+  /// Confines the entries to a rectangle of the terminal, such as when a host
+  /// application owns the rest of the screen.
+  pub fn set_viewport(viewport: Option<Viewport>) {
+    DEFAULT_INSTANCE.set_viewport(viewport)
+  }
+
+  /// Sets where the region is drawn in the terminal.
+  pub fn set_pin(pin: Pin) {
+    DEFAULT_INSTANCE.set_pin(pin)
+  }
+
+  /// Prints the line to stderr above the drawn region by clearing the region,
+  /// printing the line, and then drawing the region again.
+  pub fn println(text: &str) {
+    DEFAULT_INSTANCE.println(text)
+  }
+
+  /// Leaves the currently drawn frame in the scrollback and continues
+  /// drawing below it.
+  pub fn commit_current() {
+    DEFAULT_INSTANCE.commit_current()
+  }
+
+  /// Sets whether the draw thread draws less often while the process is starved
+  /// of CPU, such as on a loaded system, which is detected by the draw thread
+  /// consistently sleeping longer than requested.
+  pub fn set_adaptive_interval(value: bool) {
+    DEFAULT_INSTANCE.set_adaptive_interval(value)
+  }
+
+  /// Sets whether the draw thread draws less often while many lines are printed
+  /// via `println`, since clearing the region and drawing it again for every
+  /// line causes flicker.
+  pub fn set_output_backpressure(value: bool) {
+    DEFAULT_INSTANCE.set_output_backpressure(value)
+  }
+
+  /// Sets the value of its type in the context provided to
+  /// `DrawThreadRenderer::render_with_context`, replacing any previous value of
+  /// the same type.
+  pub fn set_context<T: std::any::Any + Send + Sync>(value: Arc<T>) {
+    DEFAULT_INSTANCE.set_context(value)
+  }
+
+  /// Removes the value of the provided type from the context.
+  pub fn remove_context<T: std::any::Any + Send + Sync>() {
+    DEFAULT_INSTANCE.remove_context::<T>()
+  }
+
+  /// Sets how many of the most recent lines of `DrawThread::log` are drawn
+  /// above the entries.
+  pub fn set_managed_log_lines(count: usize) {
+    DEFAULT_INSTANCE.set_managed_log_lines(count)
+  }
+
+  /// Logs the line above the entries, keeping only the number of lines set by
+  /// `set_managed_log_lines`.
+  pub fn log(line: impl Into<String>) {
+    DEFAULT_INSTANCE.log(line)
+  }
+
+  /// Prints the line once the last entry finishes and the region is cleared, so
+  /// that it stays in the scrollback, such as "Done in 4.2s".
+  pub fn finish_with_summary(line: impl Into<String>) {
+    DEFAULT_INSTANCE.finish_with_summary(line)
+  }
+
+  /// Sets what to output instead of drawing to the terminal, such as JSON lines
+  /// that wrappers can parse.
+  pub fn set_fallback(fallback: Fallback) {
+    DEFAULT_INSTANCE.set_fallback(fallback)
+  }
+
+  /// Gets a snapshot of the ids of the entries that are currently alive, in the
+  /// order they're drawn.
+  pub fn active_ids() -> Vec<u16> {
+    DEFAULT_INSTANCE.active_ids()
+  }
+
+  /// Gets the id and `Debug` representation of each entry that's alive.
+  pub fn describe_entries() -> Vec<(u16, String)> {
+    DEFAULT_INSTANCE.describe_entries()
+  }
+
+  /// Sets whether a record of each entry is kept once it finishes, which can be
+  /// taken via `DrawThread::take_completion_log` for a summary of the
+  /// operations after they're done.
+  pub fn set_record_completions(value: bool) {
+    DEFAULT_INSTANCE.set_record_completions(value)
+  }
+
+  /// Takes the records of the entries that finished since the last call, in the
+  /// order they finished.
+  pub fn take_completion_log() -> Vec<EntryCompletion> {
+    DEFAULT_INSTANCE.take_completion_log()
+  }
+
+  /// Sets the maximum number of completion records and managed log lines
+  /// that are kept.
+  pub fn set_history_limit(limit: usize) {
+    DEFAULT_INSTANCE.set_history_limit(limit)
+  }
+
+  /// Renders the entries at the provided width as plain text without any
+  /// styles, such as for writing the final state of the progress to a log.
+  pub fn render_plain(cols: u16) -> String {
+    DEFAULT_INSTANCE.render_plain(cols)
+  }
+
+  /// Gets the statistics of the current or most recent draw thread.
+  pub fn stats() -> DrawStats {
+    DEFAULT_INSTANCE.stats()
+  }
+
+  /// Gets the statistics of the current or most recent draw thread and resets
+  /// them, such as for sampling the rates over an interval.
+  pub fn take_stats() -> DrawStats {
+    DEFAULT_INSTANCE.take_stats()
+  }
+
+  /// Gets whether the most recent render pass wrote a frame to the terminal,
+  /// which it doesn't do when the frame is unchanged or the region is hidden,
+  /// such as to find out why an update isn't shown.
+  pub fn last_frame_emitted() -> bool {
+    DEFAULT_INSTANCE.last_frame_emitted()
+  }
+
+  /// Clears the region and stops the draw thread until the returned token is
+  /// resumed, keeping all the entries.
+  pub fn suspend() -> SuspendToken {
+    DEFAULT_INSTANCE.suspend()
+  }
+
+  /// Hides the draw thread until the matching `show()`, which may be
+  /// called on another thread.
+  pub fn hide() {
+    DEFAULT_INSTANCE.hide()
+  }
+
+  /// Shows the draw thread if it was previously hidden.
+  pub fn show() {
+    DEFAULT_INSTANCE.show()
+  }
+
+  /// Hides the draw thread until the returned guard is dropped, which is
+  /// cheaper than `hide()` for deeply nested operations.
+  pub fn hide_scope() -> HideGuard {
+    DEFAULT_INSTANCE.hide_scope()
+  }
+
+  /// Sets whether rendering is driven manually with `tick()` instead of by a
+  /// spawned draw thread, such as for deterministic tests or when embedding in
+  /// an external event loop.
+  pub fn set_manual(value: bool) {
+    DEFAULT_INSTANCE.set_manual(value)
+  }
+
+  /// Clears the region and stops drawing for good, after which adding an entry
+  /// does nothing until `reset()` is called.
+  pub fn shutdown() {
+    DEFAULT_INSTANCE.shutdown()
+  }
+
+  /// Allows drawing again after `shutdown()`.
+  pub fn reset() {
+    DEFAULT_INSTANCE.reset()
+  }
+
+  /// Drives the draw thread by ticking it at the interval on the runtime rather
+  /// than on a thread of its own until the returned guard is dropped.
+  pub fn attach_ticker(
+    interval: Duration,
+    handle: &tokio::runtime::Handle,
+  ) -> TickerGuard {
+    DEFAULT_INSTANCE.attach_ticker(interval, handle)
+  }
+
+  /// Performs exactly one render pass when in manual mode, returning whether a
+  /// frame was drawn.
+  pub fn tick() -> bool {
+    DEFAULT_INSTANCE.tick()
+  }
+
+  /// Synchronously draws the first frame on the calling thread, returning
+  /// whether it was drawn.
+  pub fn prime() -> bool {
+    DEFAULT_INSTANCE.prime()
+  }
+}
+
+/// A draw thread with its own entries and settings. `DrawThread`'s
+/// functions use a default instance, while other instances are isolated
+/// from it, such as for tests that run in parallel or for drawing
+/// multiple independent regions. Clones share the same state.
+///
+/// The settings of the terminal itself, such as whether drawing is
+/// enabled, are shared by every instance.
+#[derive(Clone, Debug)]
+pub struct DrawThreadInstance {
+  state: Arc<Mutex<InternalState>>,
+  region_cleared: Arc<Notify>,
+}
+
+impl DrawThreadInstance {
+  fn new() -> Self {
+    let state = InternalState::new();
+    Self {
+      region_cleared: state.region_cleared.clone(),
+      state: Arc::new(Mutex::new(state)),
+    }
+  }
+
+  /// Resolves once the region is next cleared, such as when the last
+  /// entry finishes or the draw thread is hidden, which allows printing
+  /// without racing the draw thread. Clearing the region in order to
+  /// draw it again doesn't count, and neither do the clears of other
+  /// instances. All the futures created before the clear are resolved.
+  pub fn on_next_clear(&self) -> impl Future<Output = ()> + '_ {
+    // a clear before this is first polled still resolves it
+    self.region_cleared.notified()
+  }
+
+  /// Adds a renderer to the draw thread.
+  pub fn add_entry(
+    &self,
+    renderer: Arc<dyn DrawThreadRenderer>,
+  ) -> DrawThreadGuard {
+    self.add_entry_with(renderer, EntryAttributes::default())
+  }
+
+  /// Adds the entry with all its attributes under a single lock, so
+  /// it's never drawn before they're set.
+  fn add_entry_with(
+    &self,
+    renderer: Arc<dyn DrawThreadRenderer>,
+    attributes: EntryAttributes,
+  ) -> DrawThreadGuard {
+    DrawThreadGuard {
+      id: self.push_entry(renderer, attributes),
+      instance: self.clone(),
+      lingering: false,
+    }
+  }
+
+  /// Adds a renderer to the draw thread that's removed once the token is
+  /// cancelled or the returned guard is dropped, whichever happens first.
+  /// This must be called within a tokio runtime.
+  pub fn add_entry_with_token(
+    &self,
+    renderer: Arc<dyn DrawThreadRenderer>,
+    token: CancellationToken,
+  ) -> DrawThreadGuard {
+    let watcher_token = CancellationToken::new();
+    let guard = self.add_entry_with(
+      renderer,
+      EntryAttributes {
+        watcher_token: Some(watcher_token.clone()),
+        ..Default::default()
+      },
+    );
+    let Some(entry_id) = guard.id else {
+      return guard;
+    };
+    let instance = self.clone();
+    spawn(async move {
+      tokio::select! {
+        biased;
+        _ = watcher_token.cancelled() => {}
+        _ = token.cancelled() => {
+          // the entry may have been finished and its id reused while
+          // this task was waking up, which cancels the watcher token
+          instance.finish_watched_entry(entry_id, &watcher_token)
+        }
+      }
+    });
+    guard
+  }
+
+  /// Adds a renderer to the draw thread with a category, such as
+  /// "network", that's shared by the entries that can be hidden
+  /// together with `hide_tag`.
+  pub fn add_entry_tagged(
+    &self,
+    renderer: Arc<dyn DrawThreadRenderer>,
+    tag: &str,
+  ) -> DrawThreadGuard {
+    self.add_entry_with(
+      renderer,
+      EntryAttributes {
+        tag: Some(tag.into()),
+        ..Default::default()
+      },
+    )
+  }
+
+  /// Adds a renderer to the draw thread that's only drawn in the frames
+  /// where the predicate returns `true`, such as while a verbose mode is
+  /// toggled on, which avoids adding and removing the entry as the
+  /// condition changes. The predicate is called every frame outside the
+  /// lock, like the renderer.
+  pub fn add_entry_if(
+    &self,
+    renderer: Arc<dyn DrawThreadRenderer>,
+    predicate: Arc<dyn Fn() -> bool + Send + Sync>,
+  ) -> DrawThreadGuard {
+    self.add_entry_with(
+      renderer,
+      EntryAttributes {
+        predicate: Some(EntryPredicate(predicate)),
+        ..Default::default()
+      },
+    )
+  }
+
+  /// Adds a renderer to the draw thread that always occupies the `count`
+  /// rows of the frame starting at row `start`, such as for a dashboard
+  /// with a fixed layout. Its text is padded or clipped to the rows and
+  /// the other entries fill the rows that aren't occupied.
+  ///
+  /// When the rows overlap with those of another entry, the entry added
+  /// later occupies the overlapping rows.
+  pub fn add_entry_at_rows(
+    &self,
+    renderer: Arc<dyn DrawThreadRenderer>,
+    start: u16,
+    count: u16,
+  ) -> DrawThreadGuard {
+    self.add_entry_with(
+      renderer,
+      EntryAttributes {
+        rows: Some(start..start.saturating_add(count)),
+        ..Default::default()
+      },
+    )
+  }
+
+  /// Wraps the reader to display a bar of the bytes read from it, such
+  /// as for a download or an extracted archive, until the returned reader
+  /// is dropped.
+  ///
+  /// The bar bounces while the total is unknown. Reaching the end of the
+  /// reader completes the progress, even when the total was unknown or
+  /// differs from the bytes read, and a read error marks it as failed.
+  pub fn track_reader<R: std::io::Read>(
+    &self,
+    reader: R,
+    total: Option<u64>,
+    label: impl Into<String>,
+  ) -> reader::TrackedReader<R> {
+    let state =
+      Arc::new(progress::ProgressState::new(total.unwrap_or(0), label));
+    let template = match total {
+      Some(_) => "{msg} [{bar}] {percent}%",
+      None => "{spinner} {msg} [{bar}]",
+    };
+    let renderer = template::TemplateRenderer::new(template, state.clone());
+    let guard = self.add_entry(Arc::new(renderer));
+    reader::TrackedReader::new(reader, state, guard)
+  }
+
+  /// Adds a bar that's updated by sending events over the returned
+  /// channel, which decouples the code doing the work from the state
+  /// the bar renders. The events are applied by a task, so this must be
+  /// called within a tokio runtime.
+  ///
+  /// The entry is removed once `ProgressEvent::Finish` is sent, every
+  /// sender is dropped, or the returned guard is dropped, whichever
+  /// happens first.
+  pub fn add_entry_from_channel(
+    &self,
+  ) -> (
+    tokio::sync::mpsc::UnboundedSender<progress::ProgressEvent>,
+    DrawThreadGuard,
+  ) {
+    let state = Arc::new(progress::ProgressState::default());
+    let renderer = template::TemplateRenderer::new(
+      "{msg} [{bar}] {percent}%",
+      state.clone(),
+    );
+    let watcher_token = CancellationToken::new();
+    let guard = self.add_entry_with(
+      Arc::new(renderer),
+      EntryAttributes {
+        watcher_token: Some(watcher_token.clone()),
+        ..Default::default()
+      },
+    );
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let Some(entry_id) = guard.id else {
+      // the events are dropped along with the receiver
+      return (sender, guard);
+    };
+    let instance = self.clone();
+    spawn(async move {
+      loop {
+        let event = tokio::select! {
+          biased;
+          _ = watcher_token.cancelled() => return,
+          event = receiver.recv() => event,
+        };
+        match event {
+          Some(progress::ProgressEvent::SetTotal(total)) => {
+            state.set_length(total)
+          }
+          Some(progress::ProgressEvent::Inc(delta)) => state.inc(delta),
+          Some(progress::ProgressEvent::SetMessage(message)) => {
+            state.set_message(message)
+          }
+          // every sender was dropped
+          Some(progress::ProgressEvent::Finish) | None => break,
+        }
+      }
+      instance.finish_watched_entry(entry_id, &watcher_token);
+    });
+    (sender, guard)
+  }
+
+  /// Stops drawing the entries with the tag, including ones added
+  /// later, until `show_tag` is called.
+  pub fn hide_tag(&self, tag: &str) {
+    let mut internal_state = self.state.lock();
+    internal_state.hidden_tags.insert(tag.into());
+  }
+
+  /// Draws the entries with the tag again after `hide_tag`.
+  pub fn show_tag(&self, tag: &str) {
+    let mut internal_state = self.state.lock();
+    internal_state.hidden_tags.remove(tag);
+  }
+
+  /// Briefly shows a message, such as "Saved!", that's removed once the
+  /// duration elapses. Multiple messages stack and each expires on its own.
+  pub fn flash(&self, message: impl Into<String>, duration: Duration) {
+    let expires_at = Instant::now() + duration;
+    self.push_entry(
+      Arc::new(FlashRenderer(message.into())),
+      EntryAttributes {
+        expires_at: Some(expires_at),
+        ..Default::default()
+      },
+    );
+  }
+
+  fn push_entry(
+    &self,
+    renderer: Arc<dyn DrawThreadRenderer>,
+    attributes: EntryAttributes,
+  ) -> Option<u16> {
+    let mut internal_state = self.state.lock();
+    if internal_state.shutting_down {
+      // no id is taken, so the entry's guard and watcher do nothing
+      return None;
+    }
+    let now = Instant::now();
+    Self::reap_expired_entries(&mut internal_state, now);
+    // skip the ids that are still in use once the counter wraps
+    assert!(
+      internal_state.entries.len() <= usize::from(u16::MAX),
+      "all the draw thread entry ids are in use"
+    );
+    let mut id = internal_state.next_entry_id;
+    while internal_state.entries.get(id).is_some() {
+      id = id.wrapping_add(1);
+    }
+    internal_state.next_entry_id = id.wrapping_add(1);
+    let EntryAttributes {
+      expires_at,
+      watcher_token,
+      tag,
+      predicate,
+      rows,
+      #[cfg(feature = "tracing")]
+      span,
+    } = attributes;
+    let conflicting_id = rows.as_ref().and_then(|rows| {
+      internal_state
+        .entries
+        .iter()
+        .find(|e| {
+          e.rows.as_ref().is_some_and(|other| {
+            other.start < rows.end && rows.start < other.end
+          })
+        })
+        .map(|e| e.id)
+    });
+    #[cfg(feature = "tracing")]
+    let added_span = span.clone();
+    internal_state.entries.push(
+      id,
+      InternalEntry {
+        id,
+        added_at: now,
+        renderer,
+        expires_at,
+        watcher_token,
+        tag,
+        indent: 0,
+        predicate,
+        rows,
+        verbose: false,
+        #[cfg(feature = "tracing")]
+        span,
+      },
+    );
+
+    self.maybe_start_draw_thread(&mut internal_state);
+    // log outside the lock because logging hides the draw thread
+    drop(internal_state);
+
+    if let Some(conflicting_id) = conflicting_id {
+      log::debug!(
+        "Draw thread entry {} overlaps the rows of entry {}, which it replaces.",
+        id,
+        conflicting_id
+      );
+    }
+    #[cfg(feature = "tracing")]
+    match added_span {
+      Some(span) => span.in_scope(|| {
+        tracing::debug!(entry_id = id, "draw thread entry added");
+      }),
+      None => tracing::debug!(entry_id = id, "draw thread entry added"),
+    }
+
+    Some(id)
+  }
+
+  /// Adds a renderer to the draw thread that's associated with the
+  /// current tracing span, so its lifecycle events are recorded
+  /// within the span.
+  #[cfg(feature = "tracing")]
+  pub fn add_entry_in_current_span(
+    &self,
+    renderer: Arc<dyn DrawThreadRenderer>,
+  ) -> DrawThreadGuard {
+    self.add_entry_with(
+      renderer,
+      EntryAttributes {
+        span: Some(tracing::Span::current()),
+        ..Default::default()
+      },
+    )
+  }
+
+  /// Shows the renderer while the provided future runs, returning its
+  /// output. The entry is removed once the future completes or when
+  /// the returned future is dropped. The future still runs when drawing
+  /// isn't supported.
+  pub async fn add_entry_until<TFuture: Future>(
+    &self,
+    renderer: Arc<dyn DrawThreadRenderer>,
+    future: TFuture,
+  ) -> TFuture::Output {
+    let _guard = self.add_entry(renderer);
+    future.await
+  }
+
+  /// Sets whether the aggregate progress of the entries should also
+  /// be displayed in the terminal's taskbar (OSC 9;4). Terminals that
+  /// don't support this will ignore the sequence.
+  pub fn set_taskbar_progress(&self, value: bool) {
+    let mut internal_state = self.state.lock();
+    internal_state.taskbar_progress = value;
+    if !value {
+      internal_state.clear_taskbar_progress();
+    }
+  }
+
+  /// Adds a sink that receives every frame drawn in addition
+  /// to the terminal for as long as the returned guard is alive.
+  pub fn add_sink(&self, sink: Box<dyn FrameSink>) -> FrameSinkGuard {
+    let mut internal_state = self.state.lock();
+    let id = internal_state.next_sink_id;
+    internal_state.next_sink_id += 1;
+    internal_state.sinks.push((id, sink));
+    FrameSinkGuard {
+      id,
+      instance: self.clone(),
+    }
+  }
+
+  /// Adds a sink that draws every frame into the writer for as long as
+  /// the returned guard is alive, such as to forward the progress into
+  /// the pseudo terminal of a subprocess.
+  ///
+  /// The frames are rendered at the provided size rather than the
+  /// terminal's and the writer receives the cursor movements that
+  /// replace the previous frame, independent of the terminal.
+  pub fn add_writer_sink(
+    &self,
+    writer: Box<dyn Write + Send>,
+    size: ConsoleSize,
+  ) -> FrameSinkGuard {
+    self.add_sink(Box::new(writer_sink::WriterSink::new(writer, size)))
+  }
+
+  /// Sets how the previous frame is cleared when drawing a new one.
+  pub fn set_clear_strategy(&self, strategy: ClearStrategy) {
+    let mut internal_state = self.state.lock();
+    internal_state.clear_strategy = strategy;
+  }
+
+  /// Sets a function that's called each time the drawn text is cleared,
+  /// such as to write an additional sequence that a terminal needs in
+  /// order to clear without artifacts. Defaults to `None`.
+  ///
+  /// The hook is called after the regular clear, which has already moved
+  /// the cursor back to where the region starts, so it must leave the
+  /// cursor there for the rows the region tracks to stay correct. It's
+  /// called while the draw thread's state is locked, so it must not call
+  /// into the draw thread.
+  pub fn set_clear_hook(&self, hook: Option<Box<dyn Fn() + Send + Sync>>) {
+    let mut internal_state = self.state.lock();
+    internal_state.clear_hook = hook.map(ClearHook);
+  }
+
+  /// Sets how the rendered text of the entries is joined.
+  pub fn set_join_mode(&self, mode: JoinMode) {
+    let mut internal_state = self.state.lock();
+    internal_state.join_mode = mode;
+  }
+
+  /// Sets the characters that end each line written to the terminal,
+  /// such as for legacy terminals that need an explicit `\r\n`, and
+  /// that join the lines of the frames provided to the sinks. Defaults
+  /// to `LineEnding::Lf`.
+  ///
+  /// The frames are still measured by their lines, so the rows that are
+  /// cleared are the same with either line ending. Sinks that render at
+  /// their own size measure their rows too, so they're provided the
+  /// lines joined with `\n`.
+  pub fn set_line_ending(&self, line_ending: LineEnding) {
+    let mut internal_state = self.state.lock();
+    internal_state.line_ending = line_ending;
+  }
+
+  /// Sets how long an entry must exist before it's drawn, which prevents
+  /// quick operations from briefly flashing on the screen. Defaults to
+  /// zero.
+  pub fn set_render_delay(&self, delay: Duration) {
+    let mut internal_state = self.state.lock();
+    internal_state.render_delay = delay;
+  }
+
+  /// Sets a size that's used instead of the size of the console, such
+  /// as for reproducible output or when rendering for a sink of a known
+  /// width. Resizes are then detected based on the override and `None`
+  /// returns to using the console's size. When a viewport is set, the
+  /// renderers are still provided the size of the viewport.
+  pub fn set_size_override(&self, size: Option<ConsoleSize>) {
+    let mut internal_state = self.state.lock();
+    internal_state.size_override = size;
+  }
+
+  /// Sets the order the entries are drawn in. When the entries don't fit
+  /// in the console, the rows furthest from the top are cut off. Defaults
+  /// to `OrderDirection::Oldest`.
+  pub fn set_order_direction(&self, direction: OrderDirection) {
+    let mut internal_state = self.state.lock();
+    internal_state.order_direction = direction;
+  }
+
+  /// Sets what's drawn when every entry renders empty text, such as
+  /// while idle. Defaults to `EmptyFrame::Clear`.
+  pub fn set_empty_frame(&self, empty_frame: EmptyFrame) {
+    let mut internal_state = self.state.lock();
+    internal_state.empty_frame = empty_frame;
+  }
+
+  /// Sets whether each frame, along with the redraw after printing
+  /// above the region, is drawn using the terminal's synchronized output
+  /// mode, which prevents the terminal from displaying a frame partway
+  /// through clearing and redrawing it. Terminals that don't support it
+  /// ignore the sequences. Defaults to `SynchronizedOutput::Auto`.
+  pub fn set_synchronized_output(&self, value: SynchronizedOutput) {
+    let mut internal_state = self.state.lock();
+    internal_state.synchronized_output = value;
+  }
+
+  /// Sets whether a line describing the draw thread's internals, such as
+  /// the frame number and how long the frame took to render, is drawn
+  /// below the entries for diagnosing rendering issues. Defaults
+  /// to `false`.
+  pub fn set_debug_overlay(&self, value: bool) {
+    let mut internal_state = self.state.lock();
+    internal_state.debug_overlay = value;
+  }
+
+  /// Sets the maximum number of columns the region uses, such as to
+  /// prevent progress bars from stretching across ultrawide terminals.
+  /// The renderers are provided at most this many columns and the
+  /// region stays left aligned. `None` uses the full width of the
+  /// console, which is the default.
+  pub fn set_max_cols(&self, max_cols: Option<u16>) {
+    let mut internal_state = self.state.lock();
+    internal_state.max_cols = max_cols;
+  }
+
+  /// Sets the number of rows the console must have for all the entries
+  /// to be drawn. Consoles with fewer rows only draw the first line of
+  /// the latest entry, since the stacked entries otherwise scroll off
+  /// of a tiny terminal. Defaults to zero, which always draws all of
+  /// the entries.
+  pub fn set_min_rows_required(&self, rows: u16) {
+    let mut internal_state = self.state.lock();
+    internal_state.min_rows_required = rows;
+  }
+
+  /// Draws the provided text instead of the entries until this is
+  /// called with `None`, such as while waiting on a lock. The entries
+  /// stay alive while overridden and are drawn again once it's cleared.
+  /// Lines wider than the console are truncated.
+  pub fn override_frame(&self, text: Option<String>) {
+    let mut internal_state = self.state.lock();
+    internal_state.frame_override = text;
+  }
+
+  /// Sets how the frames are output to the terminal. Defaults
+  /// to `RenderMode::Static`.
+  pub fn set_render_mode(&self, mode: RenderMode) {
+    let mut internal_state = self.state.lock();
+    if internal_state.render_mode == mode {
+      return;
+    }
+    if internal_state.has_draw_thread && internal_state.hide_count == 0 {
+      internal_state.clear_drawn_text();
+    }
+    internal_state.render_mode = mode;
+    internal_state.last_appended = None;
+  }
+
+  /// Sets how lines of the rendered text that are wider than the
+  /// console are handled. Defaults to `WrapMode::None`.
+  pub fn set_wrap_mode(&self, mode: WrapMode) {
+    let mut internal_state = self.state.lock();
+    internal_state.wrap_mode = mode;
+  }
+
+  /// Sets whether the tab separated cells in the rendered text of the
+  /// entries are aligned into columns across all the entries, which
+  /// draws independent renderers as a table. Defaults to `false`.
+  pub fn set_table_align(&self, value: bool) {
+    let mut internal_state = self.state.lock();
+    internal_state.table_align = value;
+  }
+
+  /// Sets whether identical adjacent lines of the region are collapsed
+  /// into one line with a `(xN)` suffix, such as when many workers are
+  /// waiting on the same lock. Lines that are identical but not
+  /// adjacent stay separate. Defaults to `false`.
+  pub fn set_collapse_duplicates(&self, value: bool) {
+    let mut internal_state = self.state.lock();
+    internal_state.collapse_duplicates = value;
+  }
+
+  /// Sets how long the drawn text may stay unchanged before it's
+  /// marked as having no updates, such as when a task hangs.
+  /// Defaults to `None`, which never marks it.
+  pub fn set_stale_timeout(&self, timeout: Option<Duration>) {
+    let mut internal_state = self.state.lock();
+    internal_state.stale_timeout = timeout;
+  }
+
+  /// Sets how long each entry has to render each frame, which limits
+  /// how much a slow renderer delays the frame and the other entries.
+  /// Defaults to `None`, which renders the entries on the draw thread
+  /// and always waits for them.
+  ///
+  /// With a timeout, the entries are rendered on a worker thread that's
+  /// reused across the frames and each is waited on up until the timeout.
+  /// A renderer can't be interrupted, so an entry that doesn't render
+  /// in time keeps rendering in the background while the frame is drawn
+  /// with its last text. It's not rendered again until that render
+  /// finishes, whose text is drawn in the next frame after. A warning
+  /// is logged each time an entry exceeds the timeout.
+  pub fn set_render_timeout(&self, timeout: Option<Duration>) {
+    let mut internal_state = self.state.lock();
+    internal_state.render_timeout = timeout;
+    if timeout.is_none() {
+      internal_state.render_workers.lock().clear();
+    }
+  }
+
+  /// Confines the entries to a rectangle of the terminal, such as when a
+  /// host application owns the rest of the screen. The renderers are
+  /// provided the size of the viewport and their output is clipped to
+  /// it. `None` draws the entries below the cursor using the full width
+  /// of the terminal, which is the default.
+  pub fn set_viewport(&self, viewport: Option<Viewport>) {
+    let mut internal_state = self.state.lock();
+    if internal_state.viewport == viewport {
+      return;
+    }
+    if internal_state.has_draw_thread && internal_state.hide_count == 0 {
+      internal_state.clear_drawn_text();
+    }
+    internal_state.viewport = viewport;
+  }
+
+  /// Sets where the region is drawn in the terminal. Defaults
+  /// to `Pin::Bottom`.
+  pub fn set_pin(&self, pin: Pin) {
+    let mut internal_state = self.state.lock();
+    if internal_state.pin == pin {
+      return;
+    }
+    if internal_state.has_draw_thread && internal_state.hide_count == 0 {
+      internal_state.clear_drawn_text();
+    }
+    internal_state.pin = pin;
+  }
+
+  /// Prints the line to stderr above the drawn region by clearing the
+  /// region, printing the line, and then drawing the region again.
+  /// Prefer the `draw_eprintln!` macro.
+  ///
+  /// With `set_output_backpressure` enabled, the region isn't drawn again
+  /// while many lines are being printed and is instead drawn by the draw
+  /// thread once the output slows down.
+  pub fn println(&self, text: &str) {
+    let mut internal_state = self.state.lock();
+    let line = format!("{}\n", text);
+    let now = Instant::now();
+    if internal_state.has_draw_thread {
+      internal_state.print_rate.record(now);
+    }
+    match internal_state.last_drawn.take() {
+      Some((drawn_text, size)) => {
+        internal_state.begin_synchronized_update();
+        internal_state.clear_drawn_text();
+        internal_state.write_terminal(&line);
+        if !internal_state.is_backing_off(now) {
+          internal_state.draw_text(&drawn_text, size);
+        }
+        internal_state.end_synchronized_update();
+      }
+      None => internal_state.write_terminal(&line),
+    }
+  }
+
+  /// Leaves the currently drawn frame in the scrollback as if the
+  /// operations paused there, such as to checkpoint a milestone, and
+  /// continues drawing a new region below it.
+  ///
+  /// This does nothing when nothing is drawn, or when the region isn't
+  /// part of the scrollback, such as when it's pinned or in a viewport.
+  pub fn commit_current(&self) {
+    let mut internal_state = self.state.lock();
+    if internal_state.last_drawn.take().is_none() {
+      return;
+    }
+    // the static text leaves the cursor on the last row of the frame,
+    // so move below it and forget the frame so it's not overwritten
+    internal_state.write_terminal("\n");
+    internal_state.static_text = new_static_text();
+    internal_state.set_drawn_row_count(0);
+  }
+
+  /// Sets whether the draw thread draws less often while the process is
+  /// starved of CPU, such as on a loaded system, which is detected by the
+  /// draw thread consistently sleeping longer than requested. The
+  /// interval stays within one to four times the regular interval.
+  /// Defaults to `false`.
+  pub fn set_adaptive_interval(&self, value: bool) {
+    let mut internal_state = self.state.lock();
+    internal_state.adaptive_interval = value;
+  }
+
+  /// Sets whether the draw thread draws less often while many lines are
+  /// printed via `println`, since clearing the region and drawing it
+  /// again for every line causes flicker. The region is drawn again once
+  /// the output slows down. Defaults to `false`.
+  pub fn set_output_backpressure(&self, value: bool) {
+    let mut internal_state = self.state.lock();
+    internal_state.output_backpressure = value;
+  }
+
+  /// Sets the value of its type in the context provided to
+  /// `DrawThreadRenderer::render_with_context`, replacing any previous
+  /// value of the same type.
+  pub fn set_context<T: std::any::Any + Send + Sync>(&self, value: Arc<T>) {
+    let mut internal_state = self.state.lock();
+    Arc::make_mut(&mut internal_state.context).set(value);
+  }
+
+  /// Removes the value of the provided type from the context.
+  pub fn remove_context<T: std::any::Any + Send + Sync>(&self) {
+    let mut internal_state = self.state.lock();
+    Arc::make_mut(&mut internal_state.context).remove::<T>();
+  }
+
+  /// Sets how many of the most recent lines of `DrawThread::log` are
+  /// drawn above the entries. Unlike `println`, these lines are part of
+  /// the drawn region, so they reflow when the console is resized. The
+  /// oldest lines are omitted when they don't fit in the console along
+  /// with the entries. Defaults to zero, which disables this.
+  pub fn set_managed_log_lines(&self, count: usize) {
+    let mut internal_state = self.state.lock();
+    internal_state.managed_log_lines = count;
+    internal_state.trim_history();
+  }
+
+  /// Logs the line above the entries, keeping only the number of lines
+  /// set by `set_managed_log_lines`. The line is printed like `println`
+  /// when there are no managed log lines or no region is drawn. The
+  /// lines are discarded once the last entry finishes.
+  pub fn log(&self, line: impl Into<String>) {
+    let line = line.into();
+    {
+      let mut internal_state = self.state.lock();
+      let is_managed = internal_state.managed_log_lines > 0
+        && internal_state.has_draw_thread
+        && internal_state.render_mode == RenderMode::Static;
+      if is_managed {
+        internal_state.log_lines.push_back(line);
+        internal_state.trim_history();
+        return;
+      }
+    }
+    self.println(&line);
+  }
+
+  /// Prints the line once the last entry finishes and the region is
+  /// cleared, so that it stays in the scrollback, such as
+  /// "Done in 4.2s". The line is printed immediately when there are no
+  /// entries and replaces any line that's still pending.
+  pub fn finish_with_summary(&self, line: impl Into<String>) {
+    let mut internal_state = self.state.lock();
+    internal_state.pending_summary = Some(line.into());
+    if internal_state.entries.is_empty() {
+      Self::on_entries_finished(&mut internal_state);
+    }
+  }
+
+  /// Sets what to output instead of drawing to the terminal, such as
+  /// JSON lines that wrappers can parse. Defaults to `Fallback::None`.
+  pub fn set_fallback(&self, fallback: Fallback) {
+    let mut internal_state = self.state.lock();
+    if internal_state.fallback == fallback {
+      return;
+    }
+    Self::clear_and_stop_draw_thread(&mut internal_state);
+    internal_state.fallback = fallback;
+    self.maybe_start_draw_thread(&mut internal_state);
+  }
+
+  /// Gets a snapshot of the ids of the entries that are currently alive,
+  /// in the order they're drawn.
+  pub fn active_ids(&self) -> Vec<u16> {
+    let internal_state = self.state.lock();
+    internal_state.entries.iter().map(|e| e.id).collect()
+  }
+
+  /// Gets a snapshot of the id and `Debug` representation of the renderer
+  /// of each entry that's alive, in the order they're drawn, such as for
+  /// diagnosing entries that are never removed.
+  pub fn describe_entries(&self) -> Vec<(u16, String)> {
+    let renderers = {
+      let internal_state = self.state.lock();
+      internal_state
+        .entries
+        .iter()
+        .map(|e| (e.id, e.renderer.clone()))
+        .collect::<Vec<_>>()
+    };
+    // format outside the lock since the renderers may lock their state
+    renderers
+      .into_iter()
+      .map(|(id, renderer)| (id, format!("{:?}", renderer)))
+      .collect()
+  }
+
+  /// Sets whether a record of each entry is kept once it finishes,
+  /// which can be taken via `DrawThread::take_completion_log` for a
+  /// summary of the operations after they're done. Defaults to `false`
+  /// so that the records don't accumulate by default.
+  pub fn set_record_completions(&self, value: bool) {
+    let mut internal_state = self.state.lock();
+    internal_state.record_completions = value;
+  }
+
+  /// Takes the records of the entries that finished since the
+  /// last call, in the order they finished.
+  pub fn take_completion_log(&self) -> Vec<EntryCompletion> {
+    std::mem::take(&mut self.state.lock().completions).into()
+  }
+
+  /// Sets the maximum number of completion records and managed log lines
+  /// that are kept, where the oldest are removed first, so that they don't
+  /// grow unbounded in a long running process. Defaults to 1000.
+  pub fn set_history_limit(&self, limit: usize) {
+    let mut internal_state = self.state.lock();
+    internal_state.history_limit = limit;
+    internal_state.trim_history();
+  }
+
+  /// Renders the entries at the provided width as plain text without any
+  /// styles, such as for writing the final state of the progress to a
+  /// log. This doesn't depend on the console, so the renderers are
+  /// provided as many rows as they need and lines wider than the width
+  /// are wrapped. Nothing is drawn and the frame number isn't advanced.
+  pub fn render_plain(&self, cols: u16) -> String {
+    let (
+      entries,
+      context,
+      frame,
+      table_align,
+      wrap_mode,
+      join_mode,
+      collapse_duplicates,
+    ) = {
+      let internal_state = self.state.lock();
+      (
+        internal_state.entries_to_render(),
+        internal_state.context.clone(),
+        internal_state.next_frame,
+        internal_state.table_align,
+        internal_state.wrap_mode,
+        internal_state.join_mode,
+        internal_state.collapse_duplicates,
+      )
+    };
+    // call into the renderers outside the lock
+    let size = ConsoleSize {
+      cols: cols as u32,
+      rows: u16::MAX as u32,
+    };
+    let context = with_summary_counts(&entries, context);
+    let entries = entries
+      .into_iter()
+      .filter(|e| e.is_drawn(&size))
+      .collect::<Vec<_>>();
+    let mut rendered = render_entries(&entries, &size, frame, &context);
+    format_rendered(&entries, &mut rendered, &size, table_align, wrap_mode);
+    for (entry, (_, text)) in entries.iter().zip(&mut rendered) {
+      let cols = indented_size(&size, entry.indent).cols as usize;
+      if text.split('\n').any(|line| display_width(line) > cols) {
+        *text = wrap_to_width(text, cols);
+      }
+    }
+    let text =
+      join_rendered(&entries, &rendered, join_mode, collapse_duplicates);
+    console_static_text::ansi::strip_ansi_codes(&text).into_owned()
+  }
+
+  /// Gets the statistics of the current or most recent draw thread.
+  pub fn stats(&self) -> DrawStats {
+    self.state.lock().stats
+  }
+
+  /// Gets the statistics of the current or most recent draw thread and
+  /// resets them, such as for sampling the rates over an interval. No
+  /// frame is counted by two calls.
+  pub fn take_stats(&self) -> DrawStats {
+    std::mem::take(&mut self.state.lock().stats)
+  }
+
+  /// Gets whether the most recent render pass wrote a frame to the
+  /// terminal, which it doesn't do when the frame is unchanged or the
+  /// region is hidden, such as to find out why an update isn't shown.
+  pub fn last_frame_emitted(&self) -> bool {
+    self.state.lock().last_frame_emitted
+  }
+
+  /// Clears the region and stops the draw thread until the returned
+  /// token is resumed, keeping all the entries. Unlike `hide()`, this
+  /// fully relinquishes the terminal, such as for a subprocess that
+  /// takes it over.
+  pub fn suspend(&self) -> SuspendToken {
+    let mut internal_state = self.state.lock();
+    internal_state.suspend_count += 1;
+    Self::clear_and_stop_draw_thread(&mut internal_state);
+    SuspendToken {
+      instance: self.clone(),
+    }
+  }
+
+  /// Hides the draw thread until the matching `show()`. The calls nest
+  /// and are counted under the lock, so a `hide()` and its `show()` may
+  /// be called on different threads, such as by the logger.
+  pub fn hide(&self) {
+    let mut internal_state = self.state.lock();
+    let is_showing =
+      internal_state.has_draw_thread && internal_state.hide_count == 0;
+    internal_state.hide_count += 1;
+
+    if is_showing {
+      // Clear it on the current thread in order to stop it from
+      // showing immediately. Also, don't stop the draw thread here
+      // because the calling code might be called from outside a
+      // tokio runtime and when it goes to start the thread on the
+      // thread pool it might panic.
+      internal_state.clear_region();
+      internal_state.clear_sinks();
+    }
+  }
+
+  /// Shows the draw thread if it was previously hidden.
+  pub fn show(&self) {
+    let mut internal_state = self.state.lock();
+    if internal_state.hide_count > 0 {
+      internal_state.hide_count -= 1;
+    }
+  }
+
+  /// Hides the draw thread until the returned guard is dropped.
+  ///
+  /// The guards are counted per thread and only the outermost guard on
+  /// each thread takes the lock, which counts it like a `hide()`. The
+  /// nested guards are balanced once they're dropped, so they're cheap
+  /// in hot paths that hide the draw thread many times.
+  pub fn hide_scope(&self) -> HideGuard {
+    let key = self.hide_depth_key();
+    let is_nested = HIDE_DEPTHS.with(|depths| {
+      let mut depths = depths.borrow_mut();
+      match depths.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, depth)) => {
+          *depth += 1;
+          true
+        }
+        None => {
+          depths.push((key, 1));
+          false
+        }
+      }
+    });
+    if !is_nested {
+      self.hide();
+    }
+    HideGuard {
+      instance: self.clone(),
+      _not_send: PhantomData,
+    }
+  }
+
+  /// Identifies the instance in `HIDE_DEPTHS`, which the guards keep alive.
+  fn hide_depth_key(&self) -> usize {
+    Arc::as_ptr(&self.state) as usize
+  }
+
+  /// Sets whether rendering is driven manually with `tick()` instead
+  /// of by a spawned draw thread, such as for deterministic tests or
+  /// when embedding in an external event loop. Set this before adding
+  /// any entries.
+  pub fn set_manual(&self, value: bool) {
+    let mut internal_state = self.state.lock();
+    if internal_state.manual == value {
+      return;
+    }
+    Self::clear_and_stop_draw_thread(&mut internal_state);
+    internal_state.manual = value;
+    internal_state.manual_pass_state = None;
+    self.maybe_start_draw_thread(&mut internal_state);
+  }
+
+  /// Clears the region and stops drawing for good, such as while the
+  /// process exits, so that an entry added by a task that's late to
+  /// finish doesn't start the draw thread again during the teardown.
+  ///
+  /// The entries added after this return a guard that does nothing and
+  /// the entries that were already added are no longer drawn. Ticking
+  /// in manual mode does nothing either. This lasts until `reset()`.
+  pub fn shutdown(&self) {
+    let mut internal_state = self.state.lock();
+    internal_state.shutting_down = true;
+    Self::clear_and_stop_draw_thread(&mut internal_state);
+  }
+
+  /// Allows drawing again after `shutdown()`, such as for reusing the
+  /// draw thread between tests. The entries added during the shutdown
+  /// stay unregistered.
+  pub fn reset(&self) {
+    let mut internal_state = self.state.lock();
+    internal_state.shutting_down = false;
+    self.maybe_start_draw_thread(&mut internal_state);
+  }
+
+  /// Drives the draw thread by calling `tick` at the interval on a task
+  /// spawned on the runtime rather than on a thread of its own, such as
+  /// for an app that already has a periodic tick for its UI.
+  ///
+  /// Attaching switches to the manual mode, which stops the draw
+  /// thread's own loop, and attaching another ticker stops this one.
+  /// Dropping the returned guard stops the task and restores the manual
+  /// mode from before the ticker was attached, unless another ticker
+  /// was attached since. Setting the manual mode to `false` while the
+  /// ticker is attached also switches back, after which the ticks do
+  /// nothing. Each tick runs on a blocking thread so that rendering
+  /// doesn't stall the runtime's other tasks.
+  ///
+  /// The ticks do nothing while drawing isn't supported, like the
+  /// draw thread's own loop.
+  pub fn attach_ticker(
+    &self,
+    interval: Duration,
+    handle: &tokio::runtime::Handle,
+  ) -> TickerGuard {
+    let (id, token) = {
+      let mut internal_state = self.state.lock();
+      let previous_manual = match internal_state.ticker.take() {
+        Some(ticker) => {
+          ticker.token.cancel();
+          ticker.previous_manual
+        }
+        None => internal_state.manual,
+      };
+      let id = internal_state.next_ticker_id;
+      internal_state.next_ticker_id += 1;
+      let token = CancellationToken::new();
+      internal_state.ticker = Some(AttachedTicker {
+        id,
+        token: token.clone(),
+        previous_manual,
+      });
+      (id, token)
+    };
+    self.set_manual(true);
+
+    let instance = self.clone();
+    let task_token = token.clone();
+    handle.spawn(async move {
+      let mut interval = tokio::time::interval(interval);
+      interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+      loop {
+        tokio::select! {
+          biased;
+          _ = task_token.cancelled() => break,
+          _ = interval.tick() => {
+            let is_supported = DrawThread::is_supported()
+              || instance.state.lock().uses_json_lines();
+            if is_supported {
+              // the tick renders and writes to the terminal, which blocks
+              let instance = instance.clone();
+              let _ = tokio::task::spawn_blocking(move || instance.tick())
+                .await;
+            }
+          }
+        }
+      }
+    });
+    TickerGuard {
+      id,
+      token,
+      instance: self.clone(),
+    }
+  }
+
+  /// Performs exactly one render pass when in manual mode, returning
+  /// whether a frame was drawn. Ticking while not in manual mode
+  /// does nothing.
+  pub fn tick(&self) -> bool {
+    let mut pass_state = {
+      let mut internal_state = self.state.lock();
+      if !internal_state.manual || internal_state.shutting_down {
+        return false;
+      }
+      match internal_state.manual_pass_state.take() {
+        Some(pass_state) => pass_state,
+        None => RenderPassState::new(internal_state.console_size()),
+      }
+    };
+    let outcome = {
+      let _flag = DrawThreadFlag::set();
+      render_pass(&self.state, None, &mut pass_state)
+    };
+    let mut internal_state = self.state.lock();
+    if internal_state.manual {
+      internal_state.manual_pass_state = Some(pass_state);
+    }
+    outcome == RenderPassOutcome::Drawn
+  }
+
+  /// Synchronously draws the first frame on the calling thread, returning
+  /// whether it was drawn. This shows the entries during blocking work
+  /// that's done before the draw thread gets a chance to run. Does
+  /// nothing once a frame was drawn, so it's safe to call multiple times,
+  /// and does nothing in manual mode.
+  pub fn prime(&self) -> bool {
+    let (drawer_id, mut pass_state) = {
+      let mut internal_state = self.state.lock();
+      self.maybe_start_draw_thread(&mut internal_state);
+      if !internal_state.has_draw_thread
+        || internal_state.manual
+        || internal_state.stats.frames_rendered > 0
+      {
+        return false;
+      }
+      (
+        internal_state.drawer_id,
+        RenderPassState::new(internal_state.console_size()),
+      )
+    };
+    let _flag = DrawThreadFlag::set();
+    render_pass(&self.state, Some(drawer_id), &mut pass_state)
+      == RenderPassOutcome::Drawn
+  }
+
+  fn finish_entry(&self, entry_id: u16) {
+    self.finish_entry_unless_cancelled(entry_id, None)
+  }
+
+  /// Finishes the entry a watching task was started for. The watcher
+  /// token is cancelled under the lock once the entry is finished, so
+  /// this never finishes a later entry that reused the id.
+  fn finish_watched_entry(
+    &self,
+    entry_id: u16,
+    watcher_token: &CancellationToken,
+  ) {
+    self.finish_entry_unless_cancelled(entry_id, Some(watcher_token))
+  }
+
+  fn finish_entry_unless_cancelled(
+    &self,
+    entry_id: u16,
+    watcher_token: Option<&CancellationToken>,
+  ) {
+    let mut internal_state = self.state.lock();
+    if watcher_token.is_some_and(|token| token.is_cancelled()) {
+      return;
+    }
+
+    if let Some(entry) = internal_state.entries.remove(entry_id) {
+      let last_text = internal_state.last_rendered.remove(&entry_id);
+      internal_state.drawn_versions.remove(&entry_id);
+      if let Some(watcher_token) = &entry.watcher_token {
+        watcher_token.cancel();
+      }
+
+      if internal_state.entries.is_empty() {
+        Self::on_entries_finished(&mut internal_state);
+      }
+      let record_completion = internal_state.record_completions;
+      // log outside the lock because logging hides the draw thread
+      drop(internal_state);
+
+      if record_completion {
+        let completion = EntryCompletion {
+          id: entry_id,
+          tag: entry.tag.as_deref().map(ToString::to_string),
+          elapsed: entry.added_at.elapsed(),
+          text: last_text
+            .map(|text| {
+              console_static_text::ansi::strip_ansi_codes(&text).into()
+            })
+            .unwrap_or_default(),
+          // call into the renderer outside the lock
+          failed: entry.renderer.is_failed(),
+        };
+        let mut internal_state = self.state.lock();
+        internal_state.completions.push_back(completion);
+        internal_state.trim_history();
+      }
+
+      #[cfg(feature = "tracing")]
+      tracing::debug!(
+        parent: entry.span.as_ref().and_then(|s| s.id()),
+        entry_id,
+        age_ms = entry.added_at.elapsed().as_millis() as u64,
+        "draw thread entry removed"
+      );
+    }
+  }
+
+  fn reap_expired_entries(internal_state: &mut InternalState, now: Instant) {
+    if internal_state.remove_expired_entries(now)
+      && internal_state.entries.is_empty()
+    {
+      Self::on_entries_finished(internal_state);
+    }
+  }
+
+  fn on_entries_finished(internal_state: &mut InternalState) {
+    Self::clear_and_stop_draw_thread(internal_state);
+    internal_state.log_lines.clear();
+    if let Some(summary) = internal_state.pending_summary.take() {
+      internal_state.write_terminal(&format!("{}\n", summary));
+    }
+  }
+
+  fn clear_and_stop_draw_thread(internal_state: &mut InternalState) {
+    if internal_state.has_draw_thread {
+      internal_state.clear_region();
+      internal_state.clear_sinks();
+      internal_state.clear_taskbar_progress();
+      // bump the drawer id to exit the draw thread
+      internal_state.drawer_id += 1;
+      internal_state.has_draw_thread = false;
+      internal_state.last_frame_change = None;
+      internal_state.last_appended = None;
+    }
+  }
+
+  fn maybe_start_draw_thread(&self, internal_state: &mut InternalState) {
+    if internal_state.has_draw_thread
+      || internal_state.shutting_down
+      || internal_state.manual
+      || internal_state.suspend_count > 0
+      || internal_state.entries.is_empty()
+      || !(DrawThread::is_supported() || internal_state.uses_json_lines())
+    {
+      return;
+    }
+
+    internal_state.drawer_id += 1;
+    internal_state.has_draw_thread = true;
+    internal_state.stats = Default::default();
+
+    let drawer_id = internal_state.drawer_id;
+    let mut pass_state = RenderPassState::new(internal_state.console_size());
+    let state = self.state.clone();
+    spawn_blocking(move || {
+      // blocking threads are reused, so this is unset once the loop exits
+      let _flag = DrawThreadFlag::set();
+      loop {
+        let delay = match render_pass(&state, Some(drawer_id), &mut pass_state)
+        {
+          RenderPassOutcome::Exit => break,
+          // means the user is actively resizing the console...
+          // wait a little bit until they stop resizing
+          RenderPassOutcome::Resizing => Duration::from_millis(200),
+          // let the printed lines flush before drawing again
+          RenderPassOutcome::BackingOff => backpressure::BACKOFF_INTERVAL,
+          RenderPassOutcome::Drawn | RenderPassOutcome::Skipped => {
+            pass_state.draw_interval()
+          }
+        };
+        let sleep_start = Instant::now();
+        std::thread::sleep(delay);
+        if pass_state.adaptive_interval.is_some() {
+          pass_state.record_sleep(delay, sleep_start.elapsed());
+        }
+      }
+    });
+  }
+}
+
+thread_local! {
+  static IS_DRAW_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+thread_local! {
+  /// The number of nested `HideGuard`s on this thread for each instance,
+  /// where only the outermost one is counted in the shared state.
+  static HIDE_DEPTHS: RefCell<Vec<(usize, usize)>> =
+    const { RefCell::new(Vec::new()) };
+}
+
+/// Marks the current thread as the draw thread until dropped.
+struct DrawThreadFlag {
+  previous: bool,
+}
+
+impl DrawThreadFlag {
+  fn set() -> Self {
+    Self {
+      previous: IS_DRAW_THREAD.with(|flag| flag.replace(true)),
+    }
+  }
+}
+
+impl Drop for DrawThreadFlag {
+  fn drop(&mut self) {
+    IS_DRAW_THREAD.with(|flag| flag.set(self.previous));
+  }
+}
+
+/// State kept between render passes.
+#[derive(Debug)]
+struct RenderPassState {
+  previous_size: Option<ConsoleSize>,
+  last_render_time: Option<Instant>,
+  /// Set while `DrawThread::set_adaptive_interval` is enabled.
+  adaptive_interval: Option<interval::AdaptiveInterval>,
+}
+
+impl RenderPassState {
+  fn new(size: Option<ConsoleSize>) -> Self {
+    Self {
+      previous_size: size,
+      last_render_time: None,
+      adaptive_interval: None,
+    }
+  }
+
+  fn draw_interval(&self) -> Duration {
+    self
+      .adaptive_interval
+      .as_ref()
+      .map(|interval| interval.current())
+      .unwrap_or(DRAW_INTERVAL)
+  }
+
+  fn record_sleep(&mut self, requested: Duration, actual: Duration) {
+    if let Some(interval) = &mut self.adaptive_interval {
+      interval.record_sleep(requested, actual);
+    }
+  }
+
+  fn set_adaptive_interval(&mut self, value: bool) {
+    if value != self.adaptive_interval.is_some() {
+      self.adaptive_interval =
+        value.then(|| interval::AdaptiveInterval::new(DRAW_INTERVAL));
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RenderPassOutcome {
+  /// Rendering should stop.
+  Exit,
+  /// A frame was drawn.
+  Drawn,
+  /// Nothing was drawn because the output is hidden.
+  Skipped,
+  /// Nothing was drawn because the console is being resized.
+  Resizing,
+  /// Nothing was drawn because many lines are being printed.
+  BackingOff,
+}
+
+/// Renders the message of `DrawThread::flash`.
+#[derive(Debug)]
+struct FlashRenderer(String);
+
+impl DrawThreadRenderer for FlashRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    truncate_to_width(&self.0, size.cols as usize)
+  }
+}
+
+/// The settings of a render pass, which are copied out of the
+/// internal state so the renderers can be called outside the lock.
+struct PassSettings {
+  entries: Vec<InternalEntry>,
+  taskbar_progress: bool,
+  join_mode: JoinMode,
+  size_provider: fn() -> Option<ConsoleSize>,
+  size_override: Option<ConsoleSize>,
+  max_cols: Option<u16>,
+  min_rows_required: u16,
+  frame_override: Option<String>,
+  viewport: Option<Viewport>,
+  json_lines: bool,
+  table_align: bool,
+  collapse_duplicates: bool,
+  wrap_mode: WrapMode,
+  order_direction: OrderDirection,
+  debug_overlay: bool,
+  log_lines: Vec<String>,
+  /// The sinks that render at their own size.
+  sink_sizes: Vec<(usize, ConsoleSize)>,
+  render_timeout: Option<Duration>,
+  /// The text each entry rendered in the last frame, which is only
+  /// provided when using the render timeout.
+  cached_texts: HashMap<u16, String>,
+  render_workers: Arc<Mutex<RenderWorkers>>,
+  context: Arc<RenderContext>,
+  frame: u64,
+}
+
+/// Performs a single render pass. The drawer id is the id of the
+/// draw thread doing the pass or `None` in manual mode.
+fn render_pass(
+  state: &Mutex<InternalState>,
+  drawer_id: Option<usize>,
+  pass_state: &mut RenderPassState,
+) -> RenderPassOutcome {
+  fn should_exit(
+    internal_state: &InternalState,
+    drawer_id: Option<usize>,
+  ) -> bool {
+    match drawer_id {
+      Some(drawer_id) => internal_state.should_exit_draw_thread(drawer_id),
+      None => !internal_state.manual || internal_state.entries.is_empty(),
+    }
+  }
+
+  // Get the entries to render.
+  let maybe_entries = {
+    let mut internal_state = state.lock();
+    DrawThreadInstance::reap_expired_entries(
+      &mut internal_state,
+      Instant::now(),
+    );
+    // set once the text is written below
+    internal_state.last_frame_emitted = false;
+    pass_state.set_adaptive_interval(internal_state.adaptive_interval);
+    if should_exit(&internal_state, drawer_id) {
+      return RenderPassOutcome::Exit;
+    }
+    // a manual caller decides when to render
+    let is_append_pending = drawer_id.is_some()
+      && internal_state.render_mode == RenderMode::AppendOnly
+      && internal_state
+        .last_appended
+        .as_ref()
+        .is_some_and(|(_, time)| time.elapsed() < APPEND_ONLY_INTERVAL);
+    if is_append_pending {
+      pass_state.last_render_time = None;
+      return RenderPassOutcome::Skipped;
+    }
+    if drawer_id.is_some() && internal_state.is_backing_off(Instant::now()) {
+      pass_state.last_render_time = None;
+      return RenderPassOutcome::BackingOff;
+    }
+    let should_display =
+      internal_state.hide_count == 0 && internal_state.suspend_count == 0;
+    should_display.then(|| {
+      let frame = internal_state.next_frame;
+      internal_state.next_frame += 1;
+      PassSettings {
+        entries: internal_state.entries_to_render(),
+        taskbar_progress: internal_state.taskbar_progress,
+        join_mode: internal_state.join_mode,
+        size_provider: internal_state.size_provider,
+        size_override: internal_state.size_override,
+        max_cols: internal_state.max_cols,
+        min_rows_required: internal_state.min_rows_required,
+        frame_override: internal_state.frame_override.clone(),
+        viewport: internal_state.viewport,
+        json_lines: internal_state.uses_json_lines(),
+        table_align: internal_state.table_align,
+        collapse_duplicates: internal_state.collapse_duplicates,
+        wrap_mode: internal_state.wrap_mode,
+        order_direction: internal_state.order_direction,
+        debug_overlay: internal_state.debug_overlay,
+        log_lines: internal_state.log_lines.iter().cloned().collect(),
+        sink_sizes: internal_state
+          .sinks
+          .iter()
+          .filter_map(|(id, sink)| Some((*id, sink.size()?)))
+          .collect(),
+        render_timeout: internal_state.render_timeout,
+        cached_texts: match internal_state.render_timeout {
+          Some(_) => internal_state.last_rendered.clone(),
+          None => HashMap::new(),
+        },
+        render_workers: internal_state.render_workers.clone(),
+        context: internal_state.context.clone(),
+        frame,
+      }
+    })
+  };
+
+  let Some(PassSettings {
+    entries,
+    taskbar_progress,
+    join_mode,
+    size_provider,
+    size_override,
+    max_cols,
+    min_rows_required,
+    frame_override,
+    viewport,
+    json_lines,
+    table_align,
+    collapse_duplicates,
+    wrap_mode,
+    order_direction,
+    debug_overlay,
+    log_lines,
+    sink_sizes,
+    render_timeout,
+    cached_texts,
+    render_workers,
+    context,
+    frame,
+  }) = maybe_entries
+  else {
+    // don't count the time spent hidden as falling behind
+    pass_state.last_render_time = None;
+    return RenderPassOutcome::Skipped;
+  };
+
+  // this should always be set, but have the code handle
+  // it not being for some reason
+  let size = size_override.or_else(size_provider);
+  CACHED_CONSOLE_SIZE.set(size);
+
+  // Call into the renderers outside the lock to prevent a potential
+  // deadlock between our internal state lock and the renderers
+  // internal state lock.
+  //
+  // Example deadlock if this code didn't do this:
+  // 1. Other thread - Renderer - acquired internal lock to update state
+  // 2. This thread  - Acquired internal state
+  // 3. Other thread - Renderer - drops DrawThreadGuard
+  // 4. This thread - Calls renderer.render within internal lock,
+  //    which attempts to acquire the other thread's Render's internal
+  //    lock causing a deadlock
+  if size != pass_state.previous_size {
+    pass_state.previous_size = size;
+    pass_state.last_render_time = None;
+    return RenderPassOutcome::Resizing;
+  }
+  let size = match size {
+    Some(size) => size,
+    None if json_lines => JSON_LINES_CONSOLE_SIZE,
+    None => return RenderPassOutcome::Skipped,
+  };
+  let mut size = viewport.map(|v| v.size()).unwrap_or(size);
+  if let Some(max_cols) = max_cols {
+    size.cols = size.cols.min(max_cols as u32);
+  }
+  // count the entries that aren't drawn at this size too
+  let context = with_summary_counts(&entries, context);
+  let mut entries = entries
+    .into_iter()
+    .filter(|e| e.is_drawn(&size))
+    .collect::<Vec<_>>();
+  if frame_override.is_some() {
+    entries.clear();
+  }
+  let is_compact = size.rows < min_rows_required as u32;
+  if is_compact {
+    entries = latest_entry(entries, order_direction).into_iter().collect();
+  }
+
+  // the first entry with a determinate progress provides
+  // the aggregate progress shown in the taskbar
+  let taskbar_progress = if taskbar_progress {
+    entries.iter().find_map(|e| e.renderer.progress())
+  } else {
+    None
+  };
+  // get the versions before rendering so they're never newer than
+  // the text that's drawn
+  let versions = entries
+    .iter()
+    .filter_map(|e| Some((e.id, e.renderer.version()?)))
+    .collect::<Vec<_>>();
+  let render_start = Instant::now();
+  let (mut rendered, timed_out_ids) = match render_timeout {
+    Some(timeout) => render_entries_with_timeout(
+      &entries,
+      &size,
+      frame,
+      &context,
+      timeout,
+      &cached_texts,
+      &render_workers,
+    ),
+    None => (render_entries(&entries, &size, frame, &context), Vec::new()),
+  };
+  let render_time = render_start.elapsed();
+  format_rendered(&entries, &mut rendered, &size, table_align, wrap_mode);
+  let json_lines =
+    json_lines.then(|| render_json_lines(&entries, &rendered, &size));
+  let mut text =
+    join_rendered(&entries, &rendered, join_mode, collapse_duplicates);
+  if is_compact {
+    text.truncate(text.find('\n').unwrap_or(text.len()));
+  }
+  // the overlay takes the last row
+  let content_size = ConsoleSize {
+    rows: size.rows.saturating_sub(debug_overlay as u32),
+    ..size
+  };
+  let text = match order_direction {
+    // the static text cuts off the top rows, which are the newest
+    OrderDirection::Newest => take_top_rows(&text, &content_size),
+    OrderDirection::Oldest => text,
+  };
+  let text = match &frame_override {
+    Some(text) => text
+      .lines()
+      .map(|line| truncate_to_width(line, size.cols as usize))
+      .collect::<Vec<_>>()
+      .join("\n"),
+    None => prepend_log_lines(&log_lines, text, &content_size),
+  };
+  // the log lines and overlay are only drawn to the terminal
+  let mut sink_texts: Vec<(usize, String, ConsoleSize)> =
+    Vec::with_capacity(sink_sizes.len());
+  for (id, sink_size) in sink_sizes {
+    // render the entries once per size since rendering may have side
+    // effects, such as sampling the rate of a progress
+    let same_size_text = sink_texts
+      .iter()
+      .find(|(_, _, size)| *size == sink_size)
+      .map(|(_, text, _)| text.clone());
+    if let Some(text) = same_size_text {
+      sink_texts.push((id, text, sink_size));
+      continue;
+    }
+    let sink_entries;
+    let sink_rendered;
+    let (entries, rendered) = if sink_size == size {
+      (&entries, &rendered)
+    } else {
+      sink_entries = entries
+        .iter()
+        .filter(|e| e.is_drawn(&sink_size))
+        .cloned()
+        .collect::<Vec<_>>();
+      let mut rendered =
+        render_entries(&sink_entries, &sink_size, frame, &context);
+      format_rendered(
+        &sink_entries,
+        &mut rendered,
+        &sink_size,
+        table_align,
+        wrap_mode,
+      );
+      sink_rendered = rendered;
+      (&sink_entries, &sink_rendered)
+    };
+    let text = join_rendered(entries, rendered, join_mode, collapse_duplicates);
+    let text = match &frame_override {
+      Some(text) => text
+        .lines()
+        .map(|line| truncate_to_width(line, sink_size.cols as usize))
+        .collect::<Vec<_>>()
+        .join("\n"),
+      None => match order_direction {
+        OrderDirection::Newest => take_top_rows(&text, &sink_size),
+        OrderDirection::Oldest => text,
+      },
+    };
+    sink_texts.push((id, text, sink_size));
+  }
+  let text = if debug_overlay {
+    let overlay =
+      debug_overlay_text(drawer_id, entries.len(), frame, render_time, &size);
+    join_entry_texts([text.as_str(), overlay.as_str()], JoinMode::SkipEmpty)
+  } else {
+    text
+  };
+
+  // now reacquire the lock, ensure we should still be drawing, then
+  // output the text
+  let mut internal_state = state.lock();
+  if should_exit(&internal_state, drawer_id) {
+    return RenderPassOutcome::Exit;
+  }
+  internal_state.cache_rendered(rendered);
+  let now = Instant::now();
+  if let Some(json_lines) = json_lines {
+    internal_state.write_json_lines(&json_lines);
+    internal_state.last_frame_emitted = true;
+  } else if text.is_empty() && internal_state.empty_frame == EmptyFrame::Keep {
+    // leave the last frame drawn
+  } else {
+    let text = internal_state.mark_if_stale(text, now);
+    internal_state.begin_synchronized_update();
+    if internal_state.clear_strategy == ClearStrategy::FullClear {
+      internal_state.clear_drawn_text();
+    }
+    internal_state.last_frame_emitted = if text.is_empty() {
+      internal_state.clear_drawn_text();
+      true
+    } else {
+      // the renderers and themes may use colors the terminal can't
+      // display, which are only downgraded when it's known to be limited
+      match TERMINAL_CAPS.color_depth {
+        Some(depth) if depth < ColorDepth::TrueColor => internal_state
+          .draw_text(&capabilities::downgrade_colors(&text, depth), size),
+        _ => internal_state.draw_text(&text, size),
+      }
+    };
+    let line_ending = internal_state.line_ending;
+    for (id, sink) in &mut internal_state.sinks {
+      match sink_texts.iter().find(|(sink_id, _, _)| sink_id == id) {
+        Some((_, sink_text, sink_size)) => {
+          sink.write_frame(sink_text, sink_size)
+        }
+        // added after the frame was rendered or it doesn't have a size
+        None if sink.size().is_some() => {}
+        None => sink.write_frame(&line_ending.apply(&text), &size),
+      }
+    }
+    if let Some(progress) = taskbar_progress {
+      internal_state.write_terminal(&taskbar_progress_text(progress));
+      internal_state.is_showing_taskbar_progress = true;
+    }
+    internal_state.end_synchronized_update();
+  }
+  if internal_state.last_frame_emitted {
+    internal_state.record_drawn_versions(versions, now);
+  }
+  if drawer_id.is_none() {
+    // so the region gets cleared once the last entry finishes
+    internal_state.has_draw_thread = true;
+  }
+
+  internal_state.stats.frames_rendered += 1;
+  // a manual caller decides when to render, so it never falls behind
+  if let (Some(last_render_time), Some(_)) =
+    (pass_state.last_render_time, drawer_id)
+  {
+    if now - last_render_time > pass_state.draw_interval() * 2 {
+      internal_state.stats.dropped_frames += 1;
+    }
+  }
+  pass_state.last_render_time = Some(now);
+  // log outside the lock because logging hides the draw thread
+  #[cfg(feature = "tracing")]
+  let frames_rendered = internal_state.stats.frames_rendered;
+  drop(internal_state);
+
+  #[cfg(feature = "tracing")]
+  tracing::debug!(
+    frames_rendered,
+    entries = entries.len(),
+    "draw thread frame rendered"
+  );
+  for entry in entries.iter().filter(|e| timed_out_ids.contains(&e.id)) {
+    log::warn!(
+      "Draw thread entry {} exceeded the render timeout, so its last text is drawn until it finishes rendering: {:?}",
+      entry.id,
+      entry.renderer
+    );
+  }
+
+  RenderPassOutcome::Drawn
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use test_util::assert_contains;
+  use test_util::assert_not_contains;
+
+  use super::tail::TailRenderer;
+
+  #[derive(Debug)]
+  struct TextRenderer(&'static str);
+
+  impl DrawThreadRenderer for TextRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      self.0.to_string()
+    }
+  }
+
+  /// Whether drawing is supported is global, so ensure the tests run one
+  /// at a time. Each test otherwise uses an instance of its own.
+  static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+  fn rendered_ids(instance: &DrawThreadInstance) -> Vec<u16> {
+    instance
+      .state
+      .lock()
+      .entries_to_render()
+      .iter()
+      .map(|e| e.id)
+      .collect()
+  }
+
+  #[test]
+  fn joins_entry_texts() {
+    let texts = || ["", "first", "second\nline", "", ""];
+    assert_eq!(
+      join_entry_texts(texts(), JoinMode::SkipEmpty),
+      "first\nsecond\nline"
+    );
+    assert_eq!(
+      join_entry_texts(texts(), JoinMode::OneLinePerEntry),
+      "\nfirst\nsecond\nline\n\n"
+    );
+  }
+
+  #[test]
+  fn expand_renders_only_last_expanded_entry() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let first = instance.add_entry(Arc::new(TextRenderer("first")));
+    let second = instance.add_entry(Arc::new(TextRenderer("second")));
+    let third = instance.add_entry(Arc::new(TextRenderer("third")));
+    assert_eq!(
+      rendered_ids(&instance),
+      vec![first.id.unwrap(), second.id.unwrap(), third.id.unwrap()]
+    );
+
+    let expand_first = first.expand();
+    assert_eq!(rendered_ids(&instance), vec![first.id.unwrap()]);
+    let expand_third = third.expand();
+    assert_eq!(rendered_ids(&instance), vec![third.id.unwrap()]);
+
+    // finishing the expanded entry falls back to the previous expand
+    drop(third);
+    assert_eq!(rendered_ids(&instance), vec![first.id.unwrap()]);
+    drop(expand_third);
+    assert_eq!(rendered_ids(&instance), vec![first.id.unwrap()]);
+    drop(expand_first);
+    assert_eq!(
+      rendered_ids(&instance),
+      vec![first.id.unwrap(), second.id.unwrap()]
+    );
+  }
+
+  #[test]
+  fn active_ids_match_live_guards() {
+    let _lock = TEST_LOCK.lock();
+    assert_eq!(DrawThread::active_ids(), Vec::<u16>::new());
+    let first = DrawThread::add_entry(Arc::new(TextRenderer("first")));
+    let second = DrawThread::add_entry(Arc::new(TextRenderer("second")));
+    let third = DrawThread::add_entry(Arc::new(TextRenderer("third")));
+    assert_eq!(
+      DrawThread::active_ids(),
+      vec![first.id.unwrap(), second.id.unwrap(), third.id.unwrap()]
+    );
+    drop(second);
+    assert_eq!(
+      DrawThread::active_ids(),
+      vec![first.id.unwrap(), third.id.unwrap()]
+    );
+    drop(first);
+    drop(third);
+    assert_eq!(DrawThread::active_ids(), Vec::<u16>::new());
+  }
+
+  #[test]
+  fn add_entry_until_removes_entry() {
+    use deno_core::futures::future;
+    use deno_core::futures::FutureExt;
+
+    let _lock = TEST_LOCK.lock();
+
+    let instance = DrawThread::new();
+    let output = instance
+      .add_entry_until(Arc::new(TextRenderer("text")), async {
+        instance.active_ids().len()
+      })
+      .now_or_never();
+    assert_eq!(output, Some(1));
+    assert_eq!(instance.active_ids(), Vec::<u16>::new());
+
+    // dropping the pending future removes the entry
+    let output = instance
+      .add_entry_until(Arc::new(TextRenderer("text")), future::pending::<()>())
+      .now_or_never();
+    assert_eq!(output, None);
+    assert_eq!(instance.active_ids(), Vec::<u16>::new());
+  }
+
+  #[test]
+  fn cached_console_size() {
+    let cached = CachedConsoleSize::new();
+    assert_eq!(cached.get(), None);
+    cached.set(Some(ConsoleSize { cols: 80, rows: 20 }));
+    assert_eq!(cached.get(), Some(ConsoleSize { cols: 80, rows: 20 }));
+    // resizing
+    cached.set(Some(ConsoleSize { cols: 120, rows: 5 }));
+    assert_eq!(cached.get(), Some(ConsoleSize { cols: 120, rows: 5 }));
+    cached.set(None);
+    assert_eq!(cached.get(), None);
+  }
+
+  #[test]
+  fn enabled_override_ignores_log_level() {
+    let _lock = TEST_LOCK.lock();
+    let max_level = log::max_level();
+    log::set_max_level(log::LevelFilter::Warn);
+    assert!(!is_supported_with(None, true, false));
+    assert!(is_supported_with(Some(true), true, false));
+    assert!(!is_supported_with(Some(false), true, false));
+    // still requires a tty
+    assert!(!is_supported_with(Some(true), false, false));
+
+    DrawThread::set_enabled(Some(true));
+    assert_eq!(ENABLED_OVERRIDE.get(), Some(true));
+    DrawThread::set_enabled(None);
+    assert_eq!(ENABLED_OVERRIDE.get(), None);
+    log::set_max_level(max_level);
+  }
+
+  #[test]
+  fn suspended_by_parent_disables_drawing() {
+    assert!(!is_suspended_value(None));
+    assert!(!is_suspended_value(Some("")));
+    assert!(!is_suspended_value(Some("0")));
+    assert!(is_suspended_value(Some("1")));
+    assert!(is_suspended_value(Some("true")));
+    assert!(!is_supported_with(Some(true), true, true));
+    assert!(!is_supported_with(None, true, true));
+  }
+
+  #[test]
+  fn terminal_override_starts_thread() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .build()
+      .unwrap();
+    let _runtime_guard = runtime.enter();
+    let terminal = TestTerminal::with_manual(&instance, false);
+    assert!(!DrawThread::is_supported());
+    let supported = ForceSupported::new();
+    assert!(DrawThread::is_supported());
+
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(instance.state.lock().has_draw_thread);
+    let start = Instant::now();
+    let mut text = String::new();
+    while !text.contains("downloading") {
+      assert!(start.elapsed() < Duration::from_secs(10), "{:?}", text);
+      std::thread::sleep(Duration::from_millis(10));
+      text.push_str(&terminal.output.take_text());
+    }
+    drop(guard);
+    assert!(!instance.state.lock().has_draw_thread);
+
+    drop(supported);
+    assert!(!DrawThread::is_supported());
+  }
+
+  #[test]
+  fn counts_rows() {
+    let size = ConsoleSize { cols: 10, rows: 5 };
+    assert_eq!(row_count("", &size), 0);
+    assert_eq!(row_count("short", &size), 1);
+    assert_eq!(row_count("a\n\nb", &size), 3);
+    // wrapped by the terminal
+    assert_eq!(row_count("this line wraps", &size), 2);
+    assert_eq!(row_count("日本語日本語", &size), 2);
+    // limited by the console height
+    assert_eq!(row_count("1\n2\n3\n4\n5\n6", &size), 5);
+  }
+
+  #[test]
+  fn guard_elapsed() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let guard = instance.add_entry(Arc::new(TextRenderer("text")));
+    std::thread::sleep(Duration::from_millis(50));
+    let elapsed = guard.elapsed();
+    assert!(elapsed >= Duration::from_millis(50), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+  }
+
+  #[test]
+  fn suspend_keeps_entries() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let first = instance.add_entry(Arc::new(TextRenderer("first")));
+    let token = instance.suspend();
+    let second = instance.add_entry(Arc::new(TextRenderer("second")));
+    {
+      let internal_state = instance.state.lock();
+      assert_eq!(internal_state.suspend_count, 1);
+      assert!(!internal_state.has_draw_thread);
+    }
+    assert_eq!(
+      rendered_ids(&instance),
+      vec![first.id.unwrap(), second.id.unwrap()]
+    );
+    DrawThread::resume(token);
+    assert_eq!(instance.state.lock().suspend_count, 0);
+    assert_eq!(
+      rendered_ids(&instance),
+      vec![first.id.unwrap(), second.id.unwrap()]
+    );
+  }
+
+  #[test]
+  fn caches_rendered_text_of_live_entries_only() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let first = instance.add_entry(Arc::new(TextRenderer("first")));
+    let second = instance.add_entry(Arc::new(TextRenderer("second")));
+    let entries = instance.state.lock().entries_to_render();
+    let size = ConsoleSize { cols: 10, rows: 10 };
+    let rendered =
+      render_entries(&entries, &size, 0, &RenderContext::default());
+    // finish an entry in between rendering and caching
+    let second_id = second.id.unwrap();
+    drop(second);
+    let mut internal_state = instance.state.lock();
+    internal_state.cache_rendered(rendered);
+    assert_eq!(
+      internal_state
+        .last_rendered
+        .get(&first.id.unwrap())
+        .map(|t| t.as_str()),
+      Some("first")
+    );
+    assert!(!internal_state.last_rendered.contains_key(&second_id));
+    drop(internal_state);
+
+    let first_id = first.id.unwrap();
+    drop(first);
+    assert!(!instance.state.lock().last_rendered.contains_key(&first_id));
+  }
+
+  #[test]
+  fn render_delay_skips_young_entries() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let first = instance.add_entry(Arc::new(TextRenderer("first")));
+    instance.set_render_delay(Duration::from_millis(50));
+    assert_eq!(rendered_ids(&instance), Vec::<u16>::new());
+    std::thread::sleep(Duration::from_millis(50));
+    let second = instance.add_entry(Arc::new(TextRenderer("second")));
+    assert_eq!(rendered_ids(&instance), vec![first.id.unwrap()]);
+    instance.set_render_delay(Duration::ZERO);
+    assert_eq!(
+      rendered_ids(&instance),
+      vec![first.id.unwrap(), second.id.unwrap()]
+    );
+  }
+
+  #[derive(Clone, Default)]
+  struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+  impl CaptureWriter {
+    fn take_text(&self) -> String {
+      let bytes = std::mem::take(&mut *self.0.lock());
+      String::from_utf8(bytes).unwrap()
+    }
+  }
+
+  impl Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+      self.0.lock().extend_from_slice(buf);
+      Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+      Ok(())
+    }
+  }
+
+  /// Draws the instance to an in-memory terminal until dropped, which
+  /// restores the default instance for the tests that need it.
+  struct TestTerminal {
+    instance: DrawThreadInstance,
+    output: CaptureWriter,
+    json_output: CaptureWriter,
+  }
+
+  impl TestTerminal {
+    fn new(instance: &DrawThreadInstance) -> Self {
+      Self::with_manual(instance, true)
+    }
+
+    fn with_manual(instance: &DrawThreadInstance, manual: bool) -> Self {
+      let output = CaptureWriter::default();
+      let json_output = CaptureWriter::default();
+      {
+        let mut internal_state = instance.state.lock();
+        internal_state.size_provider =
+          || Some(ConsoleSize { cols: 80, rows: 20 });
+        internal_state.output = TerminalOutput(Box::new(output.clone()));
+        internal_state.json_output =
+          TerminalOutput(Box::new(json_output.clone()));
+        // keep the output the same regardless of the terminal running
+        // the tests
+        internal_state.synchronized_output = SynchronizedOutput::Off;
+      }
+      instance.set_manual(manual);
+      Self {
+        instance: instance.clone(),
+        output,
+        json_output,
+      }
+    }
+  }
+
+  impl Drop for TestTerminal {
+    fn drop(&mut self) {
+      self.instance.set_manual(false);
+      let mut internal_state = self.instance.state.lock();
+      internal_state.size_provider = console_size;
+      internal_state.output = TerminalOutput(Box::new(std::io::stderr()));
+      internal_state.json_output = TerminalOutput(Box::new(std::io::stdout()));
+      internal_state.synchronized_output = SynchronizedOutput::Auto;
+    }
+  }
+
+  /// Considers drawing supported until dropped, which is global.
+  struct ForceSupported;
+
+  impl ForceSupported {
+    fn new() -> Self {
+      DrawThread::set_terminal_override(Some(true));
+      DrawThread::set_enabled(Some(true));
+      Self
+    }
+  }
+
+  impl Drop for ForceSupported {
+    fn drop(&mut self) {
+      DrawThread::set_enabled(None);
+      DrawThread::set_terminal_override(None);
+    }
+  }
+
+  #[test]
+  fn manual_tick() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    assert!(!instance.tick());
+
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(!instance.state.lock().has_draw_thread);
+    assert!(terminal.output.take_text().is_empty());
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "downloading");
+    assert!(instance.tick());
+
+    drop(guard);
+    assert!(!instance.state.lock().has_draw_thread);
+    // the region was cleared
+    assert!(!terminal.output.take_text().is_empty());
+    assert!(!instance.tick());
+    assert!(terminal.output.take_text().is_empty());
+  }
+
+  #[test]
+  fn marks_stale_text() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_stale_timeout(Some(Duration::ZERO));
+    let renderer = Arc::new(TailRenderer::new(10, 10));
+    renderer.push_line("first");
+    let guard = instance.add_entry(renderer.clone());
+
+    assert!(instance.tick());
+    assert_not_contains!(terminal.output.take_text(), "no updates");
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "(no updates for 0s)");
+    // the marker shows how long ago the text last changed
+    if let Some((_, changed_at)) = &mut instance.state.lock().last_frame_change
+    {
+      *changed_at -= Duration::from_secs(3);
+    }
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "(no updates for 3s)");
+    renderer.push_line("second");
+    assert!(instance.tick());
+    assert_not_contains!(terminal.output.take_text(), "no updates");
+
+    drop(guard);
+
+    let mut internal_state = instance.state.lock();
+    let now = Instant::now();
+    for (timeout, elapsed, expected) in [
+      (
+        Duration::from_millis(500),
+        Duration::from_millis(500),
+        Some("(no updates for 0s)"),
+      ),
+      (
+        Duration::from_secs(1),
+        Duration::from_secs(5),
+        Some("(no updates for 5s)"),
+      ),
+      (
+        Duration::from_secs(90),
+        Duration::from_secs(150),
+        Some("(no updates for 2m30s)"),
+      ),
+      (Duration::from_secs(90), Duration::from_secs(60), None),
+    ] {
+      internal_state.stale_timeout = Some(timeout);
+      internal_state.last_frame_change = None;
+      internal_state.mark_if_stale("text".to_string(), now);
+      let text =
+        internal_state.mark_if_stale("text".to_string(), now + elapsed);
+      match expected {
+        Some(expected) => {
+          assert_contains!(text, expected);
+        }
+        None => assert_eq!(text, "text"),
+      }
+    }
+  }
+
+  #[test]
+  fn viewport_draw_text() {
+    let viewport = Viewport {
+      top: 2,
+      left: 4,
+      width: 5,
+      height: 3,
+    };
+    assert_eq!(viewport.size(), ConsoleSize { cols: 5, rows: 3 });
+    assert_eq!(
+      viewport.draw_text("first line\nab\nthird\nfourth"),
+      concat!(
+        "\x1b7",
+        "\x1b[3;5Hfirst",
+        "\x1b[4;5Hab   ",
+        "\x1b[5;5Hthird",
+        "\x1b8",
+      )
+    );
+    assert_eq!(
+      viewport.clear_text(),
+      "\x1b7\x1b[3;5H     \x1b[4;5H     \x1b[5;5H     \x1b8"
+    );
+  }
+
+  #[test]
+  fn draws_in_viewport() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_viewport(Some(Viewport {
+      top: 0,
+      left: 0,
+      width: 4,
+      height: 1,
+    }));
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(instance.tick());
+    assert_eq!(terminal.output.take_text(), "\x1b7\x1b[1;1Hdown\x1b8");
+    drop(guard);
+    assert_eq!(terminal.output.take_text(), "\x1b7\x1b[1;1H    \x1b8");
+  }
+
+  #[derive(Debug)]
+  struct CountRenderer(u64);
+
+  impl DrawThreadRenderer for CountRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      colors::green(format!("{} files", self.0)).to_string()
+    }
+
+    fn as_structured(&self) -> Option<&dyn StructuredRenderer> {
+      Some(self)
+    }
+  }
+
+  impl StructuredRenderer for CountRenderer {
+    fn render_structured(&self, _data: &ConsoleSize) -> serde_json::Value {
+      serde_json::json!({ "files": self.0 })
+    }
+  }
+
+  #[test]
+  fn emits_json_lines() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_fallback(Fallback::ForceJsonLines);
+    let first = instance.add_entry(Arc::new(TextRenderer("text")));
+    let second = instance.add_entry(Arc::new(CountRenderer(5)));
+    assert!(instance.tick());
+    assert_eq!(
+      terminal.json_output.take_text(),
+      format!(
+        "{{\"id\":{},\"text\":\"text\"}}\n{{\"data\":{{\"files\":5}},\"id\":{},\"text\":\"5 files\"}}\n",
+        first.id.unwrap(), second.id.unwrap(),
+      ),
+    );
+    assert!(terminal.output.take_text().is_empty());
+    drop(first);
+    drop(second);
+  }
+
+  #[derive(Debug)]
+  struct SizeRenderer;
+
+  impl DrawThreadRenderer for SizeRenderer {
+    fn render(&self, data: &ConsoleSize) -> String {
+      format!("{}x{}", data.cols, data.rows)
+    }
+  }
+
+  #[test]
+  fn size_override() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let guard = instance.add_entry(Arc::new(SizeRenderer));
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "80x20");
+
+    instance.set_size_override(Some(ConsoleSize { cols: 40, rows: 2 }));
+    // changing the size is detected as a resize
+    assert!(!instance.tick());
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "40x2");
+
+    instance.set_size_override(None);
+    assert!(!instance.tick());
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "80x20");
+    drop(guard);
+  }
+
+  #[derive(Debug)]
+  struct FrameRenderer;
+
+  impl DrawThreadRenderer for FrameRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      unreachable!()
+    }
+
+    fn render_frame(&self, _data: &ConsoleSize, frame: u64) -> String {
+      format!("frame {}", frame)
+    }
+  }
+
+  #[test]
+  fn passes_increasing_frame_numbers() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let frame = instance.state.lock().next_frame;
+    let guard = instance.add_entry(Arc::new(FrameRenderer));
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), &format!("frame {}", frame));
+    assert!(instance.tick());
+    assert_contains!(
+      terminal.output.take_text(),
+      &format!("frame {}", frame + 1)
+    );
+    drop(guard);
+
+    // persists when drawing restarts
+    let guard = instance.add_entry(Arc::new(FrameRenderer));
+    assert!(instance.tick());
+    assert_contains!(
+      terminal.output.take_text(),
+      &format!("frame {}", frame + 2)
+    );
+    drop(guard);
+  }
+
+  #[derive(Debug)]
+  struct WideRenderer;
+
+  impl DrawThreadRenderer for WideRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      "wide".to_string()
+    }
+
+    fn min_cols(&self) -> u16 {
+      50
+    }
+  }
+
+  #[test]
+  fn hides_entries_narrower_than_min_cols() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let narrow = instance.add_entry(Arc::new(TextRenderer("narrow")));
+    let wide = instance.add_entry(Arc::new(WideRenderer));
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "narrow");
+    assert_contains!(text, "wide");
+
+    instance.set_size_override(Some(ConsoleSize { cols: 40, rows: 20 }));
+    assert!(!instance.tick());
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "narrow");
+    assert_not_contains!(text, "wide");
+
+    // reappears once widened
+    instance.set_size_override(None);
+    assert!(!instance.tick());
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "wide");
+    drop(narrow);
+    drop(wide);
+  }
+
+  #[test]
+  fn draw_eprintln_prints_above_region() {
+    let _lock = TEST_LOCK.lock();
+    // the macro prints with the default instance
+    let terminal = TestTerminal::new(&DEFAULT_INSTANCE);
+    draw_eprintln!("hello {}", 5);
+    draw_eprintln!();
+    assert_eq!(terminal.output.take_text(), "hello 5\n\n");
+
+    let guard = DrawThread::add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(DrawThread::tick());
+    terminal.output.take_text();
+    draw_eprintln!("log line");
+    let text = terminal.output.take_text();
+    let line_index = text.find("log line\n").unwrap();
+    let region_index = text.rfind("downloading").unwrap();
+    assert!(line_index < region_index, "{:?}", text);
+    drop(guard);
+  }
+
+  #[test]
+  fn append_only_mode() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_render_mode(RenderMode::AppendOnly);
+    let renderer = Arc::new(TailRenderer::new(10, 10));
+    renderer.push_line("first");
+    let guard = instance.add_entry(renderer.clone());
+    assert!(instance.tick());
+    assert_eq!(terminal.output.take_text(), "first\n");
+    // unchanged frames are skipped
+    assert!(instance.tick());
+    assert_eq!(terminal.output.take_text(), "");
+    renderer.push_line("second");
+    assert!(instance.tick());
+    assert_eq!(terminal.output.take_text(), "first\nsecond\n");
+    drop(guard);
+    // nothing to clear
+    assert_eq!(terminal.output.take_text(), "");
+  }
+
+  #[test]
+  fn flashes_expire_independently() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.flash("Saved!", Duration::from_millis(20));
+    instance.flash("Formatted", Duration::from_millis(200));
+    assert_eq!(instance.active_ids().len(), 2);
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "Saved!");
+    assert_contains!(text, "Formatted");
+
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(instance.tick());
+    assert_eq!(instance.active_ids().len(), 1);
+    let text = terminal.output.take_text();
+    assert_not_contains!(text, "Saved!");
+    assert_contains!(text, "Formatted");
+
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(!instance.tick());
+    assert_eq!(instance.active_ids(), Vec::<u16>::new());
+    assert!(!instance.state.lock().has_draw_thread);
+  }
+
+  #[test]
+  fn draws_managed_log_lines_above_entries() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_managed_log_lines(2);
+    // printed since nothing is drawn yet
+    instance.log("before");
+    assert_eq!(terminal.output.take_text(), "before\n");
+
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(instance.tick());
+    terminal.output.take_text();
+    instance.log("first");
+    instance.log("second");
+    instance.log("third");
+    assert_eq!(terminal.output.take_text(), "");
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_not_contains!(text, "first");
+    let second_index = text.find("second").unwrap();
+    let third_index = text.find("third").unwrap();
+    let entry_index = text.find("downloading").unwrap();
+    assert!(second_index < third_index && third_index < entry_index);
+
+    // the entries take precedence when they don't all fit
+    let log_lines = ["second".to_string(), "third".to_string()];
+    let size = ConsoleSize { cols: 80, rows: 2 };
+    assert_eq!(
+      prepend_log_lines(&log_lines, "downloading".to_string(), &size),
+      "third\ndownloading"
+    );
+    assert_eq!(
+      prepend_log_lines(&log_lines, "a\nb".to_string(), &size),
+      "a\nb"
+    );
+
+    drop(guard);
+    assert!(instance.state.lock().log_lines.is_empty());
+  }
+
+  #[test]
+  fn removes_entry_when_token_cancelled() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    runtime.block_on(async {
+      let token = CancellationToken::new();
+      let guard = instance.add_entry_with_token(
+        Arc::new(TextRenderer("downloading")),
+        token.clone(),
+      );
+      assert_eq!(instance.active_ids(), vec![guard.id.unwrap()]);
+      token.cancel();
+      tokio::task::yield_now().await;
+      assert_eq!(instance.active_ids(), Vec::<u16>::new());
+      drop(guard);
+
+      // dropping the guard first stops the watching task
+      let token = CancellationToken::new();
+      let guard = instance.add_entry_with_token(
+        Arc::new(TextRenderer("downloading")),
+        token.clone(),
+      );
+      drop(guard);
+      assert_eq!(instance.active_ids(), Vec::<u16>::new());
+      tokio::task::yield_now().await;
+      assert_eq!(runtime_alive_tasks(), 0);
+    });
+  }
+
+  fn runtime_alive_tasks() -> usize {
+    tokio::runtime::Handle::current()
+      .metrics()
+      .num_alive_tasks()
+  }
+
+  #[test]
+  fn prints_summary_once_entries_finish() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.finish_with_summary("nothing to do");
+    assert_eq!(terminal.output.take_text(), "nothing to do\n");
+
+    let first = instance.add_entry(Arc::new(TextRenderer("first")));
+    let second = instance.add_entry(Arc::new(TextRenderer("second")));
+    assert!(instance.tick());
+    instance.finish_with_summary("Done in 4.2s");
+    drop(first);
+    assert!(instance.tick());
+    assert_not_contains!(terminal.output.take_text(), "Done");
+    drop(second);
+    // printed after the region is cleared
+    let text = terminal.output.take_text();
+    assert!(text.len() > "Done in 4.2s\n".len(), "{:?}", text);
+    assert!(text.ends_with("Done in 4.2s\n"), "{:?}", text);
+    assert!(instance.state.lock().pending_summary.is_none());
+  }
+
+  #[derive(Debug)]
+  struct Locale(&'static str);
+
+  #[derive(Debug)]
+  struct LocaleRenderer;
+
+  impl DrawThreadRenderer for LocaleRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      "no context".to_string()
+    }
+
+    fn render_with_context(
+      &self,
+      _data: &ConsoleSize,
+      _frame: u64,
+      context: &RenderContext,
+    ) -> String {
+      match context.get::<Locale>() {
+        Some(locale) => format!("locale {}", locale.0),
+        None => "no locale".to_string(),
+      }
+    }
+  }
+
+  #[test]
+  fn provides_context_to_renderers() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let guard = instance.add_entry(Arc::new(LocaleRenderer));
+    let other_guard = instance.add_entry(Arc::new(TextRenderer("plain")));
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "no locale");
+    assert_contains!(text, "plain");
+
+    instance.set_context(Arc::new(Locale("en-US")));
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "locale en-US");
+    instance.remove_context::<Locale>();
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "no locale");
+    drop(guard);
+    drop(other_guard);
+  }
+
+  #[derive(Debug, Default)]
+  struct DrawThreadCheckRenderer(Mutex<Vec<bool>>);
+
+  impl DrawThreadRenderer for DrawThreadCheckRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      self.0.lock().push(DrawThread::is_draw_thread());
+      String::new()
+    }
+  }
+
+  #[test]
+  fn is_draw_thread_while_rendering() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    let renderer = Arc::new(DrawThreadCheckRenderer::default());
+    let guard = instance.add_entry(renderer.clone());
+    assert!(!DrawThread::is_draw_thread());
+    assert!(instance.tick());
+    assert!(!DrawThread::is_draw_thread());
+    assert_eq!(*renderer.0.lock(), vec![true]);
+    drop(guard);
+  }
+
+  #[test]
+  fn caps_cols_at_max_cols() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let state = Arc::new(progress::ProgressState::new(10, ""));
+    state.set_position(5);
+    let renderer = Arc::new(template::TemplateRenderer::new("[{bar}]", state));
+    let guard = instance.add_entry(renderer);
+    instance.set_max_cols(Some(22));
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    let text = console_static_text::ansi::strip_ansi_codes(&text);
+    assert_contains!(text, "[##########----------]");
+    drop(guard);
+  }
+
+  #[test]
+  fn prime_draws_first_frame() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .max_blocking_threads(1)
+      .build()
+      .unwrap();
+    let _enter = runtime.enter();
+    let terminal = TestTerminal::with_manual(&instance, false);
+    let _supported = ForceSupported::new();
+    // nothing to draw
+    assert!(!instance.prime());
+
+    // occupy the only blocking thread so the draw thread can't start
+    let (sender, receiver) = std::sync::mpsc::channel::<()>();
+    runtime.spawn_blocking(move || receiver.recv());
+    let guard = instance.add_entry(Arc::new(TextRenderer("setting up")));
+    assert!(instance.prime());
+    assert_contains!(terminal.output.take_text(), "setting up");
+    assert!(!instance.prime());
+    assert_eq!(instance.stats().frames_rendered, 1);
+
+    // the draw thread doesn't draw the same frame again
+    drop(sender);
+    let start = Instant::now();
+    while instance.stats().frames_rendered < 2 {
+      assert!(start.elapsed() < Duration::from_secs(5));
+      std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_not_contains!(terminal.output.take_text(), "setting up");
+
+    drop(guard);
+  }
+
+  #[test]
+  fn draws_newest_entries_first() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let guards = ["first", "second", "third"]
+      .map(|text| instance.add_entry(Arc::new(TextRenderer(text))));
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "first\r\nsecond\r\nthird");
+
+    instance.set_order_direction(OrderDirection::Newest);
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "third\r\nsecond\r\nfirst");
+    assert_eq!(
+      take_top_rows("third\nsecond\nfirst", &ConsoleSize { cols: 80, rows: 2 }),
+      "third\nsecond"
+    );
+    drop(guards);
+  }
+
+  #[test]
+  fn draws_debug_overlay() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(instance.tick());
+    assert_not_contains!(terminal.output.take_text(), "[draw debug]");
+
+    instance.set_debug_overlay(true);
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    let text = console_static_text::ansi::strip_ansi_codes(&text);
+    assert_contains!(
+      text,
+      "downloading\r\n[draw debug] drawer=manual entries=1"
+    );
+    assert_contains!(text, "size=80x20");
+    drop(guard);
+
+    let text = debug_overlay_text(
+      Some(3),
+      2,
+      42,
+      Duration::from_micros(1500),
+      &ConsoleSize {
+        cols: 200,
+        rows: 20,
+      },
+    );
+    assert_eq!(
+      console_static_text::ansi::strip_ansi_codes(&text),
+      "[draw debug] drawer=3 entries=2 frame=42 render=1.5ms size=200x20"
+    );
+  }
+
+  #[test]
+  fn notifies_on_next_clear() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    runtime.block_on(async {
+      let guard = instance.add_entry(Arc::new(TextRenderer("a")));
+      assert!(instance.tick());
+      let first = instance.on_next_clear();
+      let second = instance.on_next_clear();
+      tokio::pin!(first);
+      // redrawing doesn't count
+      instance.println("line");
+      let timeout = Duration::from_millis(20);
+      assert!(tokio::time::timeout(timeout, &mut first).await.is_err());
+      // and neither does clearing another instance
+      let other = DrawThread::new();
+      let _other_terminal = TestTerminal::new(&other);
+      let other_guard = other.add_entry(Arc::new(TextRenderer("b")));
+      assert!(other.tick());
+      drop(other_guard);
+      assert!(tokio::time::timeout(timeout, &mut first).await.is_err());
+      drop(guard);
+      first.await;
+      second.await;
+    });
+  }
+
+  #[test]
+  fn hides_tagged_entries() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    let first =
+      instance.add_entry_tagged(Arc::new(TextRenderer("a")), "network");
+    let second = instance.add_entry(Arc::new(TextRenderer("b")));
+    let third =
+      instance.add_entry_tagged(Arc::new(TextRenderer("c")), "network");
+    let fourth = instance.add_entry_tagged(Arc::new(TextRenderer("d")), "disk");
+    instance.hide_tag("network");
+    assert_eq!(
+      rendered_ids(&instance),
+      vec![second.id.unwrap(), fourth.id.unwrap()]
+    );
+    instance.show_tag("network");
+    assert_eq!(
+      rendered_ids(&instance),
+      vec![
+        first.id.unwrap(),
+        second.id.unwrap(),
+        third.id.unwrap(),
+        fourth.id.unwrap()
+      ]
+    );
+    drop((first, second, third, fourth));
+  }
+
+  #[derive(Debug, Clone, Default)]
+  struct CaptureSink(Arc<Mutex<Vec<String>>>);
+
+  impl FrameSink for CaptureSink {
+    fn write_frame(&mut self, text: &str, _size: &ConsoleSize) {
+      self.0.lock().push(text.to_string());
+    }
+  }
+
+  #[derive(Debug, Default)]
+  struct ToggleRenderer(Mutex<&'static str>);
+
+  impl DrawThreadRenderer for ToggleRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      self.0.lock().to_string()
+    }
+  }
+
+  #[test]
+  fn empty_frame_behavior() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let sink = CaptureSink::default();
+    let _sink_guard = instance.add_sink(Box::new(sink.clone()));
+    let renderer = Arc::new(ToggleRenderer(Mutex::new("working")));
+    let guard = instance.add_entry(renderer.clone());
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "working");
+
+    // clears by default
+    *renderer.0.lock() = "";
+    assert!(instance.tick());
+    assert!(!terminal.output.take_text().is_empty());
+    assert_eq!(*sink.0.lock(), vec!["working", ""]);
+    assert!(instance.state.lock().last_drawn.is_none());
+
+    instance.set_empty_frame(EmptyFrame::Keep);
+    *renderer.0.lock() = "working";
+    assert!(instance.tick());
+    terminal.output.take_text();
+    *renderer.0.lock() = "";
+    assert!(instance.tick());
+    assert_eq!(terminal.output.take_text(), "");
+    assert_eq!(*sink.0.lock(), vec!["working", "", "working"]);
+    assert!(instance.state.lock().last_drawn.is_some());
+
+    drop(guard);
+  }
+
+  #[test]
+  fn nested_hide_across_threads() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let guard = instance.add_entry(Arc::new(TextRenderer("entry")));
+    assert!(instance.tick());
+    terminal.output.take_text();
+
+    instance.hide();
+    instance.hide();
+    assert!(!instance.tick());
+    instance.show();
+    assert!(!instance.tick());
+    instance.show();
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "entry");
+
+    // shown on another thread than it was hidden on
+    instance.hide();
+    std::thread::spawn({
+      let instance = instance.clone();
+      move || instance.show()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(instance.state.lock().hide_count, 0);
+    assert!(instance.tick());
+    // which still hides it again on this thread
+    instance.hide();
+    assert_eq!(instance.state.lock().hide_count, 1);
+    assert!(!instance.tick());
+    instance.show();
+    drop(guard);
+  }
+
+  #[test]
+  fn hide_scope_only_counts_outermost() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let guard = instance.add_entry(Arc::new(TextRenderer("entry")));
+    assert!(instance.tick());
+    terminal.output.take_text();
+
+    let outer = instance.hide_scope();
+    let middle = instance.hide_scope();
+    let inner = instance.hide_scope();
+    assert_eq!(instance.state.lock().hide_count, 1);
+    assert!(!instance.tick());
+    drop(inner);
+    drop(middle);
+    assert_eq!(instance.state.lock().hide_count, 1);
+    assert!(!instance.tick());
+
+    // each thread is counted separately
+    std::thread::spawn({
+      let instance = instance.clone();
+      move || {
+        let _scope = instance.hide_scope();
+        assert_eq!(instance.state.lock().hide_count, 2);
+      }
+    })
+    .join()
+    .unwrap();
+    assert_eq!(instance.state.lock().hide_count, 1);
+    // and the instances too
+    let other = DrawThread::new();
+    let other_scope = other.hide_scope();
+    assert_eq!(other.state.lock().hide_count, 1);
+    drop(other_scope);
+    assert_eq!(other.state.lock().hide_count, 0);
+
+    drop(outer);
+    assert_eq!(instance.state.lock().hide_count, 0);
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "entry");
+    drop(guard);
+  }
+
+  /// Compares nested `hide()` and `show()` calls, which each take the
+  /// lock, against nested hide scopes. Run with
+  /// `cargo test --release bench_nested_hide -- --ignored`.
+  #[test]
+  #[ignore]
+  fn bench_nested_hide() {
+    const ITERATIONS: usize = 200_000;
+    const DEPTH: usize = 10;
+
+    fn nested_scopes(instance: &DrawThreadInstance, depth: usize) {
+      let _scope = instance.hide_scope();
+      if depth > 1 {
+        nested_scopes(instance, depth - 1);
+      }
+    }
+
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+      for _ in 0..DEPTH {
+        instance.hide();
+      }
+      for _ in 0..DEPTH {
+        instance.show();
+      }
+    }
+    let locked_elapsed = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+      nested_scopes(&instance, DEPTH);
+    }
+    let scoped_elapsed = start.elapsed();
+    assert_eq!(instance.state.lock().hide_count, 0);
+    assert!(
+      scoped_elapsed < locked_elapsed,
+      "scoped: {:?}, locked: {:?}",
+      scoped_elapsed,
+      locked_elapsed
+    );
+  }
+
+  #[test]
+  fn draws_latest_entry_when_too_short() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_size_override(Some(ConsoleSize { cols: 80, rows: 2 }));
+    instance.set_min_rows_required(3);
+    let first = instance.add_entry(Arc::new(TextRenderer("first")));
+    let second = instance.add_entry(Arc::new(TextRenderer("second")));
+    let third = instance.add_entry(Arc::new(TextRenderer("third\nline")));
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "third");
+    assert_not_contains!(text, "second");
+    assert_not_contains!(text, "line");
+
+    instance.set_order_direction(OrderDirection::Newest);
+    drop(third);
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "second");
+    assert_not_contains!(text, "first");
+    instance.set_order_direction(OrderDirection::Oldest);
+
+    instance.set_min_rows_required(0);
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "first");
+    assert_contains!(text, "second");
+    drop(first);
+    drop(second);
+  }
+
+  #[test]
+  fn draws_frame_override_instead_of_entries() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "downloading");
+
+    instance
+      .override_frame(Some(format!("Waiting for lock{}", "x".repeat(100))));
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "Waiting for lock");
+    assert_contains!(text, &"x".repeat(80 - 16));
+    assert_not_contains!(text, &"x".repeat(80 - 15));
+    assert_not_contains!(text, "downloading");
+    assert!(instance
+      .state
+      .lock()
+      .entries
+      .get(guard.id.unwrap())
+      .is_some());
+
+    instance.override_frame(None);
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "downloading");
+    assert_not_contains!(text, "Waiting for lock");
+    drop(guard);
+  }
+
+  #[test]
+  fn records_completions() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let first =
+      instance.add_entry_tagged(Arc::new(TextRenderer("checking")), "check");
+    assert!(instance.tick());
+    drop(first);
+    // not recorded unless enabled
+    assert_eq!(instance.take_completion_log(), Vec::new());
+
+    instance.set_record_completions(true);
+    let state = Arc::new(progress::ProgressState::new(10, ""));
+    let download = instance.add_entry_tagged(
+      Arc::new(template::TemplateRenderer::new(
+        "{pos}/{len}",
+        state.clone(),
+      )),
+      "download",
+    );
+    let check = instance.add_entry(Arc::new(TextRenderer("checking")));
+    state.set_position(4);
+    assert!(instance.tick());
+    std::thread::sleep(Duration::from_millis(10));
+    state.fail();
+    drop(download);
+    drop(check);
+    terminal.output.take_text();
+
+    let completions = instance.take_completion_log();
+    assert_eq!(completions.len(), 2);
+    assert_eq!(completions[0].tag.as_deref(), Some("download"));
+    assert_eq!(completions[0].text, "4/10");
+    assert!(completions[0].failed);
+    assert!(completions[0].elapsed >= Duration::from_millis(10));
+    assert_eq!(completions[1].tag, None);
+    assert_eq!(completions[1].text, "checking");
+    assert!(!completions[1].failed);
+    assert_eq!(instance.take_completion_log(), Vec::new());
+  }
+
+  #[test]
+  fn lingers_before_removal() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let renderer = Arc::new(spinner::SpinnerRenderer::new(
+      "Checking",
+      theme::Theme::plain(),
+    ));
+    let guard = instance.add_entry(renderer.clone());
+    assert!(instance.tick());
+    terminal.output.take_text();
+
+    renderer.succeed();
+    guard.linger(Duration::from_millis(30));
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "✓ Checking");
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(!instance.tick());
+    assert_eq!(instance.active_ids(), Vec::<u16>::new());
+    assert!(!instance.state.lock().has_draw_thread);
+  }
+
+  #[test]
+  fn backs_off_while_printing_many_lines() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(instance.tick());
+    terminal.output.take_text();
+    let print_lines = || {
+      for i in 0..30 {
+        instance.println(&format!("line {}", i));
+      }
+      terminal.output.take_text().matches("downloading").count()
+    };
+    // drawn again after every line
+    assert_eq!(print_lines(), 30);
+
+    instance.set_output_backpressure(true);
+    instance.state.lock().print_rate = Default::default();
+    assert!(instance.tick());
+    terminal.output.take_text();
+    // only drawn again until the rate is high
+    assert_eq!(print_lines(), 20);
+    assert_eq!(instance.state.lock().last_drawn, None);
+    // the next frame draws it again
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "downloading");
+    drop(guard);
+  }
+
+  #[test]
+  fn indents_entry_lines() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let parent = instance.add_entry(Arc::new(TextRenderer("parent")));
+    let renderer = Arc::new(template::TemplateRenderer::new(
+      "child\n[{bar}]",
+      Arc::new(progress::ProgressState::new(10, "")),
+    ));
+    let child = instance.add_entry(renderer.clone());
+    child.set_indent(2);
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    let text = console_static_text::ansi::strip_ansi_codes(&text);
+    // the indent counts toward the width
+    let expected = renderer.render(&ConsoleSize { cols: 78, rows: 20 });
+    let expected = console_static_text::ansi::strip_ansi_codes(&expected);
+    assert_contains!(
+      text,
+      &format!("parent\r\n  {}", expected.replace('\n', "\r\n  "))
+    );
+    assert_eq!(indent_lines("a\n\nb", 2), "  a\n\n  b");
+    drop(child);
+    drop(parent);
+  }
+
+  #[test]
+  fn pins_region_to_top() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_pin(Pin::Top);
+    let first = instance.add_entry(Arc::new(TextRenderer("first")));
+    let second = instance.add_entry(Arc::new(TextRenderer("second")));
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "\x1b7\x1b[3;20r\x1b8");
+    assert_contains!(text, "\x1b[1;1Hfirst");
+    assert_contains!(text, "\x1b[2;1Hsecond");
+
+    // output scrolls below the region without redrawing it
+    instance.println("log");
+    assert_eq!(terminal.output.take_text(), "log\n");
+    assert!(instance.tick());
+    assert_not_contains!(terminal.output.take_text(), "\x1b[3;20r");
+
+    drop(second);
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "\x1b7\x1b[2;20r\x1b8");
+    assert_contains!(text, "\x1b[1;1Hfirst");
+
+    // reset once cleared
+    drop(first);
+    let text = terminal.output.take_text();
+    assert_contains!(text, "\x1b7\x1b[r\x1b8");
+    assert_eq!(instance.state.lock().scroll_region, None);
+  }
+
+  #[test]
+  fn clears_wide_characters_exactly() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_size_override(Some(ConsoleSize { cols: 10, rows: 20 }));
+    // the hearts occupy two rows, but the static text measures them
+    // as a single row
+    let guard =
+      instance.add_entry(Arc::new(TextRenderer("❤️❤️❤️❤️❤️❤️\nnext")));
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "❤️❤️❤️❤️❤️\r\n❤️\r\nnext");
+    assert_eq!(
+      instance
+        .state
+        .lock()
+        .last_drawn
+        .as_ref()
+        .map(|(text, size)| { row_count(text, size) }),
+      Some(3)
+    );
+    drop(guard);
+    // moves up to the first of the three rows
+    assert_contains!(terminal.output.take_text(), "\x1b[2A");
+
+    // the lines are broken at the column like the terminal wraps them
+    // rather than word wrapped
+    instance.set_wrap_mode(WrapMode::None);
+    let guard =
+      instance.add_entry(Arc::new(TextRenderer("the quick brown fox")));
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    // the static text carries the space at the break over to the next row
+    assert_contains!(text, "the quick\r\n brown fox");
+    assert_not_contains!(text, "the quick\r\nbrown fox");
+    drop(guard);
+  }
+
+  #[test]
+  fn renders_plain_text() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let state = Arc::new(progress::ProgressState::new(10, ""));
+    state.set_position(5);
+    let bar = instance.add_entry(Arc::new(
+      template::TemplateRenderer::new("[{bar}]", state)
+        .with_theme(theme::Theme::default()),
+    ));
+    let message = instance.add_entry(Arc::new(TextRenderer(
+      "\x1b[32mdownloading https://deno.land/x/mod.ts\x1b[0m",
+    )));
+    message.set_indent(2);
+    let text = instance.render_plain(22);
+    assert_eq!(
+      text,
+      concat!(
+        "[##########----------]\n",
+        "  downloading\n",
+        "  https://deno.land/x/\n",
+        "  mod.ts",
+      )
+    );
+    // nothing was drawn
+    assert_eq!(terminal.output.take_text(), "");
+    drop(message);
+    drop(bar);
+  }
+
+  #[test]
+  fn reports_whether_last_frame_was_emitted() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(instance.tick());
+    assert!(instance.last_frame_emitted());
+    assert_contains!(terminal.output.take_text(), "downloading");
+    // the frame is unchanged
+    assert!(instance.tick());
+    assert!(!instance.last_frame_emitted());
+    assert_eq!(terminal.output.take_text(), "");
+
+    instance.hide();
+    terminal.output.take_text();
+    instance.tick();
+    assert!(!instance.last_frame_emitted());
+    instance.show();
+    drop(guard);
+  }
+
+  #[test]
+  fn takes_stats() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    instance.take_stats();
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    instance.tick();
+    instance.tick();
+    assert_eq!(instance.take_stats().frames_rendered, 2);
+    assert_eq!(instance.take_stats(), DrawStats::default());
+    instance.tick();
+    assert_eq!(instance.stats().frames_rendered, 1);
+    assert_eq!(instance.take_stats().frames_rendered, 1);
+    drop(guard);
+  }
+
+  #[test]
+  fn isolates_instances() {
+    let _lock = TEST_LOCK.lock();
+    let first = DrawThread::new();
+    let first_terminal = TestTerminal::new(&first);
+    let second = DrawThread::new();
+    let second_terminal = TestTerminal::new(&second);
+
+    let first_guard = first.add_entry(Arc::new(TextRenderer("first")));
+    let second_guard = second.add_entry(Arc::new(TextRenderer("second")));
+    // the ids are also independent
+    assert_eq!((first_guard.id, second_guard.id), (Some(0), Some(0)));
+    assert!(second.tick());
+    let text = second_terminal.output.take_text();
+    assert_contains!(text, "second");
+    assert_not_contains!(text, "first");
+    assert!(first.tick());
+    let text = first_terminal.output.take_text();
+    assert_contains!(text, "first");
+    assert_not_contains!(text, "second");
+
+    drop(second_guard);
+    assert_eq!(second.active_ids(), Vec::<u16>::new());
+    assert_eq!(first.active_ids(), vec![first_guard.id.unwrap()]);
+    assert!(!second.tick());
+    assert!(!second_terminal.output.take_text().is_empty());
+    drop(first_guard);
+  }
+
+  #[test]
+  fn calls_clear_hook() {
+    use std::sync::atomic::AtomicUsize;
+
+    let _lock = TEST_LOCK.lock();
+
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let count = Arc::new(AtomicUsize::new(0));
+    instance.set_clear_hook(Some(Box::new({
+      let count = count.clone();
+      move || {
+        count.fetch_add(1, Ordering::Relaxed);
+      }
+    })));
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(instance.tick());
+    assert_eq!(count.load(Ordering::Relaxed), 0);
+    drop(guard);
+    assert_contains!(terminal.output.take_text(), "\x1b[2K");
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+
+    // not called once removed
+    instance.set_clear_hook(None);
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(instance.tick());
+    drop(guard);
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+  }
+
+  #[test]
+  fn limits_history() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    instance.set_history_limit(2);
+    instance.set_record_completions(true);
+    for tag in ["first", "second", "third"] {
+      drop(instance.add_entry_tagged(Arc::new(TextRenderer("")), tag));
+    }
+    let tags = instance
+      .take_completion_log()
+      .into_iter()
+      .map(|completion| completion.tag.unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(tags, vec!["second", "third"]);
+
+    instance.set_managed_log_lines(5);
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    assert!(instance.tick());
+    instance.log("first");
+    instance.log("second");
+    instance.log("third");
+    assert_eq!(
+      instance.state.lock().log_lines,
+      VecDeque::from(["second".to_string(), "third".to_string()])
+    );
+    instance.set_history_limit(1);
+    assert_eq!(
+      instance.state.lock().log_lines,
+      VecDeque::from(["third".to_string()])
+    );
+    drop(guard);
+  }
+
+  #[test]
+  fn draws_entries_when_predicate_is_true() {
+    use std::sync::atomic::AtomicBool;
+
+    let _lock = TEST_LOCK.lock();
+
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let is_verbose = Arc::new(AtomicBool::new(false));
+    let _first = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    let _second = instance.add_entry_if(Arc::new(TextRenderer("details")), {
+      let is_verbose = is_verbose.clone();
+      Arc::new(move || is_verbose.load(Ordering::Relaxed))
+    });
+    assert!(instance.tick());
+    assert_not_contains!(terminal.output.take_text(), "details");
+    is_verbose.store(true, Ordering::Relaxed);
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "details");
+    is_verbose.store(false, Ordering::Relaxed);
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    // the entry's row is cleared
+    assert_not_contains!(text, "details");
+    assert!(!text.is_empty());
+    assert_eq!(instance.active_ids().len(), 2);
+  }
+
+  #[derive(Debug)]
+  struct VersionedRenderer(Mutex<(String, RenderVersion)>);
+
+  impl VersionedRenderer {
+    fn update(&self, text: &str, age: Duration) {
+      let mut state = self.0.lock();
+      state.0 = text.to_string();
+      state.1 = RenderVersion {
+        number: state.1.number + 1,
+        changed_at: Instant::now() - age,
+      };
+    }
+  }
+
+  impl DrawThreadRenderer for VersionedRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      self.0.lock().0.clone()
+    }
+
+    fn version(&self) -> Option<RenderVersion> {
+      Some(self.0.lock().1)
+    }
+  }
+
+  #[test]
+  fn measures_update_latency() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    instance.take_stats();
+    let renderer = Arc::new(VersionedRenderer(Mutex::new((
+      "resolving".to_string(),
+      RenderVersion {
+        number: 0,
+        changed_at: Instant::now() - Duration::from_secs(10),
+      },
+    ))));
+    let guard = instance.add_entry(renderer.clone());
+    // the first frame isn't measured
+    instance.tick();
+    assert_eq!(instance.stats().update_latency_samples, 0);
+
+    renderer.update("downloading", Duration::from_millis(800));
+    instance.tick();
+    let stats = instance.stats();
+    assert_eq!(stats.update_latency_samples, 1);
+    assert!(stats.average_update_latency >= Duration::from_millis(800));
+    assert!(stats.average_update_latency < Duration::from_secs(10));
+    assert_eq!(stats.max_update_latency, stats.average_update_latency);
+
+    // the same version isn't measured again
+    instance.tick();
+    assert_eq!(instance.stats().update_latency_samples, 1);
+
+    renderer.update("extracting", Duration::ZERO);
+    instance.tick();
+    let new_stats = instance.stats();
+    assert_eq!(new_stats.update_latency_samples, 2);
+    assert!(new_stats.average_update_latency < stats.average_update_latency);
+    assert!(new_stats.average_update_latency >= Duration::from_millis(700));
+    assert_eq!(new_stats.max_update_latency, stats.max_update_latency);
+    drop(guard);
+  }
+
+  #[test]
+  fn draws_into_writer_sink() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let writer = CaptureWriter::default();
+    let sink_guard = instance.add_writer_sink(
+      Box::new(writer.clone()),
+      ConsoleSize { cols: 30, rows: 4 },
+    );
+    let renderer = Arc::new(ToggleRenderer(Mutex::new("downloading")));
+    let guard = instance.add_entry(renderer.clone());
+    let size_guard = instance.add_entry(Arc::new(SizeRenderer));
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "80x20");
+    let text = writer.take_text();
+    assert_contains!(text, "downloading");
+    assert_contains!(text, "30x4");
+    assert_not_contains!(text, "80x20");
+
+    // moves the writer's cursor back up to replace the frame
+    *renderer.0.lock() = "extracting";
+    assert!(instance.tick());
+    let text = writer.take_text();
+    assert_contains!(text, "\x1b[1Aextracting");
+    assert!(instance.tick());
+    assert_eq!(writer.take_text(), "");
+
+    drop(guard);
+    drop(size_guard);
+    // cleared once the entries finish
+    assert_contains!(writer.take_text(), "\x1b[J");
+    drop(sink_guard);
+  }
+
+  #[derive(Debug, Default)]
+  struct RenderCountRenderer(Mutex<usize>);
+
+  impl DrawThreadRenderer for RenderCountRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      *self.0.lock() += 1;
+      "downloading".to_string()
+    }
+  }
+
+  #[test]
+  fn renders_once_per_sink_size() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let writers = [(); 3].map(|_| CaptureWriter::default());
+    let sizes = [
+      ConsoleSize { cols: 30, rows: 4 },
+      ConsoleSize { cols: 30, rows: 4 },
+      // the size of the terminal
+      ConsoleSize { cols: 80, rows: 20 },
+    ];
+    let sink_guards = writers
+      .iter()
+      .zip(sizes)
+      .map(|(writer, size)| {
+        instance.add_writer_sink(Box::new(writer.clone()), size)
+      })
+      .collect::<Vec<_>>();
+    let renderer = Arc::new(RenderCountRenderer::default());
+    let guard = instance.add_entry(renderer.clone());
+    assert!(instance.tick());
+    // once for the terminal and once for the smaller sinks
+    assert_eq!(*renderer.0.lock(), 2);
+    assert_contains!(terminal.output.take_text(), "downloading");
+    for writer in &writers {
+      assert_contains!(writer.take_text(), "downloading");
+    }
+    drop(guard);
+    drop(sink_guards);
+  }
+
+  #[test]
+  fn describes_entries() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    assert_eq!(instance.describe_entries(), Vec::new());
+    let first = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    let second = instance.add_entry(Arc::new(SizeRenderer));
+    assert_eq!(
+      instance.describe_entries(),
+      vec![
+        (
+          first.id.unwrap(),
+          "TextRenderer(\"downloading\")".to_string()
+        ),
+        (second.id.unwrap(), "SizeRenderer".to_string()),
+      ]
+    );
+    drop(first);
+    drop(second);
+  }
+
+  #[test]
+  fn commits_current_frame() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    // nothing is drawn yet
+    instance.commit_current();
+    assert_eq!(terminal.output.take_text(), "");
+
+    let renderer = Arc::new(ToggleRenderer(Mutex::new("resolved 10 packages")));
+    let guard = instance.add_entry(renderer.clone());
+    let text_guard = instance.add_entry(Arc::new(TextRenderer("1 of 3")));
+    assert!(instance.tick());
+    terminal.output.take_text();
+    instance.commit_current();
+    assert_eq!(terminal.output.take_text(), "\n");
+
+    // the new region is drawn below without moving up over the commit
+    *renderer.0.lock() = "downloading";
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert_contains!(text, "downloading");
+    assert_not_contains!(text, "\x1b[1A");
+    drop(guard);
+    drop(text_guard);
+  }
+
+  #[test]
+  fn places_entries_at_rows() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let flowing = instance.add_entry(Arc::new(TextRenderer("c1\nc2")));
+    let first =
+      instance.add_entry_at_rows(Arc::new(TextRenderer("a1\na2\na3")), 0, 2);
+    let second = instance.add_entry_at_rows(Arc::new(TextRenderer("b1")), 3, 2);
+    // clipped and padded to the rows, with the other entries around them
+    assert_eq!(instance.render_plain(80), "a1\na2\nc1\nb1\n\nc2");
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "a2\r\nc1\r\nb1");
+
+    // the later entry wins the overlapping rows
+    let overlapping =
+      instance.add_entry_at_rows(Arc::new(TextRenderer("d1")), 1, 1);
+    assert_eq!(instance.render_plain(80), "a1\nd1\nc1\nb1\n\nc2");
+    drop(overlapping);
+    drop(first);
+    drop(second);
+    assert_eq!(instance.render_plain(80), "c1\nc2");
+    drop(flowing);
+  }
+
+  #[test]
+  fn tracks_reader() {
+    use std::io::Read;
+
+    let _lock = TEST_LOCK.lock();
+
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    let bytes = b"0123456789";
+    let mut reader = instance.track_reader(&bytes[..], Some(10), "data");
+    assert_eq!(instance.active_ids().len(), 1);
+    let mut buffer = [0; 4];
+    assert_eq!(reader.read(&mut buffer).unwrap(), 4);
+    assert_eq!(reader.state().position(), 4);
+    assert_eq!(
+      reader.state().status(),
+      progress::ProgressStatus::InProgress
+    );
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"456789");
+    assert_eq!(reader.state().status(), progress::ProgressStatus::Completed);
+    assert_eq!(instance.render_plain(20), "data [########] 100%");
+    drop(reader);
+    assert_eq!(instance.active_ids(), Vec::<u16>::new());
+
+    // reaching the end completes a reader of an unknown length
+    let mut reader = instance.track_reader(&bytes[..], None, "data");
+    reader.read_to_end(&mut Vec::new()).unwrap();
+    assert_eq!(reader.state().length(), 10);
+    assert_eq!(reader.state().status(), progress::ProgressStatus::Completed);
+  }
+
+  #[test]
+  fn brackets_frames_in_synchronized_updates() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_synchronized_output(SynchronizedOutput::On);
+    let renderer = Arc::new(ToggleRenderer(Mutex::new("downloading")));
+    let guard = instance.add_entry(renderer.clone());
+    assert!(instance.tick());
+    let text = terminal.output.take_text();
+    assert!(text.starts_with(SYNCHRONIZED_UPDATE_BEGIN));
+    assert!(text.ends_with(SYNCHRONIZED_UPDATE_END));
+    assert_contains!(text, "downloading");
+    // nothing is written when the frame is unchanged
+    assert!(instance.tick());
+    assert_eq!(terminal.output.take_text(), "");
+
+    instance.println("done");
+    let text = terminal.output.take_text();
+    assert!(text.starts_with(SYNCHRONIZED_UPDATE_BEGIN));
+    assert!(text.ends_with(SYNCHRONIZED_UPDATE_END));
+    assert_contains!(text, "done\n");
+
+    instance.set_synchronized_output(SynchronizedOutput::Off);
+    *renderer.0.lock() = "extracting";
+    assert!(instance.tick());
+    assert_not_contains!(
+      terminal.output.take_text(),
+      SYNCHRONIZED_UPDATE_BEGIN
+    );
+    drop(guard);
+  }
+
+  #[test]
+  fn ticks_on_attached_ticker() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    let terminal = TestTerminal::with_manual(&instance, false);
+    let _supported = ForceSupported::new();
+    instance.take_stats();
+    let ticker =
+      instance.attach_ticker(Duration::from_millis(5), runtime.handle());
+    assert!(instance.state.lock().manual);
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    runtime.block_on(async {
+      while instance.stats().frames_rendered < 2 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+      }
+    });
+    assert_contains!(terminal.output.take_text(), "downloading");
+    drop(guard);
+
+    drop(ticker);
+    assert!(!instance.state.lock().manual);
+    // the task may be waiting for a tick on a blocking thread
+    runtime.block_on(async {
+      while runtime.metrics().num_alive_tasks() > 0 {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+      }
+    });
+  }
+
+  #[derive(Debug, Default)]
+  struct ThreadRenderer(Mutex<Vec<std::thread::ThreadId>>);
+
+  impl DrawThreadRenderer for ThreadRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      self.0.lock().push(std::thread::current().id());
+      String::new()
+    }
+  }
+
+  #[test]
+  fn ticker_ticks_off_the_runtime_and_restores_manual() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    let _terminal = TestTerminal::new(&instance);
+    let _supported = ForceSupported::new();
+    let renderer = Arc::new(ThreadRenderer::default());
+    let guard = instance.add_entry(renderer.clone());
+    let first =
+      instance.attach_ticker(Duration::from_millis(5), runtime.handle());
+    let second =
+      instance.attach_ticker(Duration::from_millis(5), runtime.handle());
+    runtime.block_on(async {
+      while renderer.0.lock().is_empty() {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+      }
+    });
+    // the runtime's thread isn't blocked by the rendering
+    let current = std::thread::current().id();
+    assert!(renderer.0.lock().iter().all(|id| *id != current));
+
+    // the manual mode was already enabled before the tickers
+    drop(first);
+    drop(second);
+    assert!(instance.state.lock().manual);
+    drop(guard);
+
+    instance.set_manual(false);
+    let first =
+      instance.attach_ticker(Duration::from_millis(5), runtime.handle());
+    let second =
+      instance.attach_ticker(Duration::from_millis(5), runtime.handle());
+    drop(first);
+    assert!(instance.state.lock().manual);
+    // the mode from before the replaced ticker is restored
+    drop(second);
+    assert!(!instance.state.lock().manual);
+  }
+
+  #[derive(Debug)]
+  struct SlowRenderer(Mutex<(&'static str, Duration)>);
+
+  impl DrawThreadRenderer for SlowRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      let (text, delay) = *self.0.lock();
+      std::thread::sleep(delay);
+      text.to_string()
+    }
+  }
+
+  #[test]
+  fn draws_last_text_after_render_timeout() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_render_timeout(Some(Duration::from_millis(50)));
+    let renderer =
+      Arc::new(SlowRenderer(Mutex::new(("resolving", Duration::ZERO))));
+    let guard = instance.add_entry(renderer.clone());
+    let other_guard = instance.add_entry(Arc::new(TextRenderer("other")));
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "resolving");
+
+    *renderer.0.lock() = ("downloading", Duration::from_millis(300));
+    let start = Instant::now();
+    assert!(instance.tick());
+    assert!(start.elapsed() < Duration::from_millis(250));
+    assert_not_contains!(terminal.output.take_text(), "downloading");
+    // the pending render isn't waited on or started again
+    let start = Instant::now();
+    assert!(instance.tick());
+    assert!(start.elapsed() < Duration::from_millis(50));
+
+    std::thread::sleep(Duration::from_millis(400));
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "downloading");
+    // the worker that finished is kept for the next frames
+    {
+      let internal_state = instance.state.lock();
+      let workers = internal_state.render_workers.lock();
+      assert!(workers.idle.is_some());
+      assert!(workers.pending.is_empty());
+    }
+    drop(guard);
+    drop(other_guard);
+  }
+
+  #[test]
+  fn ignores_entries_after_shutdown() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .max_blocking_threads(1)
+      .build()
+      .unwrap();
+    let _enter = runtime.enter();
+    let terminal = TestTerminal::with_manual(&instance, false);
+    let _supported = ForceSupported::new();
+    instance.shutdown();
+    let guard = instance.add_entry(Arc::new(TextRenderer("downloading")));
+    let token = CancellationToken::new();
+    let token_guard = instance
+      .add_entry_with_token(Arc::new(TextRenderer("token")), token.clone());
+    let (_sender, channel_guard) = instance.add_entry_from_channel();
+    assert_eq!(
+      (guard.id, token_guard.id, channel_guard.id),
+      (None, None, None)
+    );
+    // no watcher task is spawned for the entries
+    assert_eq!(runtime.metrics().num_alive_tasks(), 0);
+    assert_eq!(instance.active_ids(), Vec::<u16>::new());
+    assert!(!instance.state.lock().has_draw_thread);
+    instance.set_manual(true);
+    assert!(!instance.tick());
+    assert_eq!(terminal.output.take_text(), "");
+
+    instance.reset();
+    let other_guard = instance.add_entry(Arc::new(TextRenderer("other")));
+    // the entries added during the shutdown took no ids
+    assert_eq!(other_guard.id, Some(0));
+    // and their guards do nothing
+    token.cancel();
+    drop((guard, token_guard, channel_guard));
+    assert_eq!(instance.active_ids(), vec![0]);
+    assert!(instance.tick());
+    assert_contains!(terminal.output.take_text(), "other");
+    drop(other_guard);
+  }
+
+  #[test]
+  fn truncates_with_ellipsis() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    instance.set_wrap_mode(WrapMode::Ellipsis(text::Ellipsis::Middle));
+    let guard = instance.add_entry(Arc::new(TextRenderer(
+      "Downloading https://jsr.io/@std/path/1.0.0/mod.ts",
+    )));
+    assert_eq!(instance.render_plain(20), "Downloadin….0/mod.ts");
+    drop(guard);
+  }
+
+  #[test]
+  fn collapses_duplicate_lines() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    instance.set_collapse_duplicates(true);
+    let guards = [
+      "Downloading mod.ts",
+      "waiting for lock",
+      "waiting for lock",
+      "waiting for lock",
+      "Downloading main.ts",
+      "waiting for lock",
+    ]
+    .map(|text| instance.add_entry(Arc::new(TextRenderer(text))));
+    assert_eq!(
+      instance.render_plain(80),
+      concat!(
+        "Downloading mod.ts\n",
+        "waiting for lock (x3)\n",
+        "Downloading main.ts\n",
+        "waiting for lock",
+      )
+    );
+    instance.tick();
+    let text = terminal.output.take_text();
+    assert_contains!(text, "waiting for lock (x3)");
+    assert_eq!(text.matches("waiting for lock").count(), 2);
+
+    instance.set_collapse_duplicates(false);
+    assert_eq!(
+      instance
+        .render_plain(80)
+        .matches("waiting for lock")
+        .count(),
+      4
+    );
+    drop(guards);
+  }
+
+  #[test]
+  fn collapses_only_adjacent_duplicate_lines() {
+    assert_eq!(collapse_duplicate_lines("a\na\nb\na"), "a (x2)\nb\na");
+    assert_eq!(collapse_duplicate_lines("a\n\n\na"), "a\n\n\na");
+    assert_eq!(collapse_duplicate_lines(""), "");
+  }
+
+  #[test]
+  fn applies_events_from_channel() {
+    use progress::ProgressEvent;
+
+    let _lock = TEST_LOCK.lock();
+
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    runtime.block_on(async {
+      let (sender, guard) = instance.add_entry_from_channel();
+      sender
+        .send(ProgressEvent::SetMessage("data".into()))
+        .unwrap();
+      sender.send(ProgressEvent::SetTotal(10)).unwrap();
+      sender.send(ProgressEvent::Inc(4)).unwrap();
+      sender.send(ProgressEvent::Inc(1)).unwrap();
+      tokio::task::yield_now().await;
+      assert_eq!(instance.render_plain(20), "data [####-----] 50%");
+      sender.send(ProgressEvent::Finish).unwrap();
+      tokio::task::yield_now().await;
+      assert_eq!(instance.active_ids(), Vec::<u16>::new());
+      // the later events are ignored
+      assert!(sender.send(ProgressEvent::Inc(1)).is_err());
+      drop(guard);
+
+      // dropping the producer finishes the entry
+      let (sender, guard) = instance.add_entry_from_channel();
+      assert_eq!(instance.active_ids().len(), 1);
+      drop(sender);
+      tokio::task::yield_now().await;
+      assert_eq!(instance.active_ids(), Vec::<u16>::new());
+      drop(guard);
+
+      // dropping the guard first stops the task without finishing the
+      // entry that reuses the id
+      let (sender, guard) = instance.add_entry_from_channel();
+      drop(guard);
+      tokio::task::yield_now().await;
+      assert_eq!(runtime_alive_tasks(), 0);
+      assert!(sender.send(ProgressEvent::Inc(1)).is_err());
+    });
+  }
+
+  #[test]
+  fn aggregates_summary_contributions() {
+    use summary::SummaryPart;
+
+    #[derive(Debug)]
+    struct PartRenderer(Mutex<SummaryPart>);
+
+    impl DrawThreadRenderer for PartRenderer {
+      fn render(&self, _size: &ConsoleSize) -> String {
+        format!("{:?}", self.0.lock())
+      }
+
+      fn summary_contribution(&self) -> Option<SummaryPart> {
+        Some(*self.0.lock())
+      }
+    }
+
+    let _lock = TEST_LOCK.lock();
+
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let summary_guard = instance.add_entry_at_rows(
+      Arc::new(summary::SummaryRenderer::new(|counts| {
+        format!(
+          "compiling {}/{} crates ({} failed)",
+          counts.done,
+          counts.total(),
+          counts.failed
+        )
+      })),
+      0,
+      1,
+    );
+    let renderers = [
+      SummaryPart::Running,
+      SummaryPart::Done,
+      SummaryPart::Failed,
+      SummaryPart::Running,
+    ]
+    .map(|part| Arc::new(PartRenderer(Mutex::new(part))));
+    let mut guards = renderers
+      .iter()
+      .map(|renderer| instance.add_entry(renderer.clone()))
+      .collect::<Vec<_>>();
+    // entries that don't contribute aren't counted
+    let text_guard = instance.add_entry(Arc::new(TextRenderer("other")));
+    assert_eq!(
+      instance.render_plain(80),
+      concat!(
+        "compiling 1/4 crates (1 failed)\n",
+        "Running\nDone\nFailed\nRunning\nother",
+      )
+    );
+
+    // reflects the live entries each frame
+    *renderers[0].0.lock() = SummaryPart::Done;
+    guards.remove(1);
+    instance.tick();
+    assert_contains!(
+      terminal.output.take_text(),
+      "compiling 1/3 crates (1 failed)"
+    );
+    drop(guards);
+    assert_eq!(
+      instance.render_plain(80),
+      "compiling 0/0 crates (0 failed)\nother"
+    );
+    drop(text_guard);
+    drop(summary_guard);
+  }
+
+  #[test]
+  fn joins_lines_with_line_ending() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let frames = Arc::new(Mutex::new(Vec::new()));
+    let sink_guard = instance.add_sink(Box::new(CaptureSink(frames.clone())));
+    let guards = ["a", "b", "c"]
+      .map(|text| instance.add_entry(Arc::new(TextRenderer(text))));
+    instance.tick();
+    assert_eq!(frames.lock().pop().unwrap(), "a\nb\nc");
+    instance.println("started");
+    let text = terminal.output.take_text();
+    assert_contains!(text, "started\n");
+    assert_not_contains!(text, "started\r\n");
+
+    instance.set_line_ending(LineEnding::CrLf);
+    instance.println("done");
+    let text = terminal.output.take_text();
+    assert_contains!(text, "done\r\n");
+    assert_eq!(text.matches('\n').count(), text.matches("\r\n").count());
+    instance.tick();
+    assert_eq!(frames.lock().pop().unwrap(), "a\r\nb\r\nc");
+    // the rows are measured by their lines
+    let (drawn_text, _) = instance.state.lock().last_drawn.clone().unwrap();
+    assert_eq!(drawn_text, "a\nb\nc");
+
+    assert_eq!(LineEnding::CrLf.apply("a\r\nb\n\nc"), "a\r\nb\r\n\r\nc");
+    assert_eq!(LineEnding::Lf.apply("a\nb"), "a\nb");
+    drop(guards);
+    drop(sink_guard);
+  }
+
+  #[test]
+  fn moves_entries_to_front_and_back() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let _terminal = TestTerminal::new(&instance);
+    let [a, b, c] = ["a", "b", "c"]
+      .map(|text| instance.add_entry(Arc::new(TextRenderer(text))));
+    b.move_to_front();
+    assert_eq!(instance.render_plain(80), "b\na\nc");
+    b.move_to_back();
+    assert_eq!(instance.render_plain(80), "a\nc\nb");
+
+    // the front is the top row when the newest entries are drawn first
+    instance.set_order_direction(OrderDirection::Newest);
+    a.move_to_front();
+    assert_eq!(instance.render_plain(80), "a\nb\nc");
+    a.move_to_back();
+    assert_eq!(instance.render_plain(80), "b\nc\na");
+    instance.set_order_direction(OrderDirection::Oldest);
+
+    // finished entries aren't added back
+    let finished = instance.add_entry(Arc::new(TextRenderer("d")));
+    let finished_id = finished.id;
+    drop(finished);
+    let guard = DrawThreadGuard {
+      id: finished_id,
+      instance: instance.clone(),
+      lingering: false,
+    };
+    guard.move_to_front();
+    assert_eq!(instance.active_ids().len(), 3);
+    drop(guard);
+    drop((a, b, c));
+  }
+
+  #[test]
+  fn renders_verbose_entries() {
+    #[derive(Debug)]
+    struct DetailRenderer(&'static str);
+
+    impl DrawThreadRenderer for DetailRenderer {
+      fn render(&self, _size: &ConsoleSize) -> String {
+        self.0.to_string()
+      }
+
+      fn render_verbose(&self, _size: &ConsoleSize) -> String {
+        format!("{}\n  waiting on lock file\n  retried 3 times", self.0)
+      }
+    }
+
+    let _lock = TEST_LOCK.lock();
+
+    let instance = DrawThread::new();
+    let terminal = TestTerminal::new(&instance);
+    let a = instance.add_entry(Arc::new(DetailRenderer("a")));
+    let b = instance.add_entry(Arc::new(DetailRenderer("b")));
+    let c = instance.add_entry(Arc::new(TextRenderer("c")));
+    assert_eq!(instance.render_plain(80), "a\nb\nc");
+    b.set_verbose(true);
+    assert_eq!(
+      instance.render_plain(80),
+      "a\nb\n  waiting on lock file\n  retried 3 times\nc"
+    );
+    // defaults to the regular text
+    c.set_verbose(true);
+    instance.tick();
+    let text = terminal.output.take_text();
+    assert_contains!(text, "retried 3 times");
+    assert_eq!(text.matches("waiting on lock file").count(), 1);
+    b.set_verbose(false);
+    assert_eq!(instance.render_plain(80), "a\nb\nc");
+    drop((a, b, c));
+  }
+
+  #[test]
+  fn skips_live_ids_after_wrapping() {
+    let _lock = TEST_LOCK.lock();
+    let instance = DrawThread::new();
+    let first_guard = instance.add_entry(Arc::new(TextRenderer("first")));
+    let second_guard = instance.add_entry(Arc::new(TextRenderer("second")));
+    assert_eq!((first_guard.id, second_guard.id), (Some(0), Some(1)));
+    instance.state.lock().next_entry_id = u16::MAX;
+    let guard = instance.add_entry(Arc::new(TextRenderer("wrapped")));
+    assert_eq!(guard.id, Some(u16::MAX));
+    let guard = instance.add_entry(Arc::new(TextRenderer("skipped")));
+    assert_eq!(guard.id, Some(2));
+    assert_eq!(instance.render_plain(20), "first\nsecond\nwrapped\nskipped");
+  }
+}
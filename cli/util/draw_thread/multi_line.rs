@@ -0,0 +1,197 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use deno_core::parking_lot::Mutex;
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::context::RenderContext;
+use super::DrawThreadRenderer;
+
+#[derive(Debug)]
+struct CachedText {
+  text: String,
+  size: ConsoleSize,
+  rendered_at: Instant,
+}
+
+#[derive(Debug)]
+struct SubLine {
+  renderer: Arc<dyn DrawThreadRenderer>,
+  refresh_interval: Duration,
+  cache: Mutex<Option<CachedText>>,
+}
+
+impl SubLine {
+  fn render(
+    &self,
+    size: &ConsoleSize,
+    frame: u64,
+    context: &RenderContext,
+    now: Instant,
+  ) -> String {
+    let mut cache = self.cache.lock();
+    if let Some(cached) = &*cache {
+      if cached.size == *size
+        && now.saturating_duration_since(cached.rendered_at)
+          < self.refresh_interval
+      {
+        return cached.text.clone();
+      }
+    }
+    let text = self.renderer.render_with_context(size, frame, context);
+    *cache = Some(CachedText {
+      text: text.clone(),
+      size: *size,
+      rendered_at: now,
+    });
+    text
+  }
+}
+
+/// Renders the lines of multiple renderers within a single entry, where
+/// each line is only rendered again once its refresh interval elapses.
+/// This prevents lines that are expensive to format, such as an
+/// estimate, from being formatted every frame alongside lines that
+/// update quickly. Lines are rendered again immediately on a resize.
+#[derive(Debug, Default)]
+pub struct MultiLineRenderer {
+  lines: Vec<SubLine>,
+}
+
+// the embedders build the lines of the renderer
+#[allow(dead_code)]
+impl MultiLineRenderer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a line that's rendered at most once per refresh interval. Use
+  /// `Duration::ZERO` to render the line every frame.
+  pub fn with_line(
+    mut self,
+    renderer: Arc<dyn DrawThreadRenderer>,
+    refresh_interval: Duration,
+  ) -> Self {
+    self.lines.push(SubLine {
+      renderer,
+      refresh_interval,
+      cache: Default::default(),
+    });
+    self
+  }
+
+  fn render_at(
+    &self,
+    size: &ConsoleSize,
+    frame: u64,
+    context: &RenderContext,
+    now: Instant,
+  ) -> String {
+    self
+      .lines
+      .iter()
+      .map(|line| line.render(size, frame, context, now))
+      .filter(|text| !text.is_empty())
+      .collect::<Vec<_>>()
+      .join("\n")
+  }
+}
+
+impl DrawThreadRenderer for MultiLineRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    self.render_frame(size, 0)
+  }
+
+  fn render_frame(&self, size: &ConsoleSize, frame: u64) -> String {
+    self.render_with_context(size, frame, &Default::default())
+  }
+
+  fn render_with_context(
+    &self,
+    size: &ConsoleSize,
+    frame: u64,
+    context: &RenderContext,
+  ) -> String {
+    self.render_at(size, frame, context, Instant::now())
+  }
+
+  fn min_cols(&self) -> u16 {
+    self
+      .lines
+      .iter()
+      .map(|line| line.renderer.min_cols())
+      .max()
+      .unwrap_or(0)
+  }
+
+  fn progress(&self) -> Option<f64> {
+    self.lines.iter().find_map(|line| line.renderer.progress())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const SIZE: ConsoleSize = ConsoleSize { cols: 80, rows: 20 };
+
+  #[derive(Debug, Default)]
+  struct CountRenderer {
+    name: &'static str,
+    count: AtomicUsize,
+  }
+
+  impl DrawThreadRenderer for CountRenderer {
+    fn render(&self, _data: &ConsoleSize) -> String {
+      let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+      format!("{} {}", self.name, count)
+    }
+  }
+
+  #[test]
+  fn reuses_cached_lines_between_refreshes() {
+    let fast = Arc::new(CountRenderer {
+      name: "bytes",
+      ..Default::default()
+    });
+    let slow = Arc::new(CountRenderer {
+      name: "eta",
+      ..Default::default()
+    });
+    let renderer = MultiLineRenderer::new()
+      .with_line(fast.clone(), Duration::ZERO)
+      .with_line(slow.clone(), Duration::from_secs(1));
+    let context = RenderContext::default();
+    let start = Instant::now();
+    assert_eq!(
+      renderer.render_at(&SIZE, 0, &context, start),
+      "bytes 1\neta 1"
+    );
+    let now = start + Duration::from_millis(500);
+    assert_eq!(
+      renderer.render_at(&SIZE, 1, &context, now),
+      "bytes 2\neta 1"
+    );
+    let now = start + Duration::from_secs(1);
+    assert_eq!(
+      renderer.render_at(&SIZE, 2, &context, now),
+      "bytes 3\neta 2"
+    );
+    assert_eq!(slow.count.load(Ordering::Relaxed), 2);
+
+    // a resize renders every line again
+    let size = ConsoleSize { cols: 40, rows: 20 };
+    assert_eq!(
+      renderer.render_at(&size, 3, &context, now),
+      "bytes 4\neta 3"
+    );
+  }
+}
@@ -0,0 +1,459 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use deno_core::parking_lot::Mutex;
+
+use crate::util::display::human_download_size;
+
+pub const SPINNER_CHARS: [&str; 8] = ["⣷", "⣯", "⣟", "⡿", "⢿", "⣻", "⣽", "⣾"];
+
+/// How far back the samples of the position are used for the rate.
+const DEFAULT_RATE_WINDOW: Duration = Duration::from_secs(2);
+
+/// Where a progress operation is at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressStatus {
+  InProgress,
+  Completed,
+  Failed,
+}
+
+/// State of a progress operation shared between the code doing the
+/// work and the renderer displaying it.
+///
+/// The numbers are atomics so that updating them from hot loops, such
+/// as for every downloaded chunk, doesn't contend on a lock with the
+/// renderer. The message is behind a lock since it's updated rarely.
+#[derive(Debug)]
+pub struct ProgressState {
+  position: AtomicU64,
+  /// Total length of the operation or `0` when unknown.
+  length: AtomicU64,
+  message: Mutex<String>,
+  start_time: Instant,
+  /// Whether the operation failed, which is shown instead
+  /// of its progress.
+  failed: AtomicBool,
+  /// The positions sampled when the rate was read, oldest first.
+  samples: Mutex<VecDeque<(Instant, u64)>>,
+  rate_window: Duration,
+}
+
+impl Default for ProgressState {
+  fn default() -> Self {
+    Self::new(0, String::new())
+  }
+}
+
+// not every operation sets the position or the message
+#[allow(dead_code)]
+impl ProgressState {
+  pub fn new(length: u64, message: impl Into<String>) -> Self {
+    Self {
+      position: AtomicU64::new(0),
+      length: AtomicU64::new(length),
+      message: Mutex::new(message.into()),
+      start_time: Instant::now(),
+      failed: AtomicBool::new(false),
+      samples: Default::default(),
+      rate_window: DEFAULT_RATE_WINDOW,
+    }
+  }
+
+  /// Sets how far back the samples of the position are used for the
+  /// rate. Defaults to 2 seconds.
+  pub fn with_rate_window(mut self, window: Duration) -> Self {
+    self.rate_window = window;
+    self
+  }
+
+  pub fn position(&self) -> u64 {
+    self.position.load(Ordering::Relaxed)
+  }
+
+  pub fn set_position(&self, position: u64) {
+    self.position.store(position, Ordering::Relaxed);
+  }
+
+  pub fn inc(&self, delta: u64) {
+    // never fails since the closure always returns a value
+    let _ = self.position.fetch_update(
+      Ordering::Relaxed,
+      Ordering::Relaxed,
+      |position| Some(position.saturating_add(delta)),
+    );
+  }
+
+  /// Gets the total length of the operation or `0` when unknown.
+  pub fn length(&self) -> u64 {
+    self.length.load(Ordering::Relaxed)
+  }
+
+  pub fn set_length(&self, length: u64) {
+    self.length.store(length, Ordering::Relaxed);
+  }
+
+  pub fn message(&self) -> String {
+    self.message.lock().clone()
+  }
+
+  pub fn set_message(&self, message: impl Into<String>) {
+    *self.message.lock() = message.into();
+  }
+
+  pub fn start_time(&self) -> Instant {
+    self.start_time
+  }
+
+  /// Marks the operation as failed.
+  pub fn fail(&self) {
+    self.failed.store(true, Ordering::Relaxed);
+  }
+
+  /// Gets whether the operation is in progress, completed, or failed.
+  /// An operation of an unknown length is never completed.
+  pub fn status(&self) -> ProgressStatus {
+    let length = self.length();
+    if self.failed.load(Ordering::Relaxed) {
+      ProgressStatus::Failed
+    } else if length > 0 && self.position() >= length {
+      ProgressStatus::Completed
+    } else {
+      ProgressStatus::InProgress
+    }
+  }
+
+  /// Gets whether the length is known. The length may be set later
+  /// on, such as once a download's size is discovered.
+  pub fn is_determinate(&self) -> bool {
+    self.length() > 0
+  }
+
+  /// Gets the completed fraction between `0.0` and `1.0` or
+  /// `None` when the length is unknown. The position is clamped
+  /// to the length.
+  pub fn fraction(&self) -> Option<f64> {
+    let length = self.length();
+    if length == 0 {
+      None
+    } else {
+      Some((self.position() as f64 / length as f64).min(1f64))
+    }
+  }
+
+  pub fn elapsed(&self) -> Duration {
+    self.start_time.elapsed()
+  }
+
+  /// Samples the position and gets the current rate per second over
+  /// the rate window, or `None` until there are two samples.
+  ///
+  /// The position is only sampled when the rate is read, such as every
+  /// frame, so updating it from hot loops doesn't take the lock.
+  pub fn sample_rate(&self) -> Option<f64> {
+    self.sample_rate_at(Instant::now())
+  }
+
+  fn sample_rate_at(&self, now: Instant) -> Option<f64> {
+    let mut samples = self.samples.lock();
+    samples.push_back((now, self.position()));
+    // keep the newest sample before the window as the baseline, so that
+    // the rate decays towards zero over a long idle gap
+    let window_start = now.checked_sub(self.rate_window);
+    while samples.len() > 2
+      && samples
+        .get(1)
+        .is_some_and(|(time, _)| Some(*time) <= window_start)
+    {
+      samples.pop_front();
+    }
+    let (first_time, first_position) = *samples.front()?;
+    let elapsed = now.saturating_duration_since(first_time).as_secs_f64();
+    if samples.len() < 2 || elapsed == 0f64 {
+      return None;
+    }
+    Some(self.position().saturating_sub(first_position) as f64 / elapsed)
+  }
+
+  /// Estimates the time remaining based on the rate so far.
+  pub fn eta(&self) -> Option<Duration> {
+    let fraction = self.fraction()?;
+    if fraction == 0f64 {
+      return None;
+    }
+    let elapsed = self.elapsed().as_secs_f64();
+    Some(Duration::from_secs_f64(
+      elapsed / fraction * (1f64 - fraction),
+    ))
+  }
+}
+
+/// An update to a progress operation sent over the channel of an entry
+/// added via `DrawThread::add_entry_from_channel`.
+// sent by the producers of `add_entry_from_channel`
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+  /// Sets the total length of the operation.
+  SetTotal(u64),
+  /// Adds to the position of the operation.
+  Inc(u64),
+  SetMessage(String),
+  /// Removes the entry, which also happens once every sender is dropped.
+  Finish,
+}
+
+/// The style durations are formatted in, such as for the elapsed time
+/// and estimates. This can be set for all the built-in renderers as a
+/// `DurationFormat` value of the `RenderContext`.
+// picked by the embedders via the render context
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationFormat {
+  /// Such as "1m2s".
+  Compact,
+  /// Such as "1:02".
+  Colon,
+  /// Such as "1 minute 2 seconds".
+  Verbose,
+}
+
+/// Formats a rate of bytes per second, such as "1.50MiB/s".
+pub fn format_speed(bytes_per_sec: f64) -> String {
+  let bytes_per_sec = bytes_per_sec.max(0f64) as u64;
+  format!("{}/s", human_download_size(bytes_per_sec, bytes_per_sec))
+}
+
+/// Formats the duration in whole seconds, rounded down.
+pub fn format_duration(duration: Duration, format: DurationFormat) -> String {
+  let secs = duration.as_secs();
+  let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+  match format {
+    DurationFormat::Compact => {
+      if hours > 0 {
+        format!("{}h{}m{}s", hours, mins, secs)
+      } else if mins > 0 {
+        format!("{}m{}s", mins, secs)
+      } else {
+        format!("{}s", secs)
+      }
+    }
+    DurationFormat::Colon => {
+      if hours > 0 {
+        format!("{}:{:0>2}:{:0>2}", hours, mins, secs)
+      } else {
+        format!("{}:{:0>2}", mins, secs)
+      }
+    }
+    DurationFormat::Verbose => {
+      let parts = [(hours, "hour"), (mins, "minute"), (secs, "second")]
+        .into_iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| {
+          format!("{} {}{}", value, unit, if value == 1 { "" } else { "s" })
+        })
+        .collect::<Vec<_>>();
+      if parts.is_empty() {
+        "0 seconds".to_string()
+      } else {
+        parts.join(" ")
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn should_get_fraction() {
+    let state = ProgressState::new(0, "");
+    assert_eq!(state.fraction(), None);
+    assert_eq!(state.eta(), None);
+    assert!(!state.is_determinate());
+    state.set_length(10);
+    assert!(state.is_determinate());
+    assert_eq!(state.fraction(), Some(0f64));
+    assert_eq!(state.eta(), None);
+    state.inc(5);
+    assert_eq!(state.fraction(), Some(0.5f64));
+    state.inc(10);
+    assert_eq!(state.fraction(), Some(1f64));
+  }
+
+  #[test]
+  fn should_get_status() {
+    let state = ProgressState::new(0, "");
+    state.inc(5);
+    assert_eq!(state.status(), ProgressStatus::InProgress);
+    state.set_length(10);
+    assert_eq!(state.status(), ProgressStatus::InProgress);
+    state.inc(5);
+    assert_eq!(state.status(), ProgressStatus::Completed);
+    state.fail();
+    assert_eq!(state.status(), ProgressStatus::Failed);
+  }
+
+  #[test]
+  fn should_get_windowed_rate() {
+    let start = Instant::now();
+    let at = |millis| start + Duration::from_millis(millis);
+    let state = ProgressState::new(0, "");
+    assert_eq!(state.sample_rate_at(at(0)), None);
+    assert_eq!(state.sample_rate_at(at(0)), None);
+
+    // two fast seconds followed by two slow ones, sampled every 100ms
+    let mut millis = 0;
+    while millis < 4000 {
+      millis += 100;
+      state.inc(if millis <= 2000 { 10_000 } else { 1_000 });
+      state.sample_rate_at(at(millis));
+    }
+    let average = state.position() as f64 / 4f64;
+    assert_eq!(average, 55_000f64);
+    assert_eq!(state.sample_rate_at(at(millis)), Some(10_000f64));
+
+    // decays over an idle gap
+    assert_eq!(state.sample_rate_at(at(millis + 1000)), Some(5_000f64));
+    assert_eq!(state.sample_rate_at(at(millis + 1500)), Some(2_500f64));
+    assert_eq!(state.sample_rate_at(at(millis + 2000)), Some(0f64));
+
+    let state = ProgressState::new(0, "").with_rate_window(Duration::ZERO);
+    state.sample_rate_at(at(0));
+    state.inc(500);
+    assert_eq!(state.sample_rate_at(at(500)), Some(1000f64));
+  }
+
+  #[test]
+  fn should_format_speed() {
+    assert_eq!(format_speed(0f64), "0.00KiB/s");
+    assert_eq!(format_speed(-1f64), "0.00KiB/s");
+    assert_eq!(format_speed(1536f64), "1.50KiB/s");
+    assert_eq!(format_speed(3f64 * 1024f64 * 1024f64), "3.00MiB/s");
+  }
+
+  #[test]
+  fn should_format_duration() {
+    #[track_caller]
+    fn assert_formats(duration: Duration, expected: [&str; 3]) {
+      let formats = [
+        DurationFormat::Compact,
+        DurationFormat::Colon,
+        DurationFormat::Verbose,
+      ];
+      assert_eq!(formats.map(|f| format_duration(duration, f)), expected);
+    }
+
+    assert_formats(Duration::ZERO, ["0s", "0:00", "0 seconds"]);
+    assert_formats(Duration::from_millis(999), ["0s", "0:00", "0 seconds"]);
+    assert_formats(Duration::from_secs(1), ["1s", "0:01", "1 second"]);
+    assert_formats(Duration::from_secs(59), ["59s", "0:59", "59 seconds"]);
+    assert_formats(Duration::from_secs(60), ["1m0s", "1:00", "1 minute"]);
+    assert_formats(
+      Duration::from_secs(62),
+      ["1m2s", "1:02", "1 minute 2 seconds"],
+    );
+    assert_formats(
+      Duration::from_secs(3599),
+      ["59m59s", "59:59", "59 minutes 59 seconds"],
+    );
+    assert_formats(Duration::from_secs(3600), ["1h0m0s", "1:00:00", "1 hour"]);
+    assert_formats(
+      Duration::from_secs(2 * 3600 + 5),
+      ["2h0m5s", "2:00:05", "2 hours 5 seconds"],
+    );
+  }
+
+  /// Compares updating the position from multiple threads while it's
+  /// rendered, with the state behind a mutex like it used to be, against
+  /// the atomics. Run with
+  /// `cargo test --release bench_chunked_updates -- --ignored`.
+  #[test]
+  #[ignore]
+  fn bench_chunked_updates() {
+    use std::sync::Arc;
+
+    const THREAD_COUNT: usize = 4;
+    const CHUNK_COUNT: u64 = 1_000_000;
+    const LENGTH: u64 = THREAD_COUNT as u64 * CHUNK_COUNT;
+
+    /// Increments the position in chunks from multiple threads while
+    /// another thread renders until all the chunks were added.
+    fn run(
+      inc: impl Fn() + Send + Sync + 'static,
+      render: impl Fn() -> Option<f64> + Send + 'static,
+    ) -> Duration {
+      let inc = Arc::new(inc);
+      let start = Instant::now();
+      let updaters = (0..THREAD_COUNT)
+        .map(|_| {
+          let inc = inc.clone();
+          std::thread::spawn(move || {
+            for _ in 0..CHUNK_COUNT {
+              inc();
+            }
+          })
+        })
+        .collect::<Vec<_>>();
+      let renderer = std::thread::spawn(move || {
+        while render() != Some(1f64) {
+          std::hint::spin_loop();
+        }
+      });
+      for updater in updaters {
+        updater.join().unwrap();
+      }
+      renderer.join().unwrap();
+      start.elapsed()
+    }
+
+    #[derive(Default)]
+    struct MutexState {
+      position: u64,
+      length: u64,
+    }
+
+    let mutex_state = Arc::new(Mutex::new(MutexState {
+      length: LENGTH,
+      ..Default::default()
+    }));
+    let mutex_elapsed = run(
+      {
+        let state = mutex_state.clone();
+        move || {
+          let mut state = state.lock();
+          state.position = state.position.saturating_add(1);
+        }
+      },
+      move || {
+        let state = mutex_state.lock();
+        Some(state.position as f64 / state.length as f64)
+      },
+    );
+
+    let atomic_state = Arc::new(ProgressState::new(LENGTH, ""));
+    let atomic_elapsed = run(
+      {
+        let state = atomic_state.clone();
+        move || state.inc(1)
+      },
+      move || atomic_state.fraction(),
+    );
+
+    assert!(
+      atomic_elapsed < mutex_elapsed,
+      "atomics: {:?}, mutex: {:?}",
+      atomic_elapsed,
+      mutex_elapsed
+    );
+  }
+}
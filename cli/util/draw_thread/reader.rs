@@ -0,0 +1,61 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::io::Read;
+use std::sync::Arc;
+
+use super::progress::ProgressState;
+use super::DrawThreadGuard;
+
+/// Wraps a reader to display how many of its bytes were read, which is
+/// created via `DrawThread::track_reader`. The entry is removed once
+/// this is dropped, whether or not the reader was read to the end.
+#[derive(Debug)]
+pub struct TrackedReader<R> {
+  reader: R,
+  state: Arc<ProgressState>,
+  _guard: DrawThreadGuard,
+}
+
+impl<R: Read> TrackedReader<R> {
+  pub(super) fn new(
+    reader: R,
+    state: Arc<ProgressState>,
+    guard: DrawThreadGuard,
+  ) -> Self {
+    Self {
+      reader,
+      state,
+      _guard: guard,
+    }
+  }
+
+  /// Gets the state of the progress, such as for changing its message.
+  // for the callers that inspect the tracked position
+  #[allow(dead_code)]
+  pub fn state(&self) -> &Arc<ProgressState> {
+    &self.state
+  }
+}
+
+impl<R: Read> Read for TrackedReader<R> {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    match self.reader.read(buf) {
+      Ok(0) if !buf.is_empty() => {
+        // the end was reached, so complete the progress even when the
+        // total was unknown or wrong
+        self.state.set_length(self.state.position());
+        Ok(0)
+      }
+      Ok(read_count) => {
+        self.state.inc(read_count as u64);
+        Ok(read_count)
+      }
+      Err(err) => {
+        if err.kind() != std::io::ErrorKind::Interrupted {
+          self.state.fail();
+        }
+        Err(err)
+      }
+    }
+  }
+}
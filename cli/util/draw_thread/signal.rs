@@ -0,0 +1,259 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+//! Clears the drawn region when the process is interrupted, since the
+//! region is otherwise left on the screen. The handlers can't acquire the
+//! internal state lock, so they rely on the number of rows drawn, which
+//! is only recorded for the default instance of the draw thread.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Once;
+
+static DRAWN_ROW_COUNT: AtomicUsize = AtomicUsize::new(0);
+static IS_SCROLL_REGION_SET: AtomicBool = AtomicBool::new(false);
+
+/// Sets the number of rows currently drawn below the cursor's line,
+/// including the cursor's line.
+pub fn set_drawn_row_count(count: usize) {
+  DRAWN_ROW_COUNT.store(count, Ordering::Relaxed);
+}
+
+/// Sets whether the scroll region of the terminal is set, which is
+/// reset when interrupted so the terminal keeps scrolling normally.
+pub fn set_scroll_region_set(value: bool) {
+  IS_SCROLL_REGION_SET.store(value, Ordering::Relaxed);
+}
+
+/// Installs the handlers for SIGINT and SIGTERM on unix or the
+/// console control handler on Windows. Installing more than once
+/// does nothing.
+pub fn install() {
+  static INSTALL: Once = Once::new();
+  INSTALL.call_once(imp::install);
+}
+
+const CLEAR_TEXT_CAPACITY: usize = 40;
+
+/// Writes the text that clears the provided number of rows and shows the
+/// cursor into the buffer, returning its length. This doesn't allocate
+/// so that it's safe to call from a signal handler.
+fn write_clear_text(
+  row_count: usize,
+  buffer: &mut [u8; CLEAR_TEXT_CAPACITY],
+) -> usize {
+  fn push(buffer: &mut [u8], len: &mut usize, bytes: &[u8]) {
+    buffer[*len..*len + bytes.len()].copy_from_slice(bytes);
+    *len += bytes.len();
+  }
+
+  let mut len = 0;
+  if row_count == 0 {
+    return len;
+  }
+  push(buffer, &mut len, b"\r");
+  if row_count > 1 {
+    // move the cursor up to the first row
+    let mut digits = [0u8; 20];
+    let mut digit_count = 0;
+    let mut value = row_count - 1;
+    while value > 0 {
+      digits[digit_count] = b'0' + (value % 10) as u8;
+      digit_count += 1;
+      value /= 10;
+    }
+    digits[..digit_count].reverse();
+    push(buffer, &mut len, b"\x1b[");
+    push(buffer, &mut len, &digits[..digit_count]);
+    push(buffer, &mut len, b"A");
+  }
+  // erase below the cursor, then show the cursor
+  push(buffer, &mut len, b"\x1b[J\x1b[?25h");
+  len
+}
+
+/// Resets the scroll region, restoring the cursor's position after.
+const RESET_SCROLL_REGION_TEXT: &[u8] = b"\x1b7\x1b[r\x1b8";
+
+/// Clears the drawn rows and resets the scroll region if it's set. This
+/// is safe to call from a signal handler.
+fn clear_drawn_rows(write: impl Fn(&[u8])) {
+  if IS_SCROLL_REGION_SET.swap(false, Ordering::Relaxed) {
+    write(RESET_SCROLL_REGION_TEXT);
+  }
+  let row_count = DRAWN_ROW_COUNT.swap(0, Ordering::Relaxed);
+  let mut buffer = [0u8; CLEAR_TEXT_CAPACITY];
+  let len = write_clear_text(row_count, &mut buffer);
+  if len > 0 {
+    write(&buffer[..len]);
+  }
+}
+
+#[cfg(unix)]
+mod imp {
+  use std::sync::OnceLock;
+
+  static PREVIOUS_SIGINT: OnceLock<libc::sigaction> = OnceLock::new();
+  static PREVIOUS_SIGTERM: OnceLock<libc::sigaction> = OnceLock::new();
+
+  pub fn install() {
+    install_handler(libc::SIGINT, &PREVIOUS_SIGINT);
+    install_handler(libc::SIGTERM, &PREVIOUS_SIGTERM);
+  }
+
+  fn install_handler(
+    signal: libc::c_int,
+    previous: &OnceLock<libc::sigaction>,
+  ) {
+    // SAFETY: the actions are initialized before being provided to
+    // sigaction and the handler only does async-signal-safe work
+    unsafe {
+      let mut previous_action: libc::sigaction = std::mem::zeroed();
+      if libc::sigaction(signal, std::ptr::null(), &mut previous_action) != 0 {
+        return;
+      }
+      let _ = previous.set(previous_action);
+
+      let mut action: libc::sigaction = std::mem::zeroed();
+      action.sa_sigaction = handle_signal as usize;
+      action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+      libc::sigemptyset(&mut action.sa_mask);
+      libc::sigaction(signal, &action, std::ptr::null_mut());
+    }
+  }
+
+  /// Gets whether this module's handler is still the one installed for
+  /// the signal. This is safe to call from a signal handler.
+  fn is_current_handler(signal: libc::c_int) -> bool {
+    // SAFETY: sigaction is async-signal-safe and the action is
+    // initialized before being provided to it
+    unsafe {
+      let mut current: libc::sigaction = std::mem::zeroed();
+      libc::sigaction(signal, std::ptr::null(), &mut current) == 0
+        && current.sa_sigaction == handle_signal as usize
+    }
+  }
+
+  extern "C" fn handle_signal(
+    signal: libc::c_int,
+    info: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+  ) {
+    super::clear_drawn_rows(|bytes| {
+      // SAFETY: write is async-signal-safe and the buffer is valid
+      unsafe {
+        libc::write(
+          libc::STDERR_FILENO,
+          bytes.as_ptr() as *const libc::c_void,
+          bytes.len(),
+        );
+      }
+    });
+
+    let previous = match signal {
+      libc::SIGINT => PREVIOUS_SIGINT.get(),
+      libc::SIGTERM => PREVIOUS_SIGTERM.get(),
+      _ => None,
+    };
+    let Some(previous) = previous else {
+      return;
+    };
+    // chain to the previous handler rather than swallowing the signal
+    let handler = previous.sa_sigaction;
+    if handler == libc::SIG_DFL {
+      if !is_current_handler(signal) {
+        // a handler installed later, such as for `Deno.addSignalListener`,
+        // chained to this one, so leave the termination to its owner
+        return;
+      }
+      // restore the default action and raise the signal again, which
+      // is delivered once this handler returns
+      // SAFETY: signal and raise are async-signal-safe
+      unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+      }
+    } else if handler != libc::SIG_IGN {
+      if previous.sa_flags & libc::SA_SIGINFO != 0 {
+        // SAFETY: the previous handler was installed with SA_SIGINFO
+        let handler: extern "C" fn(
+          libc::c_int,
+          *mut libc::siginfo_t,
+          *mut libc::c_void,
+        ) = unsafe { std::mem::transmute(handler) };
+        handler(signal, info, context);
+      } else {
+        // SAFETY: the previous handler was installed without SA_SIGINFO
+        let handler: extern "C" fn(libc::c_int) =
+          unsafe { std::mem::transmute(handler) };
+        handler(signal);
+      }
+    }
+  }
+}
+
+#[cfg(windows)]
+mod imp {
+  use std::io::Write;
+
+  use winapi::shared::minwindef::BOOL;
+  use winapi::shared::minwindef::DWORD;
+  use winapi::shared::minwindef::FALSE;
+  use winapi::shared::minwindef::TRUE;
+  use winapi::um::consoleapi::SetConsoleCtrlHandler;
+
+  pub fn install() {
+    // SAFETY: the handler is a valid function for the lifetime
+    // of the process
+    unsafe {
+      SetConsoleCtrlHandler(Some(handle_ctrl), TRUE);
+    }
+  }
+
+  unsafe extern "system" fn handle_ctrl(_ctrl_type: DWORD) -> BOOL {
+    super::clear_drawn_rows(|bytes| {
+      let mut stderr = std::io::stderr();
+      let _ = stderr.write_all(bytes);
+      let _ = stderr.flush();
+    });
+    // let the next handler run, which ends up being the default
+    FALSE
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn clear_text(row_count: usize) -> String {
+    let mut buffer = [0u8; CLEAR_TEXT_CAPACITY];
+    let len = write_clear_text(row_count, &mut buffer);
+    String::from_utf8(buffer[..len].to_vec()).unwrap()
+  }
+
+  #[test]
+  fn should_write_clear_text() {
+    assert_eq!(clear_text(0), "");
+    assert_eq!(clear_text(1), "\r\x1b[J\x1b[?25h");
+    assert_eq!(clear_text(3), "\r\x1b[2A\x1b[J\x1b[?25h");
+    assert_eq!(clear_text(124), "\r\x1b[123A\x1b[J\x1b[?25h");
+    assert_eq!(
+      clear_text(usize::MAX),
+      format!("\r\x1b[{}A\x1b[J\x1b[?25h", usize::MAX - 1)
+    );
+  }
+
+  #[test]
+  fn should_reset_scroll_region() {
+    let written = std::cell::RefCell::new(Vec::new());
+    let write = |bytes: &[u8]| written.borrow_mut().extend_from_slice(bytes);
+    set_scroll_region_set(true);
+    clear_drawn_rows(write);
+    assert!(written.take().starts_with(RESET_SCROLL_REGION_TEXT));
+    // only reset once
+    clear_drawn_rows(write);
+    assert!(!written.take().starts_with(RESET_SCROLL_REGION_TEXT));
+  }
+}
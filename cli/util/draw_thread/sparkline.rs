@@ -0,0 +1,129 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::collections::VecDeque;
+
+use deno_core::parking_lot::Mutex;
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::DrawThread;
+use super::DrawThreadRenderer;
+
+const UNICODE_LEVELS: &[char] = &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_LEVELS: &[char] = &['_', '.', ',', '-', '~', '=', '*', '#'];
+
+/// Renders the recent samples of a metric, such as the throughput of a
+/// download, as a sparkline with the newest sample on the right.
+///
+/// The levels are scaled to the lowest and highest of the samples that
+/// fit in the width, so the sparkline shows the trend rather than the
+/// magnitude. When all of them are the same, they're drawn at the
+/// middle level.
+#[derive(Debug)]
+pub struct SparklineRenderer {
+  samples: Mutex<VecDeque<f64>>,
+  capacity: usize,
+  levels: &'static [char],
+}
+
+// fed the samples by the callers
+#[allow(dead_code)]
+impl SparklineRenderer {
+  /// Creates a renderer that keeps up to `capacity` samples, discarding
+  /// the oldest sample once it's full.
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      samples: Mutex::new(VecDeque::with_capacity(capacity)),
+      capacity,
+      levels: UNICODE_LEVELS,
+    }
+    .with_unicode(DrawThread::terminal_capabilities().unicode)
+  }
+
+  /// Sets whether the levels are drawn with unicode blocks rather than
+  /// ASCII. Defaults to whether the terminal supports unicode.
+  pub fn with_unicode(mut self, value: bool) -> Self {
+    self.levels = if value { UNICODE_LEVELS } else { ASCII_LEVELS };
+    self
+  }
+
+  /// Adds a sample, ignoring it when it's not finite.
+  pub fn push_sample(&self, sample: f64) {
+    if !sample.is_finite() || self.capacity == 0 {
+      return;
+    }
+    let mut samples = self.samples.lock();
+    if samples.len() == self.capacity {
+      samples.pop_front();
+    }
+    samples.push_back(sample);
+  }
+}
+
+impl DrawThreadRenderer for SparklineRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    let samples = self.samples.lock();
+    let start = samples.len().saturating_sub(size.cols as usize);
+    let window = samples.range(start..);
+    let (min, max) = window
+      .clone()
+      .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), sample| {
+        (min.min(*sample), max.max(*sample))
+      });
+    let top_level = self.levels.len() - 1;
+    window
+      .map(|sample| {
+        let level = if max > min {
+          ((sample - min) / (max - min) * top_level as f64).round() as usize
+        } else {
+          self.levels.len() / 2
+        };
+        self.levels[level]
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const SIZE: ConsoleSize = ConsoleSize { cols: 8, rows: 10 };
+
+  #[test]
+  fn renders_scaled_samples() {
+    let renderer = SparklineRenderer::new(10).with_unicode(true);
+    assert_eq!(renderer.render(&SIZE), "");
+    renderer.push_sample(5.0);
+    assert_eq!(renderer.render(&SIZE), "▅");
+    renderer.push_sample(f64::NAN);
+    renderer.push_sample(5.0);
+    assert_eq!(renderer.render(&SIZE), "▅▅");
+    for sample in [0.0, 10.0, 2.5, 7.5] {
+      renderer.push_sample(sample);
+    }
+    assert_eq!(renderer.render(&SIZE), "▅▅▁█▃▆");
+
+    // only the samples that fit are scaled
+    for sample in [20.0, 30.0, 40.0, 50.0] {
+      renderer.push_sample(sample);
+    }
+    assert_eq!(renderer.render(&ConsoleSize { cols: 4, rows: 10 }), "▁▃▆█");
+    // the oldest samples are discarded
+    assert_eq!(renderer.render(&SIZE).chars().count(), 8);
+    assert_eq!(
+      renderer
+        .render(&ConsoleSize { cols: 20, rows: 10 })
+        .chars()
+        .count(),
+      10
+    );
+
+    let renderer = SparklineRenderer::new(4).with_unicode(false);
+    for sample in [0.0, 1.0, 2.0, 7.0] {
+      renderer.push_sample(sample);
+    }
+    assert_eq!(renderer.render(&SIZE), "_.,#");
+  }
+}
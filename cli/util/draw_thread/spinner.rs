@@ -0,0 +1,127 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use deno_core::parking_lot::Mutex;
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::progress::SPINNER_CHARS;
+use super::text::truncate_to_width;
+use super::theme::Theme;
+use super::DrawThreadRenderer;
+
+/// The result a spinner resolves to once its operation is done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpinnerOutcome {
+  Success,
+  Failure,
+}
+
+/// Renders an animated spinner followed by a message.
+///
+/// Once an outcome is set, the spinner is replaced with a checkmark or
+/// a cross, which pairs with `DrawThreadGuard::linger` to briefly leave
+/// the result on the screen.
+#[derive(Debug)]
+pub struct SpinnerRenderer {
+  message: Mutex<String>,
+  outcome: Mutex<Option<SpinnerOutcome>>,
+  theme: Theme,
+  tick: AtomicUsize,
+}
+
+// driven by the callers of long running operations
+#[allow(dead_code)]
+impl SpinnerRenderer {
+  pub fn new(message: impl Into<String>, theme: Theme) -> Self {
+    Self {
+      message: Mutex::new(message.into()),
+      outcome: Default::default(),
+      theme,
+      tick: Default::default(),
+    }
+  }
+
+  pub fn set_message(&self, message: impl Into<String>) {
+    *self.message.lock() = message.into();
+  }
+
+  /// Stops the spinner and shows a checkmark in its place.
+  pub fn succeed(&self) {
+    *self.outcome.lock() = Some(SpinnerOutcome::Success);
+  }
+
+  /// Stops the spinner and shows a cross in its place.
+  pub fn fail(&self) {
+    *self.outcome.lock() = Some(SpinnerOutcome::Failure);
+  }
+
+  pub fn outcome(&self) -> Option<SpinnerOutcome> {
+    *self.outcome.lock()
+  }
+
+  fn render_with_tick(&self, size: &ConsoleSize, tick: usize) -> String {
+    let glyph = match self.outcome() {
+      Some(SpinnerOutcome::Success) => self.theme.success.paint("✓"),
+      Some(SpinnerOutcome::Failure) => self.theme.failure.paint("✗"),
+      None => self
+        .theme
+        .spinner
+        .paint(SPINNER_CHARS[tick % SPINNER_CHARS.len()]),
+    };
+    let text = format!(
+      "{} {}",
+      glyph,
+      self.theme.label.paint(&*self.message.lock())
+    );
+    truncate_to_width(&text, size.cols as usize)
+  }
+}
+
+impl DrawThreadRenderer for SpinnerRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    let tick = self.tick.fetch_add(1, Ordering::Relaxed);
+    self.render_with_tick(size, tick)
+  }
+
+  fn render_frame(&self, size: &ConsoleSize, frame: u64) -> String {
+    self.render_with_tick(size, frame as usize)
+  }
+
+  fn is_failed(&self) -> bool {
+    self.outcome() == Some(SpinnerOutcome::Failure)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn should_render_spinner() {
+    let renderer = SpinnerRenderer::new("Checking", Theme::plain());
+    let size = ConsoleSize { cols: 20, rows: 10 };
+    assert_eq!(renderer.render(&size), "⣷ Checking");
+    assert_eq!(renderer.render(&size), "⣯ Checking");
+    renderer.set_message("Checking file:///main.ts");
+    assert_eq!(renderer.render_with_tick(&size, 8), "⣷ Checking file:///m");
+  }
+
+  #[test]
+  fn should_render_outcome() {
+    let size = ConsoleSize { cols: 20, rows: 10 };
+    let renderer = SpinnerRenderer::new("Checking", Theme::plain());
+    renderer.succeed();
+    assert_eq!(renderer.render(&size), "✓ Checking");
+    assert_eq!(renderer.render(&size), "✓ Checking");
+    assert!(!renderer.is_failed());
+
+    let renderer = SpinnerRenderer::new("Checking", Theme::plain());
+    renderer.fail();
+    assert_eq!(renderer.render_with_tick(&size, 3), "✗ Checking");
+    assert!(renderer.is_failed());
+  }
+}
@@ -0,0 +1,163 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use deno_core::parking_lot::Mutex;
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::text::truncate_to_width;
+use super::DrawThreadRenderer;
+
+#[derive(Debug)]
+struct StatusMessage {
+  priority: u8,
+  /// Order the message was pushed in, so the most recent
+  /// message wins when priorities are equal.
+  seq: u64,
+  expires_at: Option<Instant>,
+  text: String,
+}
+
+impl StatusMessage {
+  fn is_expired(&self, now: Instant) -> bool {
+    self.expires_at.map(|e| e <= now).unwrap_or(false)
+  }
+}
+
+impl PartialEq for StatusMessage {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for StatusMessage {}
+
+impl PartialOrd for StatusMessage {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for StatusMessage {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self
+      .priority
+      .cmp(&other.priority)
+      .then_with(|| self.seq.cmp(&other.seq))
+  }
+}
+
+#[derive(Debug, Default)]
+struct StatusLineState {
+  next_seq: u64,
+  messages: BinaryHeap<StatusMessage>,
+}
+
+/// A single status line shared by multiple subsystems. Each subsystem
+/// pushes messages with a priority and an optional time to live, and
+/// the highest priority message that hasn't expired is displayed.
+#[derive(Debug, Clone, Default)]
+pub struct StatusLine {
+  state: Arc<Mutex<StatusLineState>>,
+}
+
+// written to by the callers that report a status
+#[allow(dead_code)]
+impl StatusLine {
+  /// Pushes a message to the status line. When priorities are equal,
+  /// the most recently pushed message is displayed.
+  pub fn push(
+    &self,
+    text: impl Into<String>,
+    priority: u8,
+    ttl: Option<Duration>,
+  ) {
+    let mut state = self.state.lock();
+    let seq = state.next_seq;
+    state.next_seq += 1;
+    state.messages.push(StatusMessage {
+      priority,
+      seq,
+      expires_at: ttl.map(|ttl| Instant::now() + ttl),
+      text: text.into(),
+    });
+  }
+
+  /// Removes all the messages.
+  pub fn clear(&self) {
+    self.state.lock().messages.clear();
+  }
+
+  fn render_at(&self, size: &ConsoleSize, now: Instant) -> String {
+    let mut state = self.state.lock();
+    state.messages.retain(|m| !m.is_expired(now));
+    match state.messages.peek() {
+      Some(message) => truncate_to_width(&message.text, size.cols as usize),
+      None => String::new(),
+    }
+  }
+}
+
+impl DrawThreadRenderer for StatusLine {
+  fn render(&self, size: &ConsoleSize) -> String {
+    self.render_at(size, Instant::now())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const SIZE: ConsoleSize = ConsoleSize { cols: 20, rows: 10 };
+
+  #[test]
+  fn displays_highest_priority_message() {
+    let status_line = StatusLine::default();
+    assert_eq!(status_line.render(&SIZE), "");
+    status_line.push("low", 1, None);
+    status_line.push("high", 5, None);
+    status_line.push("medium", 3, None);
+    assert_eq!(status_line.render(&SIZE), "high");
+    status_line.clear();
+    assert_eq!(status_line.render(&SIZE), "");
+  }
+
+  #[test]
+  fn most_recent_message_wins_ties() {
+    let status_line = StatusLine::default();
+    status_line.push("first", 1, None);
+    status_line.push("second", 1, None);
+    assert_eq!(status_line.render(&SIZE), "second");
+    status_line.push("third", 1, None);
+    assert_eq!(status_line.render(&SIZE), "third");
+  }
+
+  #[test]
+  fn prunes_expired_messages() {
+    let status_line = StatusLine::default();
+    let now = Instant::now();
+    status_line.push("persistent", 1, None);
+    status_line.push("short", 3, Some(Duration::from_secs(1)));
+    status_line.push("long", 2, Some(Duration::from_secs(10)));
+    assert_eq!(status_line.render_at(&SIZE, now), "short");
+    let now = now + Duration::from_secs(2);
+    assert_eq!(status_line.render_at(&SIZE, now), "long");
+    assert_eq!(status_line.state.lock().messages.len(), 2);
+    let now = now + Duration::from_secs(10);
+    assert_eq!(status_line.render_at(&SIZE, now), "persistent");
+    assert_eq!(status_line.state.lock().messages.len(), 1);
+  }
+
+  #[test]
+  fn truncates_message() {
+    let status_line = StatusLine::default();
+    status_line.push("a very long status message", 1, None);
+    assert_eq!(status_line.render(&SIZE), "a very long status m");
+  }
+}
@@ -0,0 +1,126 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::context::RenderContext;
+use super::text::truncate_to_width;
+use super::DrawThreadRenderer;
+
+/// What an entry contributes to the summary, which is provided via
+/// `DrawThreadRenderer::summary_contribution`.
+// returned by the renderers that contribute to a summary
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryPart {
+  Running,
+  Done,
+  Failed,
+}
+
+/// The number of the live entries in each state, which the draw thread
+/// aggregates every frame and provides to the renderers as a value of
+/// the `RenderContext`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SummaryCounts {
+  pub running: usize,
+  pub done: usize,
+  pub failed: usize,
+}
+
+impl SummaryCounts {
+  pub fn add(&mut self, part: SummaryPart) {
+    match part {
+      SummaryPart::Running => self.running += 1,
+      SummaryPart::Done => self.done += 1,
+      SummaryPart::Failed => self.failed += 1,
+    }
+  }
+
+  /// Gets the number of entries that contributed to the summary.
+  pub fn total(&self) -> usize {
+    self.running + self.done + self.failed
+  }
+}
+
+type FormatSummary = dyn Fn(&SummaryCounts) -> String + Send + Sync;
+
+/// Renders a line, such as "compiling 3/10 crates", from the summary
+/// contributions of the other entries, so it reflects the live set of
+/// entries without bookkeeping. Add it with
+/// `DrawThread::add_entry_at_rows` to keep it on the first row.
+pub struct SummaryRenderer {
+  format: Box<FormatSummary>,
+}
+
+impl std::fmt::Debug for SummaryRenderer {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("SummaryRenderer").finish_non_exhaustive()
+  }
+}
+
+// constructed by the embedders that draw a summary line
+#[allow(dead_code)]
+impl SummaryRenderer {
+  pub fn new(
+    format: impl Fn(&SummaryCounts) -> String + Send + Sync + 'static,
+  ) -> Self {
+    Self {
+      format: Box::new(format),
+    }
+  }
+}
+
+impl DrawThreadRenderer for SummaryRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    self.render_with_context(size, 0, &RenderContext::default())
+  }
+
+  fn render_with_context(
+    &self,
+    size: &ConsoleSize,
+    _frame: u64,
+    context: &RenderContext,
+  ) -> String {
+    let counts = context.get::<SummaryCounts>().copied().unwrap_or_default();
+    truncate_to_width(&(self.format)(&counts), size.cols as usize)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::sync::Arc;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn renders_summary_counts() {
+    let renderer = SummaryRenderer::new(|counts| {
+      format!(
+        "compiling {}/{} crates, {} failed",
+        counts.done,
+        counts.total(),
+        counts.failed
+      )
+    });
+    let size = ConsoleSize { cols: 40, rows: 10 };
+    assert_eq!(renderer.render(&size), "compiling 0/0 crates, 0 failed");
+
+    let mut counts = SummaryCounts::default();
+    for part in [
+      SummaryPart::Done,
+      SummaryPart::Running,
+      SummaryPart::Failed,
+      SummaryPart::Done,
+    ] {
+      counts.add(part);
+    }
+    let mut context = RenderContext::default();
+    context.set(Arc::new(counts));
+    assert_eq!(
+      renderer.render_with_context(&size, 0, &context),
+      "compiling 2/4 crates, 1 failed"
+    );
+  }
+}
@@ -0,0 +1,112 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::collections::VecDeque;
+
+use deno_core::parking_lot::Mutex;
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::text::truncate_to_width;
+use super::DrawThreadRenderer;
+
+/// Renders the most recent lines of a log, pinned in the draw
+/// thread's region. Add it to the draw thread once, then push
+/// lines to it as they occur.
+#[derive(Debug)]
+pub struct TailRenderer {
+  lines: Mutex<VecDeque<String>>,
+  capacity: usize,
+  max_visible_lines: usize,
+}
+
+// fed by the callers that show the last lines of a log
+#[allow(dead_code)]
+impl TailRenderer {
+  /// Creates a renderer that keeps at most `capacity` lines and
+  /// displays at most `max_visible_lines` of the most recent ones.
+  pub fn new(capacity: usize, max_visible_lines: usize) -> Self {
+    Self {
+      lines: Mutex::new(VecDeque::with_capacity(capacity)),
+      capacity,
+      max_visible_lines,
+    }
+  }
+
+  /// Adds a line to the log, evicting the oldest line when at capacity.
+  /// Text containing newlines is added as multiple lines.
+  pub fn push_line(&self, text: impl AsRef<str>) {
+    if self.capacity == 0 {
+      return;
+    }
+    let mut lines = self.lines.lock();
+    for line in text.as_ref().lines() {
+      if lines.len() == self.capacity {
+        lines.pop_front();
+      }
+      lines.push_back(line.to_string());
+    }
+  }
+
+  /// Removes all the lines from the log.
+  pub fn clear(&self) {
+    self.lines.lock().clear();
+  }
+}
+
+impl DrawThreadRenderer for TailRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    let lines = self.lines.lock();
+    let visible_count = self
+      .max_visible_lines
+      .min(size.rows as usize)
+      .min(lines.len());
+    lines
+      .iter()
+      .skip(lines.len() - visible_count)
+      .map(|line| truncate_to_width(line, size.cols as usize))
+      .collect::<Vec<_>>()
+      .join("\n")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const SIZE: ConsoleSize = ConsoleSize { cols: 10, rows: 20 };
+
+  #[test]
+  fn renders_most_recent_visible_lines() {
+    let renderer = TailRenderer::new(10, 2);
+    assert_eq!(renderer.render(&SIZE), "");
+    renderer.push_line("one");
+    assert_eq!(renderer.render(&SIZE), "one");
+    renderer.push_line("two\nthree");
+    assert_eq!(renderer.render(&SIZE), "two\nthree");
+    // limited by the terminal height
+    assert_eq!(renderer.render(&ConsoleSize { cols: 10, rows: 1 }), "three");
+    renderer.clear();
+    assert_eq!(renderer.render(&SIZE), "");
+  }
+
+  #[test]
+  fn truncates_long_lines() {
+    let renderer = TailRenderer::new(10, 5);
+    renderer.push_line("this line is too long");
+    assert_eq!(renderer.render(&SIZE), "this line ");
+  }
+
+  #[test]
+  fn evicts_oldest_lines_at_capacity() {
+    let renderer = TailRenderer::new(2, 5);
+    renderer.push_line("one");
+    renderer.push_line("two");
+    renderer.push_line("three");
+    assert_eq!(renderer.render(&SIZE), "two\nthree");
+
+    let renderer = TailRenderer::new(0, 5);
+    renderer.push_line("one");
+    assert_eq!(renderer.render(&SIZE), "");
+  }
+}
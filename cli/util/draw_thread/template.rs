@@ -0,0 +1,485 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::context::RenderContext;
+use super::progress::format_duration;
+use super::progress::format_speed;
+use super::progress::DurationFormat;
+use super::progress::ProgressState;
+use super::progress::ProgressStatus;
+use super::progress::SPINNER_CHARS;
+use super::text::display_width;
+use super::text::truncate_to_width;
+use super::theme::Theme;
+use super::DrawThreadRenderer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+  Spinner,
+  Bar,
+  Percent,
+  Pos,
+  Len,
+  Msg,
+  Elapsed,
+  Eta,
+  Rate,
+}
+
+impl Placeholder {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "spinner" => Some(Self::Spinner),
+      "bar" => Some(Self::Bar),
+      "percent" => Some(Self::Percent),
+      "pos" => Some(Self::Pos),
+      "len" => Some(Self::Len),
+      "msg" => Some(Self::Msg),
+      "elapsed" => Some(Self::Elapsed),
+      "eta" => Some(Self::Eta),
+      "rate" => Some(Self::Rate),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+  Literal(String),
+  Placeholder(Placeholder),
+}
+
+/// Parses a template where `{name}` is a placeholder and `{{` or `}}`
+/// are literal braces. Unknown placeholders and unclosed braces are
+/// kept as literal text.
+fn parse_template(template: &str) -> Vec<TemplatePart> {
+  fn push_literal(parts: &mut Vec<TemplatePart>, text: &str) {
+    if let Some(TemplatePart::Literal(literal)) = parts.last_mut() {
+      literal.push_str(text);
+    } else {
+      parts.push(TemplatePart::Literal(text.to_string()));
+    }
+  }
+
+  let mut parts = Vec::new();
+  let mut remaining = template;
+  while let Some(index) = remaining.find(['{', '}']) {
+    push_literal(&mut parts, &remaining[..index]);
+    let rest = &remaining[index..];
+    if rest.starts_with("{{") || rest.starts_with("}}") {
+      push_literal(&mut parts, &rest[..1]);
+      remaining = &rest[2..];
+    } else if let Some(after) = rest.strip_prefix('}') {
+      push_literal(&mut parts, "}");
+      remaining = after;
+    } else {
+      match rest.find('}') {
+        Some(end) => {
+          let name = &rest[1..end];
+          match Placeholder::from_name(name) {
+            Some(placeholder) => {
+              parts.push(TemplatePart::Placeholder(placeholder))
+            }
+            None => push_literal(&mut parts, &rest[..end + 1]),
+          }
+          remaining = &rest[end + 1..];
+        }
+        None => {
+          push_literal(&mut parts, rest);
+          remaining = "";
+        }
+      }
+    }
+  }
+  push_literal(&mut parts, remaining);
+  parts.retain(
+    |part| !matches!(part, TemplatePart::Literal(text) if text.is_empty()),
+  );
+  parts
+}
+
+/// Renders a progress state based on a template string such
+/// as `"{spinner} {msg} [{bar}] {percent}%"`.
+///
+/// Supported placeholders are `{spinner}`, `{bar}`, `{percent}`, `{pos}`,
+/// `{len}`, `{msg}`, `{elapsed}`, `{eta}`, and `{rate}`. The `{bar}`
+/// placeholder fills the width left over by the rest of the line. Use
+/// `{{` and `}}` for literal braces.
+///
+/// The `{rate}` placeholder shows the current bytes per second over the
+/// state's rate window rather than the average so far, or `--` until
+/// it's known.
+///
+/// Once the state is completed or failed, the `{spinner}` placeholder
+/// shows a checkmark or a cross and the `{bar}` placeholder is drawn
+/// in the success or failure color of the theme.
+///
+/// While the length is unknown, the `{bar}` placeholder shows a bouncing
+/// block. The length may be set late or revised, where the bar doesn't
+/// move backwards when the length grows and instead waits for the
+/// position to catch up.
+#[derive(Debug)]
+pub struct TemplateRenderer {
+  parts: Vec<TemplatePart>,
+  state: Arc<ProgressState>,
+  theme: Theme,
+  tick: AtomicUsize,
+  /// The bits of the largest fraction shown so far.
+  shown_fraction: AtomicU64,
+}
+
+impl TemplateRenderer {
+  pub fn new(template: &str, state: Arc<ProgressState>) -> Self {
+    Self {
+      parts: parse_template(template),
+      state,
+      theme: Theme::plain(),
+      tick: Default::default(),
+      shown_fraction: AtomicU64::new(0f64.to_bits()),
+    }
+  }
+
+  /// Sets the colors to render with. Defaults to `Theme::plain()`.
+  // the themes are picked by the embedders
+  #[allow(dead_code)]
+  pub fn with_theme(mut self, theme: Theme) -> Self {
+    self.theme = theme;
+    self
+  }
+
+  /// Gets the fraction to show, which doesn't go backwards
+  /// when the length grows.
+  fn shown_fraction(&self) -> Option<f64> {
+    let fraction = self.state.fraction()?;
+    let shown_fraction = f64::from_bits(
+      self
+        .shown_fraction
+        .fetch_max(fraction.to_bits(), Ordering::Relaxed),
+    )
+    .max(fraction);
+    Some(shown_fraction)
+  }
+
+  fn render_with_tick(&self, size: &ConsoleSize, tick: usize) -> String {
+    self.render_with_format(size, tick, DurationFormat::Colon)
+  }
+
+  fn render_with_format(
+    &self,
+    size: &ConsoleSize,
+    tick: usize,
+    duration_format: DurationFormat,
+  ) -> String {
+    let state = &*self.state;
+    let status = state.status();
+    let fraction = self.shown_fraction();
+    let length = state.length();
+    let position = if length == 0 {
+      state.position()
+    } else {
+      state.position().min(length)
+    };
+    let texts = self
+      .parts
+      .iter()
+      .map(|part| match part {
+        TemplatePart::Literal(text) => Some(text.clone()),
+        TemplatePart::Placeholder(placeholder) => match placeholder {
+          Placeholder::Bar => None,
+          Placeholder::Spinner => Some(match status {
+            ProgressStatus::InProgress => self
+              .theme
+              .spinner
+              .paint(SPINNER_CHARS[tick % SPINNER_CHARS.len()]),
+            ProgressStatus::Completed => self.theme.success.paint("✓"),
+            ProgressStatus::Failed => self.theme.failure.paint("✗"),
+          }),
+          Placeholder::Percent => Some(
+            ((fraction.unwrap_or(0f64) * 100f64).floor() as u64).to_string(),
+          ),
+          Placeholder::Pos => Some(position.to_string()),
+          Placeholder::Len => Some(length.to_string()),
+          Placeholder::Msg => Some(state.message()),
+          Placeholder::Elapsed => {
+            Some(format_duration(state.elapsed(), duration_format))
+          }
+          Placeholder::Eta => Some(match state.eta() {
+            Some(eta) => format_duration(eta, duration_format),
+            None if duration_format == DurationFormat::Colon => {
+              "--:--".to_string()
+            }
+            None => "--".to_string(),
+          }),
+          Placeholder::Rate => Some(match state.sample_rate() {
+            Some(rate) => format_speed(rate),
+            None => "--".to_string(),
+          }),
+        },
+      })
+      .collect::<Vec<_>>();
+
+    let bar_count = texts.iter().filter(|text| text.is_none()).count();
+    let bar_width = if bar_count == 0 {
+      0
+    } else {
+      let used_width = texts.iter().flatten().map(|t| display_width(t)).sum();
+      (size.cols as usize).saturating_sub(used_width) / bar_count
+    };
+    let mut text = String::new();
+    for part in texts {
+      match part {
+        Some(part) => text.push_str(&part),
+        None => {
+          text.push_str(&self.render_bar(status, fraction, bar_width, tick))
+        }
+      }
+    }
+    truncate_to_width(&text, size.cols as usize)
+  }
+
+  fn render_bar(
+    &self,
+    status: ProgressStatus,
+    fraction: Option<f64>,
+    width: usize,
+    tick: usize,
+  ) -> String {
+    const BOUNCING_BLOCK: &str = "<=>";
+
+    let Some(fraction) = fraction else {
+      if status == ProgressStatus::InProgress && width >= BOUNCING_BLOCK.len() {
+        let travel = width - BOUNCING_BLOCK.len();
+        let offset = if travel == 0 {
+          0
+        } else {
+          let offset = tick % (travel * 2);
+          offset.min(travel * 2 - offset)
+        };
+        return format!(
+          "{}{}{}",
+          self.theme.bar_empty.paint("-".repeat(offset)),
+          self.theme.bar_filled.paint(BOUNCING_BLOCK),
+          self.theme.bar_empty.paint("-".repeat(travel - offset))
+        );
+      }
+      return self.render_bar(status, Some(0f64), width, tick);
+    };
+    let filled = ((width as f64 * fraction).floor() as usize).min(width);
+    let filled_text = "#".repeat(filled);
+    let empty_text = "-".repeat(width - filled);
+    match status {
+      ProgressStatus::InProgress => format!(
+        "{}{}",
+        self.theme.bar_filled.paint(filled_text),
+        self.theme.bar_empty.paint(empty_text)
+      ),
+      ProgressStatus::Completed => self.theme.success.paint(filled_text),
+      ProgressStatus::Failed => self
+        .theme
+        .failure
+        .paint(format!("{}{}", filled_text, empty_text)),
+    }
+  }
+}
+
+impl DrawThreadRenderer for TemplateRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    let tick = self.tick.fetch_add(1, Ordering::Relaxed);
+    self.render_with_tick(size, tick)
+  }
+
+  fn render_frame(&self, size: &ConsoleSize, frame: u64) -> String {
+    self.render_with_tick(size, frame as usize)
+  }
+
+  fn render_with_context(
+    &self,
+    size: &ConsoleSize,
+    frame: u64,
+    context: &RenderContext,
+  ) -> String {
+    let duration_format =
+      context.duration_format().unwrap_or(DurationFormat::Colon);
+    self.render_with_format(size, frame as usize, duration_format)
+  }
+
+  fn progress(&self) -> Option<f64> {
+    self.state.fraction()
+  }
+
+  fn is_failed(&self) -> bool {
+    self.state.status() == ProgressStatus::Failed
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use test_util::strip_ansi_codes;
+
+  use super::*;
+  use crate::util::draw_thread::theme::ThemeColor;
+
+  fn literal(text: &str) -> TemplatePart {
+    TemplatePart::Literal(text.to_string())
+  }
+
+  #[test]
+  fn should_parse_template() {
+    assert_eq!(parse_template(""), vec![]);
+    assert_eq!(
+      parse_template("{spinner} {msg} [{bar}] {percent}%"),
+      vec![
+        TemplatePart::Placeholder(Placeholder::Spinner),
+        literal(" "),
+        TemplatePart::Placeholder(Placeholder::Msg),
+        literal(" ["),
+        TemplatePart::Placeholder(Placeholder::Bar),
+        literal("] "),
+        TemplatePart::Placeholder(Placeholder::Percent),
+        literal("%"),
+      ]
+    );
+    assert_eq!(
+      parse_template("{{pos}} {pos}/{len}"),
+      vec![
+        literal("{pos} "),
+        TemplatePart::Placeholder(Placeholder::Pos),
+        literal("/"),
+        TemplatePart::Placeholder(Placeholder::Len),
+      ]
+    );
+  }
+
+  #[test]
+  fn should_keep_unknown_placeholders_literal() {
+    assert_eq!(
+      parse_template("{unknown} {msg} {} {msg"),
+      vec![
+        literal("{unknown} "),
+        TemplatePart::Placeholder(Placeholder::Msg),
+        literal(" {} {msg"),
+      ]
+    );
+    assert_eq!(parse_template("a } b"), vec![literal("a } b")]);
+  }
+
+  #[test]
+  fn should_render_template() {
+    let state = Arc::new(ProgressState::new(10, "data"));
+    let renderer = TemplateRenderer::new(
+      "{spinner} {msg} [{bar}] {pos}/{len} {percent}% {unknown}",
+      state.clone(),
+    );
+    let size = ConsoleSize { cols: 40, rows: 10 };
+    assert_eq!(
+      renderer.render_with_tick(&size, 1),
+      "⣯ data [-------------] 0/10 0% {unknown}",
+    );
+    state.set_position(5);
+    assert_eq!(
+      renderer.render_with_tick(&size, 2),
+      "⣟ data [######------] 5/10 50% {unknown}",
+    );
+    // no room for the bar
+    let size = ConsoleSize { cols: 20, rows: 10 };
+    assert_eq!(renderer.render_with_tick(&size, 0), "⣷ data [] 5/10 50% {");
+
+    let renderer = TemplateRenderer::new("{pos} at {rate}", state);
+    assert_eq!(renderer.render_with_tick(&size, 0), "5 at --");
+  }
+
+  #[test]
+  fn should_render_unknown_and_changing_lengths() {
+    let state = Arc::new(ProgressState::new(0, ""));
+    let renderer =
+      TemplateRenderer::new("[{bar}] {pos}/{len} {percent}%", state.clone());
+    let size = ConsoleSize { cols: 20, rows: 10 };
+    let render = |tick| renderer.render_with_tick(&size, tick);
+    // bounces while the length is unknown
+    state.set_position(3);
+    assert_eq!(render(0), "[<=>--------] 3/0 0%");
+    assert_eq!(render(2), "[--<=>------] 3/0 0%");
+    assert_eq!(render(8), "[--------<=>] 3/0 0%");
+    assert_eq!(render(10), "[------<=>--] 3/0 0%");
+    assert_eq!(render(16), "[<=>--------] 3/0 0%");
+
+    // set late
+    state.set_length(6);
+    assert_eq!(render(0), "[#####-----] 3/6 50%");
+    // grows, which doesn't move the bar backwards
+    state.set_length(12);
+    assert_eq!(render(0), "[####-----] 3/12 50%");
+    state.set_position(9);
+    assert_eq!(render(0), "[######---] 9/12 75%");
+    // shrinks below the position
+    state.set_length(4);
+    assert_eq!(render(0), "[#########] 4/4 100%");
+
+    // no room for the bar
+    let state = Arc::new(ProgressState::new(0, ""));
+    let renderer = TemplateRenderer::new("[{bar}]", state);
+    let size = ConsoleSize { cols: 3, rows: 10 };
+    assert_eq!(renderer.render_with_tick(&size, 5), "[-]");
+    let size = ConsoleSize { cols: 0, rows: 10 };
+    assert_eq!(renderer.render_with_tick(&size, 5), "");
+  }
+
+  #[test]
+  fn should_render_completed_and_failed() {
+    let state = Arc::new(ProgressState::new(10, "data"));
+    let theme = Theme {
+      bar_filled: ThemeColor::Rgb(0, 0, 1),
+      bar_empty: ThemeColor::Rgb(0, 0, 2),
+      spinner: ThemeColor::None,
+      success: ThemeColor::Rgb(0, 0, 3),
+      failure: ThemeColor::Rgb(0, 0, 4),
+      ..Theme::plain()
+    };
+    let renderer =
+      TemplateRenderer::new("{spinner} {msg} [{bar}]", state.clone())
+        .with_theme(theme);
+    let size = ConsoleSize { cols: 20, rows: 10 };
+    let render = || renderer.render_with_tick(&size, 0);
+
+    state.set_position(6);
+    assert_eq!(strip_ansi_codes(&render()), "⣷ data [######-----]",);
+    assert_eq!(
+      render(),
+      format!(
+        "⣷ data [{}{}]",
+        theme.bar_filled.paint("######"),
+        theme.bar_empty.paint("-----")
+      ),
+    );
+
+    state.set_position(10);
+    assert_eq!(strip_ansi_codes(&render()), "✓ data [###########]");
+    assert_eq!(
+      render(),
+      format!(
+        "{} data [{}]",
+        theme.success.paint("✓"),
+        theme.success.paint("###########")
+      ),
+    );
+
+    state.fail();
+    assert_eq!(strip_ansi_codes(&render()), "✗ data [###########]");
+    assert_eq!(
+      render(),
+      format!(
+        "{} data [{}]",
+        theme.failure.paint("✗"),
+        theme.failure.paint("###########")
+      ),
+    );
+  }
+}
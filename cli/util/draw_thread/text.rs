@@ -0,0 +1,511 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use console_static_text::ansi::strip_ansi_codes;
+use console_static_text::ansi::tokenize;
+use deno_terminal::colors;
+use unicode_width::UnicodeWidthChar;
+use unicode_width::UnicodeWidthStr;
+
+/// Gets the number of columns the text occupies in the terminal,
+/// ignoring any ansi escape sequences.
+pub fn display_width(text: &str) -> usize {
+  UnicodeWidthStr::width(strip_ansi_codes(text).as_ref())
+}
+
+static HYPERLINKS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Sets whether `hyperlink` emits links, such as for terminals that
+/// output the escape sequences literally. Defaults to `true`.
+// for the embedders whose terminal prints links literally
+#[allow(dead_code)]
+pub fn set_hyperlinks_enabled(value: bool) {
+  HYPERLINKS_ENABLED.store(value, Ordering::Relaxed);
+}
+
+/// Wraps the text in an OSC 8 hyperlink to the url, which the terminal
+/// makes clickable. Only the text is returned when hyperlinks are
+/// disabled or colors aren't used, such as via `NO_COLOR`.
+// for the renderers that link to files
+#[allow(dead_code)]
+pub fn hyperlink(text: &str, url: &str) -> String {
+  if HYPERLINKS_ENABLED.load(Ordering::Relaxed) && colors::use_color() {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+  } else {
+    text.to_string()
+  }
+}
+
+/// Truncates the text so that it occupies at most `cols` columns.
+///
+/// Ansi escape sequences are kept, including ones after the cut-off
+/// point, so that styles are still reset when the text is truncated.
+pub fn truncate_to_width(text: &str, cols: usize) -> String {
+  if display_width(text) <= cols {
+    return text.to_string();
+  }
+  let mut result = String::with_capacity(text.len());
+  let mut width = 0;
+  // only the escape sequences are kept after the cut-off point
+  let mut truncated = false;
+  for token in tokenize(text) {
+    let token_text = &text[token.range];
+    if token.is_escape {
+      result.push_str(token_text);
+      continue;
+    }
+    if truncated {
+      continue;
+    }
+    for (_, cluster) in cluster_indices(token_text) {
+      let cluster_width = UnicodeWidthStr::width(cluster);
+      if width + cluster_width > cols {
+        truncated = true;
+        break;
+      }
+      width += cluster_width;
+      result.push_str(cluster);
+    }
+  }
+  result
+}
+
+/// Where the text is cut off and replaced with an ellipsis.
+// picked via `WrapMode::Ellipsis`
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Ellipsis {
+  /// Keeps the end, such as for hashes.
+  Start,
+  /// Keeps the start and the end, such as for paths.
+  Middle,
+  /// Keeps the start, such as for messages.
+  #[default]
+  End,
+}
+
+const ELLIPSIS: &str = "…";
+
+/// Truncates the text so it occupies at most `cols` columns, replacing
+/// the removed characters with an ellipsis at the provided placement.
+/// Like `truncate_to_width`, the characters that are displayed together
+/// aren't split and the escape sequences are kept.
+pub fn truncate_with_ellipsis(
+  text: &str,
+  cols: usize,
+  placement: Ellipsis,
+) -> String {
+  if display_width(text) <= cols {
+    return text.to_string();
+  }
+  if cols == 0 {
+    return truncate_to_width(text, 0);
+  }
+
+  /// Gets the width of the clusters that fit in the columns.
+  fn fitting_width<'a>(
+    widths: impl Iterator<Item = &'a Option<usize>>,
+    cols: usize,
+  ) -> usize {
+    let mut width = 0;
+    for cluster_width in widths.flatten() {
+      if width + cluster_width > cols {
+        break;
+      }
+      width += cluster_width;
+    }
+    width
+  }
+
+  // the escape sequences don't have a width and are always kept
+  let mut clusters = Vec::new();
+  for token in tokenize(text) {
+    let token_text = &text[token.range];
+    if token.is_escape {
+      clusters.push((token_text, None));
+    } else {
+      clusters
+        .extend(cluster_indices(token_text).map(|(_, cluster)| {
+          (cluster, Some(UnicodeWidthStr::width(cluster)))
+        }));
+    }
+  }
+  let widths = clusters.iter().map(|(_, width)| width);
+  let available_cols = cols - display_width(ELLIPSIS);
+  let head_width = match placement {
+    Ellipsis::Start => 0,
+    Ellipsis::Middle => {
+      fitting_width(widths.clone(), available_cols.div_ceil(2))
+    }
+    Ellipsis::End => fitting_width(widths.clone(), available_cols),
+  };
+  let tail_width = match placement {
+    Ellipsis::Start | Ellipsis::Middle => {
+      fitting_width(widths.rev(), available_cols - head_width)
+    }
+    Ellipsis::End => 0,
+  };
+  let total_width = display_width(text);
+
+  let mut result = String::with_capacity(text.len());
+  let mut width = 0;
+  let mut has_ellipsis = false;
+  for (cluster, cluster_width) in clusters {
+    let Some(cluster_width) = cluster_width else {
+      result.push_str(cluster);
+      continue;
+    };
+    let is_kept =
+      width + cluster_width <= head_width || width >= total_width - tail_width;
+    if is_kept {
+      result.push_str(cluster);
+    } else if !has_ellipsis {
+      result.push_str(ELLIPSIS);
+      has_ellipsis = true;
+    }
+    width += cluster_width;
+  }
+  result
+}
+
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+
+/// Splits the text into the characters that are displayed together along
+/// with their byte index. Zero width characters, such as variation
+/// selectors and combining marks, stay with the preceding character, as
+/// does the character following a zero width joiner. The clusters are
+/// measured as a whole because a variation selector may widen the
+/// preceding character, such as for "❤️".
+fn cluster_indices(text: &str) -> impl Iterator<Item = (usize, &str)> {
+  let mut chars = text.char_indices().peekable();
+  std::iter::from_fn(move || {
+    let (start, c) = chars.next()?;
+    let mut end = start + c.len_utf8();
+    let mut joins_next = c == ZERO_WIDTH_JOINER;
+    while let Some(&(index, next)) = chars.peek() {
+      let is_zero_width = UnicodeWidthChar::width(next).unwrap_or(0) == 0;
+      if !is_zero_width && !joins_next {
+        break;
+      }
+      joins_next = next == ZERO_WIDTH_JOINER;
+      end = index + next.len_utf8();
+      chars.next();
+    }
+    Some((start, &text[start..end]))
+  })
+}
+
+/// The direction text is read in, which can be set on the
+/// `RenderContext` as a hint for the renderers.
+// set in the render context for right to left locales
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextDirection {
+  #[default]
+  Ltr,
+  /// Right to left, such as for Arabic or Hebrew.
+  Rtl,
+}
+
+/// Pads the text so that it occupies `cols` columns, truncating it when
+/// it's wider. The padding is added after the text in the reading
+/// direction, so it's on the left for right to left text.
+// for the renderers that align text in a column
+#[allow(dead_code)]
+pub fn justify(text: &str, cols: usize, direction: TextDirection) -> String {
+  let text = truncate_to_width(text, cols);
+  let padding = " ".repeat(cols - display_width(&text));
+  match direction {
+    TextDirection::Ltr => format!("{}{}", text, padding),
+    TextDirection::Rtl => format!("{}{}", padding, text),
+  }
+}
+
+/// Splits the text at the point where it occupies `cols` columns,
+/// keeping at least one character in the first part. Escape sequences
+/// stay with the character that follows them.
+fn split_at_width(text: &str, cols: usize) -> (String, String) {
+  let mut head = String::with_capacity(text.len());
+  let mut width = 0;
+  for token in tokenize(text) {
+    if token.is_escape {
+      head.push_str(&text[token.range]);
+      continue;
+    }
+    let token_start = token.range.start;
+    for (index, cluster) in cluster_indices(&text[token.range]) {
+      let cluster_width = UnicodeWidthStr::width(cluster);
+      if width + cluster_width > cols && width > 0 {
+        return (head, text[token_start + index..].to_string());
+      }
+      width += cluster_width;
+      head.push_str(cluster);
+    }
+  }
+  (head, String::new())
+}
+
+/// Breaks each line of the text into rows of at most `cols` columns at
+/// the column boundary, the way the terminal wraps them.
+pub fn break_at_width(text: &str, cols: usize) -> String {
+  if cols == 0 {
+    return text.to_string();
+  }
+  let mut rows = Vec::new();
+  for line in text.split('\n') {
+    let mut line = line.to_string();
+    while display_width(&line) > cols {
+      let (head, tail) = split_at_width(&line, cols);
+      rows.push(head);
+      line = tail;
+    }
+    rows.push(line);
+  }
+  rows.join("\n")
+}
+
+/// Wraps each line of the text at word boundaries so that the rows
+/// occupy at most `cols` columns. Words that are wider than `cols` are
+/// broken across rows.
+pub fn wrap_to_width(text: &str, cols: usize) -> String {
+  if cols == 0 {
+    return text.to_string();
+  }
+  let mut rows = Vec::new();
+  for line in text.split('\n') {
+    let mut row = String::new();
+    let mut row_width = 0;
+    let mut is_row_empty = true;
+    for word in line.split(' ') {
+      let word_width = display_width(word);
+      if !is_row_empty {
+        if row_width + 1 + word_width <= cols {
+          row.push(' ');
+          row.push_str(word);
+          row_width += 1 + word_width;
+          continue;
+        }
+        rows.push(std::mem::take(&mut row));
+      }
+      let mut word = word.to_string();
+      let mut word_width = word_width;
+      while word_width > cols {
+        let (head, tail) = split_at_width(&word, cols);
+        rows.push(head);
+        word = tail;
+        word_width = display_width(&word);
+      }
+      row = word;
+      row_width = word_width;
+      is_row_empty = false;
+    }
+    rows.push(row);
+  }
+  rows.join("\n")
+}
+
+/// Aligns the tab separated cells of the lines across all the texts into
+/// columns, padding each cell to the widest cell of its column. Lines
+/// without a tab are left as-is and lines may have differing numbers
+/// of cells.
+pub fn align_columns(texts: &mut [String]) {
+  const COLUMN_GAP: &str = "  ";
+
+  let mut widths: Vec<usize> = Vec::new();
+  for line in texts.iter().flat_map(|t| t.lines()) {
+    if !line.contains('\t') {
+      continue;
+    }
+    for (i, cell) in line.split('\t').enumerate() {
+      let width = display_width(cell);
+      match widths.get_mut(i) {
+        Some(max_width) => *max_width = (*max_width).max(width),
+        None => widths.push(width),
+      }
+    }
+  }
+  if widths.is_empty() {
+    return;
+  }
+
+  for text in texts.iter_mut() {
+    if !text.contains('\t') {
+      continue;
+    }
+    let mut aligned = String::with_capacity(text.len());
+    for (line_index, line) in text.split('\n').enumerate() {
+      if line_index > 0 {
+        aligned.push('\n');
+      }
+      let cells = line.split('\t').collect::<Vec<_>>();
+      let last_index = cells.len() - 1;
+      for (i, cell) in cells.into_iter().enumerate() {
+        aligned.push_str(cell);
+        if i < last_index {
+          let padding = widths[i] - display_width(cell);
+          aligned.push_str(&" ".repeat(padding));
+          aligned.push_str(COLUMN_GAP);
+        }
+      }
+    }
+    *text = aligned;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn should_get_display_width() {
+    assert_eq!(display_width(""), 0);
+    assert_eq!(display_width("hello"), 5);
+    assert_eq!(display_width("\x1b[32mhello\x1b[0m"), 5);
+    assert_eq!(display_width("日本"), 4);
+    // hyperlinks terminated by either ST or BEL
+    assert_eq!(
+      display_width("\x1b]8;;https://deno.land\x1b\\deno\x1b]8;;\x1b\\"),
+      4
+    );
+    assert_eq!(
+      display_width("\x1b]8;;https://deno.land\x07deno\x1b]8;;\x07"),
+      4
+    );
+  }
+
+  #[test]
+  fn should_create_hyperlink() {
+    let link = hyperlink("main.ts", "file:///main.ts");
+    if colors::use_color() {
+      assert_eq!(link, "\x1b]8;;file:///main.ts\x1b\\main.ts\x1b]8;;\x1b\\");
+    }
+    assert_eq!(display_width(&link), 7);
+    // keeps the sequence that ends the link
+    assert_eq!(
+      truncate_to_width(&link, 4),
+      hyperlink("main", "file:///main.ts")
+    );
+    set_hyperlinks_enabled(false);
+    assert_eq!(hyperlink("main.ts", "file:///main.ts"), "main.ts");
+    set_hyperlinks_enabled(true);
+  }
+
+  #[test]
+  fn should_justify() {
+    assert_eq!(justify("data", 6, TextDirection::Ltr), "data  ");
+    assert_eq!(justify("data", 2, TextDirection::Ltr), "da");
+    // "download" in Hebrew
+    assert_eq!(justify("הורדה", 8, TextDirection::Rtl), "   הורדה");
+    // combining characters don't occupy a column
+    assert_eq!(display_width("e\u{301}"), 1);
+    assert_eq!(
+      justify("cafe\u{301}", 6, TextDirection::Rtl),
+      "  cafe\u{301}"
+    );
+  }
+
+  #[test]
+  fn should_truncate_to_width() {
+    assert_eq!(truncate_to_width("hello", 10), "hello");
+    assert_eq!(truncate_to_width("hello", 3), "hel");
+    assert_eq!(truncate_to_width("hello", 0), "");
+    // doesn't split a wide character
+    assert_eq!(truncate_to_width("日本語", 5), "日本");
+    // or a character from its variation selector or joined characters
+    assert_eq!(truncate_to_width("❤️❤️❤️", 5), "❤️❤️");
+    assert_eq!(truncate_to_width("a👨‍👩‍👧b", 2), "a");
+    assert_eq!(truncate_to_width("e\u{301}e\u{301}", 1), "e\u{301}");
+    // keeps the escape sequences that reset the style
+    assert_eq!(
+      truncate_to_width("\x1b[32mhello\x1b[0m", 2),
+      "\x1b[32mhe\x1b[0m"
+    );
+    // the text after the escape sequences is cut off too
+    assert_eq!(truncate_to_width("日本語\x1b[0ma", 5), "日本\x1b[0m");
+  }
+
+  #[test]
+  fn should_truncate_with_ellipsis() {
+    use Ellipsis::*;
+    assert_eq!(truncate_with_ellipsis("hello", 5, Start), "hello");
+    assert_eq!(truncate_with_ellipsis("hello", 4, Start), "…llo");
+    assert_eq!(truncate_with_ellipsis("hello", 4, Middle), "he…o");
+    assert_eq!(truncate_with_ellipsis("hello", 4, End), "hel…");
+    assert_eq!(truncate_with_ellipsis("hello", 1, Middle), "…");
+    assert_eq!(truncate_with_ellipsis("hello", 0, End), "");
+    assert_eq!(
+      truncate_with_ellipsis("src/util/draw_thread/mod.rs", 16, Middle),
+      "src/util…/mod.rs"
+    );
+    // doesn't split a wide character to fill the columns
+    assert_eq!(truncate_with_ellipsis("日本語です", 6, Start), "…です");
+    assert_eq!(truncate_with_ellipsis("日本語です", 6, Middle), "日…す");
+    assert_eq!(truncate_with_ellipsis("日本語です", 6, End), "日本…");
+    assert_eq!(truncate_with_ellipsis("日本語x", 6, End), "日本…");
+    // or joined characters
+    assert_eq!(truncate_with_ellipsis("a👨‍👩‍👧b👨‍👩‍👧", 3, End), "a…");
+    assert_eq!(truncate_with_ellipsis("👨‍👩‍👧ab👨‍👩‍👧", 4, Start), "…b👨‍👩‍👧");
+    // keeps the escape sequences
+    assert_eq!(
+      truncate_with_ellipsis("\x1b[32mhello\x1b[0m", 3, Middle),
+      "\x1b[32mh…o\x1b[0m"
+    );
+  }
+
+  #[test]
+  fn should_break_at_width() {
+    assert_eq!(break_at_width("hello", 10), "hello");
+    assert_eq!(
+      break_at_width("the quick brown fox\njumps", 10),
+      "the quick \nbrown fox\njumps"
+    );
+    assert_eq!(break_at_width("日本語", 5), "日本\n語");
+  }
+
+  #[test]
+  fn should_wrap_to_width() {
+    assert_eq!(wrap_to_width("hello", 10), "hello");
+    assert_eq!(
+      wrap_to_width("the quick brown fox jumps over the lazy dog", 10),
+      "the quick\nbrown fox\njumps over\nthe lazy\ndog"
+    );
+    // breaks words longer than the width
+    assert_eq!(
+      wrap_to_width("download https://deno.land/x/mod.ts now", 10),
+      "download\nhttps://de\nno.land/x/\nmod.ts now"
+    );
+    // keeps existing lines
+    assert_eq!(wrap_to_width("a b\nc d", 1), "a\nb\nc\nd");
+    // unicode width aware
+    assert_eq!(wrap_to_width("日本語 テキスト", 6), "日本語\nテキス\nト");
+    assert_eq!(wrap_to_width("❤️❤️❤️", 4), "❤️❤️\n❤️");
+    assert_eq!(
+      wrap_to_width("\x1b[32mgreen text\x1b[0m", 5),
+      "\x1b[32mgreen\ntext\x1b[0m"
+    );
+  }
+
+  #[test]
+  fn should_align_columns() {
+    let mut texts = vec![
+      "name\tstatus\tpct".to_string(),
+      "a\t\x1b[32mdone\x1b[0m\t100%\nno columns".to_string(),
+      "longer name\tdownloading".to_string(),
+      "plain".to_string(),
+    ];
+    align_columns(&mut texts);
+    assert_eq!(
+      texts,
+      vec![
+        "name         status       pct".to_string(),
+        "a            \x1b[32mdone\x1b[0m         100%\nno columns".to_string(),
+        "longer name  downloading".to_string(),
+        "plain".to_string(),
+      ]
+    );
+  }
+}
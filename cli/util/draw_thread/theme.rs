@@ -0,0 +1,118 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::fmt::Display;
+
+use deno_terminal::colors;
+
+/// A color used by the built-in renderers.
+// the palette of the themes the embedders define
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeColor {
+  /// Emits no color.
+  None,
+  Cyan,
+  Gray,
+  Green,
+  IntenseBlue,
+  Magenta,
+  Red,
+  Yellow,
+  /// A 24-bit color.
+  Rgb(u8, u8, u8),
+}
+
+impl ThemeColor {
+  /// Styles the text with this color. No color is emitted when
+  /// colors are disabled, such as via `NO_COLOR`.
+  pub fn paint(&self, text: impl Display) -> String {
+    match self {
+      ThemeColor::None => text.to_string(),
+      ThemeColor::Cyan => colors::cyan(text).to_string(),
+      ThemeColor::Gray => colors::gray(text).to_string(),
+      ThemeColor::Green => colors::green(text).to_string(),
+      ThemeColor::IntenseBlue => colors::intense_blue(text).to_string(),
+      ThemeColor::Magenta => colors::magenta(text).to_string(),
+      ThemeColor::Red => colors::red(text).to_string(),
+      ThemeColor::Yellow => colors::yellow(text).to_string(),
+      ThemeColor::Rgb(r, g, b) => {
+        if colors::use_color() {
+          format!("\x1b[38;2;{};{};{}m{}\x1b[39m", r, g, b, text)
+        } else {
+          text.to_string()
+        }
+      }
+    }
+  }
+}
+
+/// Colors used by the built-in progress renderers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+  /// Filled portion of a progress bar.
+  pub bar_filled: ThemeColor,
+  /// Empty portion of a progress bar.
+  pub bar_empty: ThemeColor,
+  pub spinner: ThemeColor,
+  /// The label describing the operation, such as "Download".
+  pub label: ThemeColor,
+  /// The numeric progress, such as the percentage or counts.
+  pub percent: ThemeColor,
+  /// A progress bar that completed successfully.
+  pub success: ThemeColor,
+  /// A progress bar that failed.
+  pub failure: ThemeColor,
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    Self {
+      bar_filled: ThemeColor::Cyan,
+      bar_empty: ThemeColor::IntenseBlue,
+      spinner: ThemeColor::Cyan,
+      label: ThemeColor::Green,
+      percent: ThemeColor::Gray,
+      success: ThemeColor::Green,
+      failure: ThemeColor::Red,
+    }
+  }
+}
+
+impl Theme {
+  /// A theme that emits no color.
+  pub fn plain() -> Self {
+    Self {
+      bar_filled: ThemeColor::None,
+      bar_empty: ThemeColor::None,
+      spinner: ThemeColor::None,
+      label: ThemeColor::None,
+      percent: ThemeColor::None,
+      success: ThemeColor::None,
+      failure: ThemeColor::None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn plain_theme_emits_no_color() {
+    let theme = Theme::plain();
+    assert_eq!(theme.bar_filled.paint("###"), "###");
+    assert_eq!(theme.label.paint("Download"), "Download");
+  }
+
+  #[test]
+  fn paints_rgb_color() {
+    let text = ThemeColor::Rgb(1, 2, 3).paint("text");
+    if colors::use_color() {
+      assert_eq!(text, "\x1b[38;2;1;2;3mtext\x1b[39m");
+    } else {
+      assert_eq!(text, "text");
+    }
+  }
+}
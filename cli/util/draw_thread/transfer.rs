@@ -0,0 +1,262 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::sync::Arc;
+
+use deno_core::parking_lot::Mutex;
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::progress::ProgressStatus;
+use super::text::truncate_to_width;
+use super::DrawThreadRenderer;
+use crate::util::display::human_download_size;
+
+/// Width of the bar displayed for each active file.
+const FILE_BAR_WIDTH: usize = 10;
+
+/// Identifies a file of a `MultiTransferRenderer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransferFileId(usize);
+
+#[derive(Debug)]
+struct TransferFile {
+  name: String,
+  bytes: u64,
+  total: u64,
+  status: ProgressStatus,
+}
+
+#[derive(Debug, Default)]
+struct TransferState {
+  files: Vec<TransferFile>,
+}
+
+fn render_bar(bytes: u64, total: u64, width: usize) -> String {
+  let filled = if total == 0 {
+    0
+  } else {
+    ((width as u128 * bytes.min(total) as u128) / total as u128) as usize
+  };
+  format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+fn percent(bytes: u64, total: u64) -> u64 {
+  if total == 0 {
+    0
+  } else {
+    (bytes.min(total) as u128 * 100 / total as u128) as u64
+  }
+}
+
+/// Renders the transfer of multiple files, such as the downloads of a
+/// package's tarballs, with the overall progress on the first line
+/// followed by the files still being transferred. Clones share the
+/// same files, so they can be updated while the renderer is drawn.
+///
+/// Files drop off the list once they're finished, but still count
+/// towards the overall progress.
+#[derive(Debug, Clone)]
+pub struct MultiTransferRenderer {
+  state: Arc<Mutex<TransferState>>,
+  max_rows: usize,
+}
+
+impl Default for MultiTransferRenderer {
+  fn default() -> Self {
+    Self {
+      state: Default::default(),
+      max_rows: 5,
+    }
+  }
+}
+
+// updated by the callers that transfer files
+#[allow(dead_code)]
+impl MultiTransferRenderer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the maximum number of rows listing the active files. Defaults
+  /// to 5. When there are more active files, the last row displays how
+  /// many files aren't listed.
+  pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+    self.max_rows = max_rows;
+    self
+  }
+
+  /// Adds a file of the provided total size in bytes.
+  pub fn add_file(
+    &self,
+    name: impl Into<String>,
+    total: u64,
+  ) -> TransferFileId {
+    let mut state = self.state.lock();
+    state.files.push(TransferFile {
+      name: name.into(),
+      bytes: 0,
+      total,
+      status: ProgressStatus::InProgress,
+    });
+    TransferFileId(state.files.len() - 1)
+  }
+
+  /// Sets the number of bytes of the file that were transferred.
+  pub fn set_bytes(&self, id: TransferFileId, bytes: u64) {
+    self.state.lock().files[id.0].bytes = bytes;
+  }
+
+  /// Adds to the number of bytes of the file that were transferred.
+  pub fn inc_bytes(&self, id: TransferFileId, bytes: u64) {
+    let mut state = self.state.lock();
+    let file = &mut state.files[id.0];
+    file.bytes = file.bytes.saturating_add(bytes);
+  }
+
+  /// Marks the file as transferred, which removes it from the list.
+  pub fn finish(&self, id: TransferFileId) {
+    let mut state = self.state.lock();
+    let file = &mut state.files[id.0];
+    file.bytes = file.total;
+    file.status = ProgressStatus::Completed;
+  }
+
+  /// Marks the file as failed, which removes it from the list.
+  pub fn fail(&self, id: TransferFileId) {
+    self.state.lock().files[id.0].status = ProgressStatus::Failed;
+  }
+}
+
+impl DrawThreadRenderer for MultiTransferRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    let state = self.state.lock();
+    if state.files.is_empty() {
+      return String::new();
+    }
+    let cols = size.cols as usize;
+    let bytes = state.files.iter().map(|file| file.bytes).sum::<u64>();
+    let total = state.files.iter().map(|file| file.total).sum::<u64>();
+    let finished_count = state
+      .files
+      .iter()
+      .filter(|file| file.status != ProgressStatus::InProgress)
+      .count();
+    let prefix = format!("{}/{} files ", finished_count, state.files.len());
+    let suffix = format!(
+      " {}/{}",
+      human_download_size(bytes, total),
+      human_download_size(total, total)
+    );
+    // the brackets of the bar take two columns
+    let bar_width = cols.saturating_sub(prefix.len() + suffix.len() + 2);
+    let mut lines = vec![truncate_to_width(
+      &format!(
+        "{}{}{}",
+        prefix,
+        render_bar(bytes, total, bar_width),
+        suffix
+      ),
+      cols,
+    )];
+
+    let active_files = state
+      .files
+      .iter()
+      .filter(|file| file.status == ProgressStatus::InProgress)
+      .collect::<Vec<_>>();
+    let listed_count = if active_files.len() > self.max_rows {
+      self.max_rows.saturating_sub(1)
+    } else {
+      active_files.len()
+    };
+    for file in &active_files[..listed_count] {
+      lines.push(truncate_to_width(
+        &format!(
+          "  {} {:>3}% {}",
+          render_bar(file.bytes, file.total, FILE_BAR_WIDTH),
+          percent(file.bytes, file.total),
+          file.name
+        ),
+        cols,
+      ));
+    }
+    if listed_count < active_files.len() && self.max_rows > 0 {
+      lines.push(truncate_to_width(
+        &format!("  +{} more", active_files.len() - listed_count),
+        cols,
+      ));
+    }
+    lines.join("\n")
+  }
+
+  fn progress(&self) -> Option<f64> {
+    let state = self.state.lock();
+    let total = state.files.iter().map(|file| file.total).sum::<u64>();
+    if total == 0 {
+      return None;
+    }
+    let bytes = state
+      .files
+      .iter()
+      .map(|file| file.bytes.min(file.total))
+      .sum::<u64>();
+    Some(bytes as f64 / total as f64)
+  }
+
+  fn is_failed(&self) -> bool {
+    self
+      .state
+      .lock()
+      .files
+      .iter()
+      .any(|file| file.status == ProgressStatus::Failed)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const SIZE: ConsoleSize = ConsoleSize { cols: 40, rows: 10 };
+
+  #[test]
+  fn renders_active_files() {
+    let renderer = MultiTransferRenderer::new().with_max_rows(2);
+    assert_eq!(renderer.render(&SIZE), "");
+    assert_eq!(renderer.progress(), None);
+    let mod_ts = renderer.add_file("mod.ts", 1024);
+    let main_ts = renderer.add_file("main.ts", 2048);
+    let deps = renderer.add_file("a_very_long_dependency_name.ts", 1024);
+    renderer.set_bytes(mod_ts, 512);
+    renderer.inc_bytes(main_ts, 512);
+    renderer.inc_bytes(main_ts, 512);
+    assert_eq!(
+      renderer.render(&SIZE),
+      concat!(
+        "0/3 files [####--------] 1.50KiB/4.00KiB\n",
+        "  [#####-----]  50% mod.ts\n",
+        "  +2 more",
+      )
+    );
+    assert_eq!(renderer.progress(), Some(0.375));
+
+    renderer.finish(mod_ts);
+    assert_eq!(
+      renderer.render(&SIZE),
+      concat!(
+        "1/3 files [######------] 2.00KiB/4.00KiB\n",
+        "  [#####-----]  50% main.ts\n",
+        "  [----------]   0% a_very_long_dependen",
+      )
+    );
+
+    renderer.finish(main_ts);
+    renderer.fail(deps);
+    assert_eq!(
+      renderer.render(&SIZE),
+      "3/3 files [#########---] 3.00KiB/4.00KiB"
+    );
+    assert!(renderer.is_failed());
+  }
+}
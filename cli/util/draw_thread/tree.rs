@@ -0,0 +1,171 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::sync::Arc;
+
+use deno_core::parking_lot::Mutex;
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::text::truncate_to_width;
+use super::DrawThreadRenderer;
+
+/// Identifies a node of a `TreeRenderer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TreeNodeId(usize);
+
+#[derive(Debug)]
+struct TreeNode {
+  label: String,
+  children: Vec<usize>,
+  completed: bool,
+}
+
+#[derive(Debug, Default)]
+struct TreeState {
+  nodes: Vec<TreeNode>,
+  roots: Vec<usize>,
+}
+
+impl TreeState {
+  fn push(&mut self, label: String) -> usize {
+    self.nodes.push(TreeNode {
+      label,
+      children: Vec::new(),
+      completed: false,
+    });
+    self.nodes.len() - 1
+  }
+
+  fn render_children(
+    &self,
+    children: &[usize],
+    prefix: &str,
+    cols: usize,
+    lines: &mut Vec<String>,
+  ) {
+    for (i, index) in children.iter().enumerate() {
+      let node = &self.nodes[*index];
+      let is_last = i == children.len() - 1;
+      let connector = if is_last { "└─ " } else { "├─ " };
+      lines.push(truncate_to_width(
+        &format!("{}{}{}", prefix, connector, node.label),
+        cols,
+      ));
+      if !node.completed {
+        let prefix =
+          format!("{}{}", prefix, if is_last { "   " } else { "│  " });
+        self.render_children(&node.children, &prefix, cols, lines);
+      }
+    }
+  }
+}
+
+/// Renders tasks and their sub-tasks as a tree, such as the tasks of a
+/// build and their dependencies. Clones share the same tree, so the
+/// tasks can be updated while the renderer is drawn.
+///
+/// Once a node is completed, its subtree is collapsed so only the
+/// node itself is displayed.
+#[derive(Debug, Clone, Default)]
+pub struct TreeRenderer {
+  state: Arc<Mutex<TreeState>>,
+}
+
+// the embedders build the tree of tasks
+#[allow(dead_code)]
+impl TreeRenderer {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a node at the top level of the tree.
+  pub fn add_root(&self, label: impl Into<String>) -> TreeNodeId {
+    let mut state = self.state.lock();
+    let index = state.push(label.into());
+    state.roots.push(index);
+    TreeNodeId(index)
+  }
+
+  /// Adds a node below the parent, after its other children.
+  pub fn add_child(
+    &self,
+    parent: TreeNodeId,
+    label: impl Into<String>,
+  ) -> TreeNodeId {
+    let mut state = self.state.lock();
+    let index = state.push(label.into());
+    state.nodes[parent.0].children.push(index);
+    TreeNodeId(index)
+  }
+
+  pub fn set_label(&self, id: TreeNodeId, label: impl Into<String>) {
+    self.state.lock().nodes[id.0].label = label.into();
+  }
+
+  /// Marks the node as completed, which collapses its subtree.
+  pub fn complete(&self, id: TreeNodeId) {
+    self.state.lock().nodes[id.0].completed = true;
+  }
+}
+
+impl DrawThreadRenderer for TreeRenderer {
+  fn render(&self, size: &ConsoleSize) -> String {
+    let state = self.state.lock();
+    let cols = size.cols as usize;
+    let mut lines = Vec::new();
+    for index in &state.roots {
+      let node = &state.nodes[*index];
+      lines.push(truncate_to_width(&node.label, cols));
+      if !node.completed {
+        state.render_children(&node.children, "", cols, &mut lines);
+      }
+    }
+    lines.join("\n")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const SIZE: ConsoleSize = ConsoleSize { cols: 20, rows: 10 };
+
+  #[test]
+  fn renders_tree() {
+    let tree = TreeRenderer::new();
+    assert_eq!(tree.render(&SIZE), "");
+    let build = tree.add_root("build");
+    let compile = tree.add_child(build, "compile");
+    tree.add_child(compile, "main.ts");
+    tree.add_child(compile, "mod.ts");
+    let bundle = tree.add_child(build, "bundle");
+    tree.add_child(bundle, "output.js");
+    assert_eq!(
+      tree.render(&SIZE),
+      concat!(
+        "build\n",
+        "├─ compile\n",
+        "│  ├─ main.ts\n",
+        "│  └─ mod.ts\n",
+        "└─ bundle\n",
+        "   └─ output.js",
+      )
+    );
+
+    tree.complete(compile);
+    tree.set_label(bundle, "bundling dependencies");
+    assert_eq!(
+      tree.render(&SIZE),
+      concat!(
+        "build\n",
+        "├─ compile\n",
+        "└─ bundling dependen\n",
+        "   └─ output.js",
+      )
+    );
+
+    tree.complete(build);
+    assert_eq!(tree.render(&SIZE), "build");
+  }
+}
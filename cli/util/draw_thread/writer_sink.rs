@@ -0,0 +1,78 @@
+// Copyright 2018-2025 the Deno authors. MIT license.
+
+use std::borrow::Cow;
+use std::io::Write;
+
+use console_static_text::ConsoleStaticText;
+use deno_runtime::ops::tty::ConsoleSize;
+
+use super::text::break_at_width;
+use super::text::display_width;
+use super::to_static_text_size;
+use super::FrameSink;
+
+/// A sink that draws each frame into a writer of a known size, such as
+/// the pseudo terminal of a subprocess, rather than the local terminal.
+///
+/// The frames are rendered at the sink's size and drawn with their own
+/// cursor movements, so they replace the previous frame in the writer
+/// the same way they do in the local terminal.
+pub struct WriterSink {
+  writer: Box<dyn Write + Send>,
+  size: ConsoleSize,
+  static_text: ConsoleStaticText,
+}
+
+impl std::fmt::Debug for WriterSink {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("WriterSink")
+      .field("size", &self.size)
+      .finish_non_exhaustive()
+  }
+}
+
+impl WriterSink {
+  pub fn new(writer: Box<dyn Write + Send>, size: ConsoleSize) -> Self {
+    Self {
+      writer,
+      size,
+      // the size is always provided when rendering
+      static_text: ConsoleStaticText::new(|| to_static_text_size(None)),
+    }
+  }
+
+  fn write(&mut self, text: &str) {
+    let _ = self.writer.write_all(text.as_bytes());
+    let _ = self.writer.flush();
+  }
+}
+
+impl FrameSink for WriterSink {
+  fn write_frame(&mut self, text: &str, size: &ConsoleSize) {
+    // break the lines at the column boundary rather than leaving it to the
+    // writer's terminal so that the number of rows is known exactly
+    let cols = size.cols as usize;
+    let text = if text.split('\n').any(|line| display_width(line) > cols) {
+      Cow::Owned(break_at_width(text, cols))
+    } else {
+      Cow::Borrowed(text)
+    };
+    let rendered = self
+      .static_text
+      .render_with_size(&text, to_static_text_size(Some(*size)));
+    if let Some(rendered) = rendered {
+      self.write(&rendered);
+    }
+  }
+
+  fn clear(&mut self) {
+    let size = to_static_text_size(Some(self.size));
+    if let Some(text) = self.static_text.render_clear_with_size(size) {
+      self.write(&text);
+    }
+  }
+
+  fn size(&self) -> Option<ConsoleSize> {
+    Some(self.size)
+  }
+}
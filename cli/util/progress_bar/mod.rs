@@ -11,6 +11,7 @@ use deno_runtime::ops::tty::ConsoleSize;
 use self::renderer::ProgressBarRenderer;
 use self::renderer::ProgressData;
 use self::renderer::ProgressDataDisplayEntry;
+use super::draw_thread::theme::Theme;
 use super::draw_thread::DrawThread;
 use super::draw_thread::DrawThreadGuard;
 use super::draw_thread::DrawThreadRenderer;
@@ -136,6 +137,17 @@ struct InternalState {
   entries: Vec<Arc<ProgressBarEntry>>,
 }
 
+impl InternalState {
+  fn percent_done(&self) -> f64 {
+    let mut total_percent_sum = 0f64;
+    for entry in &self.entries {
+      total_percent_sum += entry.percent();
+    }
+    total_percent_sum += (self.total_entries - self.entries.len()) as f64;
+    total_percent_sum / (self.total_entries as f64)
+  }
+}
+
 #[derive(Clone, Debug)]
 struct ProgressBarInner {
   state: Arc<Mutex<InternalState>>,
@@ -246,19 +258,20 @@ impl DrawThreadRenderer for ProgressBarInner {
         pending_entries: state.entries.len(),
         total_entries: state.total_entries,
         display_entries,
-        percent_done: {
-          let mut total_percent_sum = 0f64;
-          for entry in &state.entries {
-            total_percent_sum += entry.percent();
-          }
-          total_percent_sum +=
-            (state.total_entries - state.entries.len()) as f64;
-          total_percent_sum / (state.total_entries as f64)
-        },
+        percent_done: state.percent_done(),
       }
     };
     self.renderer.render(data)
   }
+
+  fn progress(&self) -> Option<f64> {
+    let state = self.state.lock();
+    if state.entries.is_empty() {
+      None
+    } else {
+      Some(state.percent_done())
+    }
+  }
 }
 
 #[derive(Clone, Debug)]
@@ -295,11 +308,15 @@ impl ProgressBar {
         ProgressBarStyle::DownloadBars => {
           Arc::new(renderer::BarProgressBarRenderer {
             display_human_download_size: true,
+            theme: Theme::default(),
+            chars: Default::default(),
           })
         }
         ProgressBarStyle::ProgressBars => {
           Arc::new(renderer::BarProgressBarRenderer {
             display_human_download_size: false,
+            theme: Theme::default(),
+            chars: Default::default(),
           })
         }
         ProgressBarStyle::TextOnly => {
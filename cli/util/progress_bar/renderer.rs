@@ -9,6 +9,10 @@ use deno_terminal::colors;
 
 use super::ProgressMessagePrompt;
 use crate::util::display::human_download_size;
+use crate::util::draw_thread::progress::SPINNER_CHARS;
+use crate::util::draw_thread::text::display_width;
+use crate::util::draw_thread::theme::Theme;
+use crate::util::draw_thread::DrawThread;
 
 #[derive(Clone)]
 pub struct ProgressDataDisplayEntry {
@@ -32,10 +36,136 @@ pub trait ProgressBarRenderer: Send + Sync + std::fmt::Debug {
   fn render(&self, data: ProgressData) -> String;
 }
 
+/// The characters a progress bar is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarChars {
+  /// Repeated for the completed portion of the bar.
+  pub filled: &'static str,
+  /// Repeated for the remaining portion of the bar.
+  pub empty: &'static str,
+  /// Drawn at the boundary of the completed portion, such as an arrow.
+  pub head: Option<&'static str>,
+  /// Drawn on the left and right of the bar.
+  pub edges: (&'static str, &'static str),
+  /// Drawn in the cell after the completed portion for the fraction of
+  /// the cell that's completed, from the least to the most filled. Not
+  /// used with a head.
+  pub partials: &'static [&'static str],
+}
+
+impl BarChars {
+  /// `[####>-----]`
+  pub const ASCII: BarChars = BarChars {
+    filled: "#",
+    empty: "-",
+    head: Some(">"),
+    edges: ("[", "]"),
+    partials: &[],
+  };
+
+  /// `[█████▌░░░░]`
+  pub const UNICODE: BarChars = BarChars {
+    filled: "█",
+    empty: "░",
+    head: None,
+    edges: ("[", "]"),
+    partials: &["▏", "▎", "▍", "▌", "▋", "▊", "▉"],
+  };
+
+  pub fn new(filled: &'static str, empty: &'static str) -> Self {
+    Self {
+      filled,
+      empty,
+      head: None,
+      edges: ("[", "]"),
+      partials: &[],
+    }
+  }
+
+  pub fn with_head(mut self, head: &'static str) -> Self {
+    self.head = Some(head);
+    self
+  }
+
+  pub fn with_edges(mut self, left: &'static str, right: &'static str) -> Self {
+    self.edges = (left, right);
+    self
+  }
+
+  pub fn with_partials(mut self, partials: &'static [&'static str]) -> Self {
+    self.partials = partials;
+    self
+  }
+
+  /// Renders the bar, excluding the edges, so that it occupies
+  /// `total_cols` columns.
+  fn render(&self, theme: &Theme, total_cols: usize, percent: f64) -> String {
+    fn repeat_to_width(text: &str, cols: usize) -> (String, usize) {
+      let width = display_width(text).max(1);
+      let count = cols / width;
+      (text.repeat(count), count * width)
+    }
+
+    let completed_cols = (total_cols as f64 * percent).floor() as usize;
+    if completed_cols >= total_cols {
+      let (filled, filled_cols) = repeat_to_width(self.filled, total_cols);
+      let padding = " ".repeat(total_cols - filled_cols);
+      return theme.success.paint(format!("{}{}", filled, padding));
+    }
+    let head = self
+      .head
+      .filter(|head| completed_cols >= display_width(head).max(1));
+    let head_cols = head.map(display_width).unwrap_or(0);
+    let (filled, filled_cols) =
+      repeat_to_width(self.filled, completed_cols - head_cols);
+    let partial = if self.head.is_none() && !self.partials.is_empty() {
+      let fraction = total_cols as f64 * percent - completed_cols as f64;
+      let index = (fraction * (self.partials.len() + 1) as f64) as usize;
+      index
+        .checked_sub(1)
+        .map(|index| self.partials[index.min(self.partials.len() - 1)])
+    } else {
+      None
+    };
+    let head = head.or(partial);
+    let used_cols = filled_cols + head.map(display_width).unwrap_or(0);
+    let (empty, empty_cols) =
+      repeat_to_width(self.empty, total_cols - used_cols);
+    let mut text = String::new();
+    if used_cols > 0 {
+      text.push_str(&theme.bar_filled.paint(format!(
+        "{}{}",
+        filled,
+        head.unwrap_or_default()
+      )));
+    }
+    text.push_str(&theme.bar_empty.paint(format!(
+      "{}{}",
+      empty,
+      " ".repeat(total_cols - used_cols - empty_cols)
+    )));
+    text
+  }
+}
+
+impl Default for BarChars {
+  /// Uses unicode blocks unless the terminal's locale doesn't
+  /// support unicode, which falls back to ASCII.
+  fn default() -> Self {
+    if DrawThread::terminal_capabilities().unicode {
+      Self::UNICODE
+    } else {
+      Self::ASCII
+    }
+  }
+}
+
 /// Indicatif style progress bar.
 #[derive(Debug)]
 pub struct BarProgressBarRenderer {
   pub display_human_download_size: bool,
+  pub theme: Theme,
+  pub chars: BarChars,
 }
 
 impl ProgressBarRenderer for BarProgressBarRenderer {
@@ -84,7 +214,7 @@ impl ProgressBarRenderer for BarProgressBarRenderer {
       writeln!(
         &mut text,
         "{} {}{}",
-        colors::green("Download"),
+        self.theme.label.paint("Download"),
         display_entry.message,
         bytes_text,
       )
@@ -92,37 +222,32 @@ impl ProgressBarRenderer for BarProgressBarRenderer {
     }
     text.push_str(&elapsed_text);
     let max_width = (data.terminal_width as i32 - 5).clamp(10, 75) as usize;
-    let same_line_text_width =
-      elapsed_text.len() + total_text_max_width + bytes_text_max_width + 3; // space, open and close brace
+    let (left_edge, right_edge) = self.chars.edges;
+    let same_line_text_width = elapsed_text.len()
+      + total_text_max_width
+      + bytes_text_max_width
+      + 1 // space
+      + display_width(left_edge)
+      + display_width(right_edge);
     let total_bars = if same_line_text_width > max_width {
       1
     } else {
       max_width - same_line_text_width
     };
-    let completed_bars =
-      (total_bars as f64 * data.percent_done).floor() as usize;
-    text.push_str(" [");
-    if completed_bars != total_bars {
-      if completed_bars > 0 {
-        text.push_str(&format!(
-          "{}",
-          colors::cyan(format!("{}{}", "#".repeat(completed_bars - 1), ">"))
-        ))
-      }
-      text.push_str(&format!(
-        "{}",
-        colors::intense_blue("-".repeat(total_bars - completed_bars))
-      ))
-    } else {
-      text.push_str(&format!("{}", colors::cyan("#".repeat(completed_bars))))
-    }
-    text.push(']');
+    text.push(' ');
+    text.push_str(left_edge);
+    text.push_str(&self.chars.render(
+      &self.theme,
+      total_bars,
+      data.percent_done,
+    ));
+    text.push_str(right_edge);
 
     // suffix
     if display_entry.message.is_empty() {
-      text.push_str(&colors::gray(bytes_text).to_string());
+      text.push_str(&self.theme.percent.paint(bytes_text));
     }
-    text.push_str(&colors::gray(total_text).to_string());
+    text.push_str(&self.theme.percent.paint(total_text));
 
     text
   }
@@ -143,7 +268,6 @@ impl Default for TextOnlyProgressBarRenderer {
   }
 }
 
-const SPINNER_CHARS: [&str; 8] = ["⣷", "⣯", "⣟", "⡿", "⢿", "⣻", "⣽", "⣾"];
 impl ProgressBarRenderer for TextOnlyProgressBarRenderer {
   fn render(&self, data: ProgressData) -> String {
     let last_tick = {
@@ -261,6 +385,8 @@ mod test {
   fn should_render_bar_progress() {
     let renderer = BarProgressBarRenderer {
       display_human_download_size: true,
+      theme: Theme::default(),
+      chars: BarChars::ASCII,
     };
     let mut data = ProgressData {
       display_entries: vec![ProgressDataDisplayEntry {
@@ -316,6 +442,63 @@ mod test {
     assert_eq!(text, "[00:01] [###################################]",);
   }
 
+  #[test]
+  fn should_render_bar_chars() {
+    let theme = Theme::plain();
+    let render =
+      |chars: BarChars, percent: f64| chars.render(&theme, 10, percent);
+    assert_eq!(render(BarChars::ASCII, 0.5), "####>-----");
+    assert_eq!(render(BarChars::ASCII, 0.0), "----------");
+    assert_eq!(render(BarChars::ASCII, 0.05), "----------");
+    assert_eq!(render(BarChars::ASCII, 1.0), "##########");
+    assert_eq!(render(BarChars::UNICODE, 0.5), "█████░░░░░");
+    assert_eq!(render(BarChars::UNICODE, 0.55), "█████▌░░░░");
+    assert_eq!(
+      render(BarChars::new("=", " ").with_head(">"), 0.5),
+      "====>     "
+    );
+    // uses the display width of wide glyphs
+    assert_eq!(render(BarChars::new("ＯＯ", "・"), 0.5), "ＯＯ・・・");
+    assert_eq!(render(BarChars::new("ＯＯ", "・"), 0.3), "・・・・・");
+    assert_eq!(render(BarChars::new("ＯＯ", "・"), 1.0), "ＯＯＯＯ  ");
+
+    let renderer = BarProgressBarRenderer {
+      display_human_download_size: false,
+      theme,
+      chars: BarChars::UNICODE.with_edges("|", "|"),
+    };
+    let data = ProgressData {
+      display_entries: vec![ProgressDataDisplayEntry {
+        prompt: ProgressMessagePrompt::Download,
+        message: "".to_string(),
+        position: 0,
+        total_size: 0,
+      }],
+      duration: Duration::from_secs(1),
+      pending_entries: 0,
+      total_entries: 1,
+      percent_done: 0.5,
+      terminal_width: 25,
+    };
+    assert_eq!(renderer.render(data), "[00:01] |█████░░░░░|");
+  }
+
+  #[test]
+  fn should_render_partial_cells() {
+    let theme = Theme::plain();
+    let render = |percent: f64| BarChars::UNICODE.render(&theme, 2, percent);
+    // each sixteenth is an eighth of the first cell
+    let expected = ["░░", "▏░", "▎░", "▍░", "▌░", "▋░", "▊░", "▉░", "█░"];
+    for (eighths, expected) in expected.iter().enumerate() {
+      assert_eq!(render(eighths as f64 / 16.0), *expected);
+    }
+    assert_eq!(render(0.5625), "█▏");
+    assert_eq!(render(1.0), "██");
+    // falls back to whole cells without partials
+    assert_eq!(BarChars::ASCII.render(&theme, 2, 0.4375), "--");
+    assert_eq!(BarChars::new("#", "-").render(&theme, 2, 0.4375), "--");
+  }
+
   #[test]
   fn should_render_text_only_progress() {
     let renderer = TextOnlyProgressBarRenderer::default();